@@ -0,0 +1,1731 @@
+// Compiles the `serde_json::Value` AST `parser::parse` produces into the
+// bootstrap interpreter's line-oriented bytecode text format (see
+// `Compiler::to_string`), the same format `crate::parse_bytecode` reads back
+// at the other end — in `build.rs` at build time for `interpreter.omg`, and
+// in `crate::repl` at REPL-input time for the session's accumulated source.
+
+use std::collections::{HashMap, HashSet};
+
+use serde_json::Value;
+
+/// Single source of truth for the instruction set: each opcode's `Op`
+/// variant, its mnemonic (what `to_string`/`disassemble` write and
+/// `main::parse_bytecode` parses back), and its operand shape (what the
+/// binary encoder/decoder and `disassemble` need to know how many bytes to
+/// read). Variants are listed in wire-byte order — a variant's position in
+/// this list *is* its one-byte encoding in `to_bytes`/`load_bytecode`, so
+/// reordering one is a breaking change to any `.bc` binary already written.
+/// Previously this information was spread across a bare `&str` op field, the
+/// `OPCODES` name table, and a separate `operand_shape` match, so a typo'd
+/// mnemonic (`"JMP"` for `"JUMP"`) compiled silently into a no-op rather
+/// than a build error; adding a new opcode is now a one-line addition here
+/// that automatically flows into every one of `Op`'s methods below, with no
+/// other call site to keep in sync.
+macro_rules! ops {
+    ($($variant:ident => $name:literal, $shape:ident;)+) => {
+        #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+        pub enum Op {
+            $($variant),+
+        }
+
+        impl Op {
+            pub fn name(self) -> &'static str {
+                match self {
+                    $(Op::$variant => $name,)+
+                }
+            }
+
+            pub fn shape(self) -> OperandShape {
+                match self {
+                    $(Op::$variant => OperandShape::$shape,)+
+                }
+            }
+
+            fn from_name(name: &str) -> Option<Op> {
+                match name {
+                    $($name => Some(Op::$variant),)+
+                    _ => None,
+                }
+            }
+
+            /// This opcode's one-byte wire encoding — its position below.
+            pub fn byte(self) -> u8 {
+                const ALL: &[Op] = &[$(Op::$variant),+];
+                ALL.iter().position(|&o| o == self).unwrap() as u8
+            }
+
+            fn from_byte(byte: u8) -> Option<Op> {
+                const ALL: &[Op] = &[$(Op::$variant),+];
+                ALL.get(byte as usize).copied()
+            }
+        }
+    };
+}
+
+ops! {
+    PushInt => "PUSH_INT", Int;
+    PushFloat => "PUSH_FLOAT", Float;
+    PushStr => "PUSH_STR", Str;
+    PushBool => "PUSH_BOOL", Int;
+    PushNone => "PUSH_NONE", None;
+    BuildList => "BUILD_LIST", Int;
+    BuildDict => "BUILD_DICT", Int;
+    Load => "LOAD", Int;
+    Store => "STORE", Int;
+    Add => "ADD", None;
+    Sub => "SUB", None;
+    Mul => "MUL", None;
+    Div => "DIV", None;
+    Mod => "MOD", None;
+    Pow => "POW", None;
+    FloorDiv => "FLOORDIV", None;
+    Eq => "EQ", None;
+    Ne => "NE", None;
+    Lt => "LT", None;
+    Le => "LE", None;
+    Gt => "GT", None;
+    Ge => "GE", None;
+    BAnd => "BAND", None;
+    BOr => "BOR", None;
+    BXor => "BXOR", None;
+    Shl => "SHL", None;
+    Shr => "SHR", None;
+    And => "AND", None;
+    Or => "OR", None;
+    Not => "NOT", None;
+    Neg => "NEG", None;
+    Index => "INDEX", None;
+    Slice => "SLICE", None;
+    StoreIndex => "STORE_INDEX", None;
+    Attr => "ATTR", Int;
+    StoreAttr => "STORE_ATTR", Int;
+    Import => "IMPORT", None;
+    Assert => "ASSERT", None;
+    CallValue => "CALL_VALUE", Int;
+    Jump => "JUMP", Int;
+    JumpIfFalse => "JUMP_IF_FALSE", Int;
+    Call => "CALL", Int;
+    TCall => "TCALL", Int;
+    Builtin => "BUILTIN", Builtin;
+    Ret => "RET", None;
+    Emit => "EMIT", None;
+    Halt => "HALT", None;
+    Pop => "POP", None;
+    PushConst => "PUSH_CONST", Int;
+    Switch => "SWITCH", Switch;
+    MakeClosure => "MAKE_CLOSURE", Closure;
+    LoadUpvalue => "LOAD_UPVALUE", Int;
+}
+
+/// `true` if `arg`'s shape agrees with `shape` — the "operand validation"
+/// `emit`/`emit_placeholder` assert on every instruction they produce, so a
+/// mismatched `Op`/`Arg` pairing (e.g. emitting `Op::Switch` with an
+/// `Arg::Int`) panics at the point it's emitted instead of silently
+/// corrupting the encoded operand bytes downstream.
+fn arg_matches_shape(arg: &Option<Arg>, shape: OperandShape) -> bool {
+    match (arg, shape) {
+        (None, OperandShape::None) => true,
+        (Some(Arg::Int(_)), OperandShape::Int) => true,
+        (Some(Arg::Float(_)), OperandShape::Float) => true,
+        (Some(Arg::Str(_)), OperandShape::Str) => true,
+        (Some(Arg::Builtin(..)), OperandShape::Builtin) => true,
+        (Some(Arg::Switch(..)), OperandShape::Switch) => true,
+        (Some(Arg::Closure(..)), OperandShape::Closure) => true,
+        _ => false,
+    }
+}
+
+#[derive(Clone)]
+struct Instr {
+    op: Op,
+    arg: Option<Arg>,
+}
+
+/// An instruction operand. `Float`'s `PartialEq`/`Hash` compare the raw bit
+/// pattern rather than IEEE equality (so `NaN == NaN` for pooling purposes) —
+/// fine here since [`Compiler::intern`] only ever uses these to recognize
+/// "already emitted this exact literal", never to compare program values.
+#[derive(Clone)]
+pub enum Arg {
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Builtin(String, usize),
+    /// `(base, len)` for a `SWITCH` jump table — never interned (like
+    /// `Builtin`, it's instruction-embedded, not a poolable literal).
+    Switch(i64, usize),
+    /// `(function name, n_upvalues)` for `MAKE_CLOSURE` — never interned,
+    /// same reasoning as `Builtin`.
+    Closure(String, usize),
+}
+
+impl PartialEq for Arg {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Arg::Int(a), Arg::Int(b)) => a == b,
+            (Arg::Float(a), Arg::Float(b)) => a.to_bits() == b.to_bits(),
+            (Arg::Str(a), Arg::Str(b)) => a == b,
+            (Arg::Builtin(a, b), Arg::Builtin(c, d)) => a == c && b == d,
+            (Arg::Switch(a, b), Arg::Switch(c, d)) => a == c && b == d,
+            (Arg::Closure(a, b), Arg::Closure(c, d)) => a == c && b == d,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Arg {}
+
+impl std::hash::Hash for Arg {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        match self {
+            Arg::Int(i) => {
+                0u8.hash(state);
+                i.hash(state);
+            }
+            Arg::Float(f) => {
+                1u8.hash(state);
+                f.to_bits().hash(state);
+            }
+            Arg::Str(s) => {
+                2u8.hash(state);
+                s.hash(state);
+            }
+            Arg::Builtin(name, argc) => {
+                3u8.hash(state);
+                name.hash(state);
+                argc.hash(state);
+            }
+            Arg::Switch(base, len) => {
+                4u8.hash(state);
+                base.hash(state);
+                len.hash(state);
+            }
+            Arg::Closure(name, n_upvalues) => {
+                5u8.hash(state);
+                name.hash(state);
+                n_upvalues.hash(state);
+            }
+        }
+    }
+}
+
+/// Render one constant-pool entry as the `CONST <TYPE> <value>` line both
+/// `Compiler::to_string` and `main::parse_bytecode`'s `"CONST "` branch agree
+/// on — entries are written in pool order, so a line's position among the
+/// `CONST` lines is the index `PUSH_CONST`/`LOAD`/etc. operands refer to.
+fn format_const_line(c: &Arg) -> String {
+    match c {
+        Arg::Int(i) => format!("CONST INT {}", i),
+        Arg::Float(f) => format!("CONST FLOAT {:?}", f),
+        Arg::Str(s) => format!("CONST STR {}", serde_json::to_string(s).unwrap()),
+        Arg::Builtin(..) | Arg::Switch(..) | Arg::Closure(..) => unreachable!("never interned"),
+    }
+}
+
+/// Read back the trailing source-line number `parser::parse_statement`
+/// appends to every statement node, or `None` for a node that doesn't carry
+/// one — an expression node reached directly (shouldn't happen, `compile_stmt`
+/// is only ever called on statement nodes) or a malformed one.
+fn stmt_line(stmt: &Value) -> Option<u32> {
+    stmt.as_array()?.last()?.as_u64().map(|n| n as u32)
+}
+
+/// Remap `debug` entries through `new_idx` (old code index -> new code
+/// index, as built by `fold_constants`/`eliminate_dead_code`), preserving the
+/// "one entry per line change" RLE invariant: an entry is dropped if it maps
+/// to the same new index as the previous surviving entry (several old
+/// indices, each carrying their own debug entry, can collapse onto one
+/// surviving instruction — the earliest, in old-index order, wins) or
+/// repeats the previous entry's line.
+fn remap_debug(debug: &[(usize, u32)], new_idx: &[usize]) -> Vec<(usize, u32)> {
+    let mut out: Vec<(usize, u32)> = Vec::with_capacity(debug.len());
+    for &(idx, line) in debug {
+        let ni = new_idx[idx];
+        match out.last() {
+            Some(&(last_ni, last_line)) if last_ni == ni || last_line == line => {}
+            _ => out.push((ni, line)),
+        }
+    }
+    out
+}
+
+/// A constant-folded operand value — the subset of [`Arg`] that
+/// [`Compiler::fold_constants`] ever computes with (no `Str`: the request
+/// only asks for arithmetic/comparison/bitwise folding, and string `ADD`
+/// concatenation isn't one of those). Mirrors `main::Value`'s
+/// `as_int`/`as_float`/`as_bool`/`to_string` promotion rules exactly, so a
+/// folded result is identical to what the VM would have computed at runtime.
+#[derive(Clone, Copy)]
+enum Lit {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+}
+
+impl Lit {
+    fn as_int(self) -> i64 {
+        match self {
+            Lit::Int(i) => i,
+            Lit::Float(f) => f as i64,
+            Lit::Bool(b) => if b { 1 } else { 0 },
+        }
+    }
+    fn as_float(self) -> f64 {
+        match self {
+            Lit::Int(i) => i as f64,
+            Lit::Float(f) => f,
+            Lit::Bool(b) => if b { 1.0 } else { 0.0 },
+        }
+    }
+    fn as_bool(self) -> bool {
+        match self {
+            Lit::Int(i) => i != 0,
+            Lit::Float(f) => f != 0.0,
+            Lit::Bool(b) => b,
+        }
+    }
+    fn to_string_val(self) -> String {
+        match self {
+            Lit::Int(i) => i.to_string(),
+            Lit::Float(f) => f.to_string(),
+            Lit::Bool(b) => b.to_string(),
+        }
+    }
+}
+
+/// Fold a binary opcode applied to two literal operands, or `None` if it
+/// can't be evaluated at compile time — either because the opcode isn't
+/// arithmetic/comparison/bitwise, or because evaluating it now would panic
+/// the compiler itself (division/modulo by zero, an overflowing shift
+/// amount) where the VM would only panic if that code actually ran. Leaving
+/// those unfolded changes nothing observable: the same panic just happens
+/// later, at the same place it always would have.
+fn fold_binop(op: Op, a: Lit, b: Lit) -> Option<Lit> {
+    let is_float = matches!(a, Lit::Float(_)) || matches!(b, Lit::Float(_));
+    match op {
+        Op::Add => Some(if is_float {
+            Lit::Float(a.as_float() + b.as_float())
+        } else {
+            Lit::Int(a.as_int() + b.as_int())
+        }),
+        Op::Sub => Some(if is_float {
+            Lit::Float(a.as_float() - b.as_float())
+        } else {
+            Lit::Int(a.as_int() - b.as_int())
+        }),
+        Op::Mul => Some(if is_float {
+            Lit::Float(a.as_float() * b.as_float())
+        } else {
+            Lit::Int(a.as_int().checked_mul(b.as_int()).unwrap_or(0))
+        }),
+        Op::Div => {
+            if is_float {
+                Some(Lit::Float(a.as_float() / b.as_float()))
+            } else if b.as_int() == 0 {
+                None
+            } else {
+                Some(Lit::Int(a.as_int() / b.as_int()))
+            }
+        }
+        Op::Mod => {
+            if b.as_int() == 0 {
+                None
+            } else {
+                Some(Lit::Int(a.as_int() % b.as_int()))
+            }
+        }
+        Op::Pow => {
+            if is_float {
+                Some(Lit::Float(a.as_float().powf(b.as_float())))
+            } else {
+                let exp = b.as_int();
+                if exp >= 0 {
+                    a.as_int().checked_pow(exp as u32).map(Lit::Int)
+                } else {
+                    Some(Lit::Float(a.as_float().powf(exp as f64)))
+                }
+            }
+        }
+        Op::FloorDiv => {
+            if is_float {
+                Some(Lit::Float((a.as_float() / b.as_float()).floor()))
+            } else {
+                let (ai, bi) = (a.as_int(), b.as_int());
+                if bi == 0 {
+                    return None;
+                }
+                let q = ai / bi;
+                let r = ai % bi;
+                Some(Lit::Int(if r != 0 && (r < 0) != (bi < 0) { q - 1 } else { q }))
+            }
+        }
+        Op::Eq => Some(Lit::Bool(a.to_string_val() == b.to_string_val())),
+        Op::Ne => Some(Lit::Bool(a.to_string_val() != b.to_string_val())),
+        Op::Lt => Some(Lit::Bool(if is_float { a.as_float() < b.as_float() } else { a.as_int() < b.as_int() })),
+        Op::Le => Some(Lit::Bool(if is_float { a.as_float() <= b.as_float() } else { a.as_int() <= b.as_int() })),
+        Op::Gt => Some(Lit::Bool(if is_float { a.as_float() > b.as_float() } else { a.as_int() > b.as_int() })),
+        Op::Ge => Some(Lit::Bool(if is_float { a.as_float() >= b.as_float() } else { a.as_int() >= b.as_int() })),
+        Op::BAnd => Some(Lit::Int(a.as_int() & b.as_int())),
+        Op::BOr => Some(Lit::Int(a.as_int() | b.as_int())),
+        Op::BXor => Some(Lit::Int(a.as_int() ^ b.as_int())),
+        Op::Shl => {
+            let shift = b.as_int();
+            if (0..64).contains(&shift) { Some(Lit::Int(a.as_int() << shift)) } else { None }
+        }
+        Op::Shr => {
+            let shift = b.as_int();
+            if (0..64).contains(&shift) { Some(Lit::Int(a.as_int() >> shift)) } else { None }
+        }
+        Op::And => Some(Lit::Bool(a.as_bool() && b.as_bool())),
+        Op::Or => Some(Lit::Bool(a.as_bool() || b.as_bool())),
+        _ => None,
+    }
+}
+
+/// Fold a unary opcode (`NEG`/`NOT`) applied to a literal operand, or `None`
+/// if `op` isn't one of those.
+fn fold_unop(op: Op, a: Lit) -> Option<Lit> {
+    match op {
+        Op::Neg => Some(if let Lit::Float(f) = a { Lit::Float(-f) } else { Lit::Int(-a.as_int()) }),
+        Op::Not => Some(Lit::Int(!a.as_int())),
+        _ => None,
+    }
+}
+
+struct FunctionEntry {
+    name: String,
+    params: Vec<String>,
+    address: usize,
+    /// Source line of the `proc` keyword that defined it — see
+    /// `Compiler::debug` for the equivalent per-instruction mapping.
+    line: u32,
+}
+
+pub struct Compiler {
+    code: Vec<Instr>,
+    pending_funcs: Vec<(String, Vec<String>, Vec<Instr>, Vec<(usize, u32)>, u32)>,
+    funcs: Vec<FunctionEntry>,
+    break_stack: Vec<Vec<usize>>,
+    builtins: HashSet<String>,
+    /// Deduplicated literal pool: every `PUSH_CONST`/`LOAD`/`STORE`/`ATTR`/
+    /// `STORE_ATTR`/`CALL`/`TCALL` operand is an index into this instead of
+    /// an inline value, so a name or literal repeated across the program is
+    /// only ever written once — see `intern`.
+    constants: Vec<Arg>,
+    const_index: HashMap<Arg, usize>,
+    /// Counter backing `gensym`, for compiler-synthesized temporaries (e.g.
+    /// a `match` subject) that must never collide with a user identifier.
+    temp_counter: usize,
+    /// How many `compile_function_body` calls are currently nested — 0 at
+    /// top level. A `func_def` seen while this is nonzero is itself nested
+    /// inside another function, so it's eligible for closure conversion if
+    /// its body references free variables (see `compile_function_body`).
+    fn_depth: usize,
+    /// Names of `func_def`s that were compiled as closures (had upvalues),
+    /// so call sites know to dispatch through `LOAD`+`CALL_VALUE` instead of
+    /// the static `CALL <name>`/`TCALL <name>` fast path, which would skip
+    /// the captured values entirely.
+    closure_names: HashSet<String>,
+    /// The source line `compile_stmt` last saw on a statement node, used to
+    /// tag every instruction `emit`/`emit_placeholder` produces until the
+    /// next statement updates it — see `debug`.
+    current_line: u32,
+    /// Parallel to `code`: `debug[k] = (code_index, line)` for every point
+    /// where the current source line changed, in ascending `code_index`
+    /// order — a run-length-encoded instruction-index-to-source-line map, so
+    /// a VM fault can binary-search it for the originating line (see
+    /// `main::line_for_pc`). Only entries where the line actually differs
+    /// from the previous instruction's are pushed; `emit`/`emit_placeholder`
+    /// do the RLE collapsing inline rather than recording one entry per
+    /// instruction and compressing it later.
+    debug: Vec<(usize, u32)>,
+}
+
+impl Compiler {
+    pub fn new() -> Self {
+        let builtins = ["chr", "ascii", "hex", "binary", "length", "read_file", "eval", "apply"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        Self {
+            code: Vec::new(),
+            pending_funcs: Vec::new(),
+            funcs: Vec::new(),
+            break_stack: Vec::new(),
+            builtins,
+            constants: Vec::new(),
+            const_index: HashMap::new(),
+            temp_counter: 0,
+            fn_depth: 0,
+            closure_names: HashSet::new(),
+            current_line: 0,
+            debug: Vec::new(),
+        }
+    }
+
+    /// Record `self.current_line` as covering `self.code` from `idx` onward,
+    /// if it's not already the line the previous run started recording —
+    /// the inline RLE collapse `debug` relies on (see its field doc comment).
+    fn mark_line(&mut self, idx: usize) {
+        if self.debug.last().map(|&(_, line)| line) != Some(self.current_line) {
+            self.debug.push((idx, self.current_line));
+        }
+    }
+
+    /// Names callable via the `BUILTIN` bytecode form rather than a user
+    /// `proc` call — used by [`crate::repl`] to offer
+    /// them for completion alongside `proc` names defined in the session.
+    pub fn builtin_names(&self) -> impl Iterator<Item = &str> {
+        self.builtins.iter().map(String::as_str)
+    }
+
+    fn emit(&mut self, op: Op, arg: Option<Arg>) {
+        debug_assert!(
+            arg_matches_shape(&arg, op.shape()),
+            "{:?} operand doesn't match its declared shape",
+            op
+        );
+        let idx = self.code.len();
+        self.code.push(Instr { op, arg });
+        self.mark_line(idx);
+    }
+
+    /// Intern `arg` into the constant pool, returning its index — reusing an
+    /// existing slot if this exact literal was already emitted.
+    fn intern(&mut self, arg: Arg) -> usize {
+        if let Some(&idx) = self.const_index.get(&arg) {
+            return idx;
+        }
+        let idx = self.constants.len();
+        self.const_index.insert(arg.clone(), idx);
+        self.constants.push(arg);
+        idx
+    }
+
+    /// Emit `op` with a constant-pool index operand in place of `arg`'s
+    /// value, interning `arg` first.
+    fn emit_const(&mut self, op: Op, arg: Arg) {
+        let idx = self.intern(arg);
+        self.emit(op, Some(Arg::Int(idx as i64)));
+    }
+
+    /// A fresh variable name no user identifier can ever collide with — the
+    /// lexer never produces `$` as part of an `Ident`, so `tag` plus a
+    /// monotonic counter is always unique within one compile. Used to stash
+    /// a `match` subject that's compared against more than one case value.
+    fn gensym(&mut self, tag: &str) -> String {
+        let n = self.temp_counter;
+        self.temp_counter += 1;
+        format!("${}{}", tag, n)
+    }
+
+    fn emit_placeholder(&mut self, op: Op) -> usize {
+        let idx = self.code.len();
+        self.code.push(Instr { op, arg: None });
+        self.mark_line(idx);
+        idx
+    }
+
+    fn patch(&mut self, idx: usize, target: usize) {
+        let op = self.code[idx].op.clone();
+        self.code[idx] = Instr { op, arg: Some(Arg::Int(target as i64)) };
+    }
+
+    /// Resolve a pool-indexed `LOAD`/`STORE`-shaped instruction's operand
+    /// back to the name it names, via the constant pool — used by
+    /// `compile_function_body`'s free-variable scan, which only has the
+    /// already-emitted `Instr`s (with `Arg::Int` pool indices) to work from.
+    fn resolve_str_arg(&self, instr: &Instr) -> String {
+        match instr.arg {
+            Some(Arg::Int(idx)) => match &self.constants[idx as usize] {
+                Arg::Str(s) => s.clone(),
+                _ => panic!("expected a string constant at pool index {}", idx),
+            },
+            _ => panic!("expected a pool-indexed string operand"),
+        }
+    }
+
+    pub fn compile(&mut self, ast: &Vec<Value>) {
+        self.compile_block(ast);
+        self.emit(Op::Halt, None);
+
+        let mut final_code = self.code.clone();
+        let mut final_debug = self.debug.clone();
+        for (name, params, body, body_debug, line) in self.pending_funcs.drain(..) {
+            let addr = final_code.len();
+            self.funcs.push(FunctionEntry { name: name.clone(), params: params.clone(), address: addr, line });
+            for instr in body {
+                match instr.arg {
+                    Some(Arg::Int(i)) if instr.op == Op::Jump || instr.op == Op::JumpIfFalse => {
+                        final_code.push(Instr { op: instr.op, arg: Some(Arg::Int(i + addr as i64)) });
+                    }
+                    _ => final_code.push(instr),
+                }
+            }
+            for (idx, line) in body_debug {
+                final_debug.push((idx + addr, line));
+            }
+        }
+        self.code = final_code;
+        self.debug = final_debug;
+        self.optimize();
+    }
+
+    /// Resolve a `PUSH_CONST`/`PUSH_BOOL` instruction to the literal it
+    /// pushes, or `None` if it's some other instruction (or a `PUSH_CONST`
+    /// of a non-numeric, e.g. `Arg::Str`, constant — strings aren't
+    /// foldable, see [`Lit`]).
+    fn as_lit(&self, instr: &Instr) -> Option<Lit> {
+        match instr.op {
+            Op::PushConst => match instr.arg {
+                Some(Arg::Int(idx)) => match self.constants.get(idx as usize) {
+                    Some(Arg::Int(i)) => Some(Lit::Int(*i)),
+                    Some(Arg::Float(f)) => Some(Lit::Float(*f)),
+                    _ => None,
+                },
+                _ => None,
+            },
+            Op::PushBool => match instr.arg {
+                Some(Arg::Int(v)) => Some(Lit::Bool(v != 0)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Emit `lit` as the `PUSH_CONST`/`PUSH_BOOL` instruction that produces
+    /// it, interning it into the constant pool like any other literal if
+    /// it's not a bool.
+    fn lit_instr(&mut self, lit: Lit) -> Instr {
+        match lit {
+            Lit::Int(i) => {
+                let idx = self.intern(Arg::Int(i));
+                Instr { op: Op::PushConst, arg: Some(Arg::Int(idx as i64)) }
+            }
+            Lit::Float(f) => {
+                let idx = self.intern(Arg::Float(f));
+                Instr { op: Op::PushConst, arg: Some(Arg::Int(idx as i64)) }
+            }
+            Lit::Bool(b) => Instr { op: Op::PushBool, arg: Some(Arg::Int(if b { 1 } else { 0 })) },
+        }
+    }
+
+    /// Post-process `self.code` after function bodies are flattened and
+    /// their `JUMP`/`JUMP_IF_FALSE` offsets are final: fold literal
+    /// arithmetic, thread `JUMP`-to-`JUMP` chains, and delete unreachable
+    /// code after an unconditional control transfer. Each rewrite can expose
+    /// a new opportunity for the others (folding a condition can turn a
+    /// `JUMP_IF_FALSE` target into dead code; deleting a chain can shorten
+    /// another thread), so the whole pass re-runs until one round changes
+    /// nothing.
+    fn optimize(&mut self) {
+        loop {
+            let folded = self.fold_constants();
+            let threaded = self.thread_jumps();
+            let trimmed = self.eliminate_dead_code();
+            if !folded && !threaded && !trimmed {
+                break;
+            }
+        }
+    }
+
+    /// Collapse `[<literal>, <literal>, <binop>]` and `[<literal>, NEG/NOT]`
+    /// runs into the single `PUSH_CONST`/`PUSH_BOOL` they'd evaluate to.
+    /// Folding shrinks the instruction count, so — like
+    /// `eliminate_dead_code` — this rebuilds an old-index-to-new-index map
+    /// and uses it to fix up every `JUMP`/`JUMP_IF_FALSE` operand and
+    /// function `address`. A jump can only ever target the *start* of a
+    /// foldable run (control flow always lands on a statement boundary, never
+    /// mid-expression), so mapping every index in a folded run to the new
+    /// single replacement instruction is always correct.
+    fn fold_constants(&mut self) -> bool {
+        let old_code = std::mem::take(&mut self.code);
+        let n = old_code.len();
+        let mut new_idx = vec![0usize; n];
+        let mut new_code: Vec<Instr> = Vec::with_capacity(n);
+        let mut changed = false;
+        let mut i = 0;
+        while i < n {
+            if i + 2 < n {
+                if let (Some(a), Some(b)) = (self.as_lit(&old_code[i]), self.as_lit(&old_code[i + 1])) {
+                    if let Some(result) = fold_binop(old_code[i + 2].op, a, b) {
+                        let pos = new_code.len();
+                        new_idx[i] = pos;
+                        new_idx[i + 1] = pos;
+                        new_idx[i + 2] = pos;
+                        let instr = self.lit_instr(result);
+                        new_code.push(instr);
+                        changed = true;
+                        i += 3;
+                        continue;
+                    }
+                }
+            }
+            if i + 1 < n {
+                if let Some(a) = self.as_lit(&old_code[i]) {
+                    if let Some(result) = fold_unop(old_code[i + 1].op, a) {
+                        let pos = new_code.len();
+                        new_idx[i] = pos;
+                        new_idx[i + 1] = pos;
+                        let instr = self.lit_instr(result);
+                        new_code.push(instr);
+                        changed = true;
+                        i += 2;
+                        continue;
+                    }
+                }
+            }
+            new_idx[i] = new_code.len();
+            new_code.push(old_code[i].clone());
+            i += 1;
+        }
+
+        if !changed {
+            self.code = old_code;
+            return false;
+        }
+
+        for instr in new_code.iter_mut() {
+            if instr.op == Op::Jump || instr.op == Op::JumpIfFalse {
+                if let Some(Arg::Int(t)) = instr.arg {
+                    instr.arg = Some(Arg::Int(new_idx[t as usize] as i64));
+                }
+            }
+        }
+        for f in self.funcs.iter_mut() {
+            f.address = new_idx[f.address];
+        }
+        self.code = new_code;
+        self.debug = remap_debug(&self.debug, &new_idx);
+        true
+    }
+
+    /// Follow any `JUMP`/`JUMP_IF_FALSE` whose target is itself an
+    /// unconditional `JUMP` until landing on a non-`JUMP` instruction (or
+    /// hitting a cycle, which an infinite source `loop` can legitimately
+    /// produce), rewriting the operand to the final target. Doesn't change
+    /// instruction count, so no index remapping is needed — including for
+    /// a `SWITCH` jump table's own slot `JUMP`s, which this applies to the
+    /// same as any other.
+    fn thread_jumps(&mut self) -> bool {
+        let mut changed = false;
+        for i in 0..self.code.len() {
+            if self.code[i].op != Op::Jump && self.code[i].op != Op::JumpIfFalse {
+                continue;
+            }
+            if let Some(Arg::Int(target)) = self.code[i].arg {
+                let mut t = target as usize;
+                let mut seen = HashSet::new();
+                while t < self.code.len() && self.code[t].op == Op::Jump && seen.insert(t) {
+                    match self.code[t].arg {
+                        Some(Arg::Int(next)) => t = next as usize,
+                        _ => break,
+                    }
+                }
+                if t != target as usize {
+                    self.code[i].arg = Some(Arg::Int(t as i64));
+                    changed = true;
+                }
+            }
+        }
+        changed
+    }
+
+    /// Delete instructions after `HALT`/`RET`/an unconditional `JUMP` up to
+    /// the next instruction actually referenced as a jump target (by a
+    /// `JUMP`/`JUMP_IF_FALSE` operand or a function's `address`). A
+    /// `SWITCH`'s slot table is addressed implicitly — the VM computes a
+    /// slot's address as `switch_pc + 1 + index`, never through a stored
+    /// operand — so its `len` slots are always kept regardless, and `SWITCH`
+    /// itself is never deleted (deleting it without its slots would corrupt
+    /// that implicit addressing; `compile_match` never emits one as
+    /// genuinely unreachable code, so this never costs a real optimization).
+    /// Deleting shifts every surviving instruction's index, so — like
+    /// `fold_constants` — this rebuilds an old-to-new index map and rewrites
+    /// jump operands and function addresses through it.
+    fn eliminate_dead_code(&mut self) -> bool {
+        let mut targets: HashSet<usize> = HashSet::new();
+        for instr in &self.code {
+            if instr.op == Op::Jump || instr.op == Op::JumpIfFalse {
+                if let Some(Arg::Int(t)) = instr.arg {
+                    targets.insert(t as usize);
+                }
+            }
+        }
+        for f in &self.funcs {
+            targets.insert(f.address);
+        }
+        targets.insert(0);
+
+        let mut protected: HashSet<usize> = HashSet::new();
+        for (i, instr) in self.code.iter().enumerate() {
+            if instr.op == Op::Switch {
+                if let Some(Arg::Switch(_, len)) = instr.arg {
+                    for k in 0..len {
+                        protected.insert(i + 1 + k);
+                    }
+                }
+            }
+        }
+
+        let mut keep = vec![true; self.code.len()];
+        let mut after_terminator = false;
+        for (i, instr) in self.code.iter().enumerate() {
+            if after_terminator
+                && !targets.contains(&i)
+                && !protected.contains(&i)
+                && instr.op != Op::Switch
+            {
+                keep[i] = false;
+                continue;
+            }
+            after_terminator = matches!(instr.op, Op::Halt | Op::Ret | Op::Jump | Op::Switch);
+        }
+
+        if keep.iter().all(|&k| k) {
+            return false;
+        }
+
+        let mut new_idx = vec![0usize; self.code.len()];
+        let mut next = 0;
+        for (i, &k) in keep.iter().enumerate() {
+            if k {
+                new_idx[i] = next;
+                next += 1;
+            }
+        }
+
+        let old_code = std::mem::take(&mut self.code);
+        let mut new_code = Vec::with_capacity(next);
+        for (i, instr) in old_code.into_iter().enumerate() {
+            if !keep[i] {
+                continue;
+            }
+            let mut instr = instr;
+            if instr.op == Op::Jump || instr.op == Op::JumpIfFalse {
+                if let Some(Arg::Int(t)) = instr.arg {
+                    instr.arg = Some(Arg::Int(new_idx[t as usize] as i64));
+                }
+            }
+            new_code.push(instr);
+        }
+        self.code = new_code;
+        for f in self.funcs.iter_mut() {
+            f.address = new_idx[f.address];
+        }
+        // Unlike `fold_constants`, `new_idx` here has no entry for a deleted
+        // (`!keep[i]`) index — forward-fill it to the next surviving
+        // instruction's new index before handing it to `remap_debug`, so a
+        // `debug` entry that happened to tag a deleted instruction still
+        // gets a valid (if approximate — that code was unreachable anyway)
+        // new index instead of the default `0`.
+        let mut carry = next;
+        for i in (0..keep.len()).rev() {
+            if keep[i] {
+                carry = new_idx[i];
+            } else {
+                new_idx[i] = carry;
+            }
+        }
+        self.debug = remap_debug(&self.debug, &new_idx);
+        true
+    }
+
+    fn compile_block(&mut self, block: &Vec<Value>) {
+        for stmt in block {
+            self.compile_stmt(stmt);
+        }
+    }
+
+    fn compile_stmt(&mut self, stmt: &Value) {
+        if let Some(line) = stmt_line(stmt) {
+            self.current_line = line;
+        }
+        let arr = stmt.as_array().expect("stmt array");
+        let kind = arr[0].as_str().expect("kind str");
+        match kind {
+            "emit" => {
+                self.compile_expr(&arr[1]);
+                self.emit(Op::Emit, None);
+            }
+            "decl" | "assign" => {
+                let name = arr[1].as_str().unwrap().to_string();
+                self.compile_expr(&arr[2]);
+                self.emit_const(Op::Store, Arg::Str(name));
+            }
+            "attr_assign" => {
+                self.compile_expr(&arr[1]);
+                self.compile_expr(&arr[3]);
+                let attr = arr[2].as_str().unwrap().to_string();
+                self.emit_const(Op::StoreAttr, Arg::Str(attr));
+            }
+            "index_assign" => {
+                self.compile_expr(&arr[1]);
+                self.compile_expr(&arr[2]);
+                self.compile_expr(&arr[3]);
+                self.emit(Op::StoreIndex, None);
+            }
+            "expr_stmt" => {
+                self.compile_expr(&arr[1]);
+                self.emit(Op::Pop, None);
+            }
+            "import" => {
+                let path = arr[1].as_str().unwrap().to_string();
+                let alias = arr[2].as_str().unwrap().to_string();
+                self.emit_const(Op::PushConst, Arg::Str(path));
+                self.emit(Op::Import, None);
+                self.emit_const(Op::Store, Arg::Str(alias));
+            }
+            "facts" => {
+                self.compile_expr(&arr[1]);
+                self.emit(Op::Assert, None);
+            }
+            "if" => {
+                // Unroll nested if/elif chain
+                let mut cond_blocks: Vec<(Value, Vec<Value>)> = Vec::new();
+                let mut current = stmt.clone();
+                let mut else_block: Option<Vec<Value>> = None;
+                loop {
+                    let carr = current.as_array().unwrap();
+                    let cond = carr[1].clone();
+                    let block_node = &carr[2];
+                    let block = block_node.as_array().unwrap()[1].as_array().unwrap().clone();
+                    cond_blocks.push((cond, block));
+                    let tail = &carr[3];
+                    if let Some(tarr) = tail.as_array() {
+                        if tarr[0].as_str().unwrap() == "if" {
+                            current = tail.clone();
+                            continue;
+                        } else if tarr[0].as_str().unwrap() == "block" {
+                            else_block = Some(tarr[1].as_array().unwrap().clone());
+                        }
+                    }
+                    break;
+                }
+                let mut end_jumps = Vec::new();
+                for (cond, block) in cond_blocks {
+                    self.compile_expr(&cond);
+                    let jf = self.emit_placeholder(Op::JumpIfFalse);
+                    self.compile_block(&block);
+                    end_jumps.push(self.emit_placeholder(Op::Jump));
+                    self.patch(jf, self.code.len());
+                }
+                if let Some(block) = else_block {
+                    self.compile_block(&block);
+                }
+                for j in end_jumps {
+                    self.patch(j, self.code.len());
+                }
+            }
+            "loop" => {
+                let start = self.code.len();
+                self.compile_expr(&arr[1]);
+                let jf = self.emit_placeholder(Op::JumpIfFalse);
+                let body = arr[2].as_array().unwrap()[1].as_array().unwrap().clone();
+                self.break_stack.push(Vec::new());
+                self.compile_block(&body);
+                self.emit(Op::Jump, Some(Arg::Int(start as i64)));
+                self.patch(jf, self.code.len());
+                if let Some(brks) = self.break_stack.pop() {
+                    for idx in brks {
+                        self.patch(idx, self.code.len());
+                    }
+                }
+            }
+            "match" => self.compile_match(arr),
+            "func_def" => {
+                let name = arr[1].as_str().unwrap().to_string();
+                let params = arr[2]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|v| v.as_str().unwrap().to_string())
+                    .collect::<Vec<_>>();
+                let body = arr[3].as_array().unwrap()[1].as_array().unwrap().clone();
+                let def_line = self.current_line;
+                // Only a `func_def` textually nested inside another function
+                // has an enclosing scope worth closing over — a top-level
+                // one has nothing to capture.
+                let is_nested = self.fn_depth > 0;
+                let (body_code, body_debug, upvalues) =
+                    self.compile_function_body(&body, &params, &name);
+                if is_nested && !upvalues.is_empty() {
+                    for uv in &upvalues {
+                        self.emit_const(Op::Load, Arg::Str(uv.clone()));
+                    }
+                    self.emit(Op::MakeClosure, Some(Arg::Closure(name.clone(), upvalues.len())));
+                    self.emit_const(Op::Store, Arg::Str(name.clone()));
+                    self.closure_names.insert(name.clone());
+                }
+                self.pending_funcs.push((name, params, body_code, body_debug, def_line));
+            }
+            "return" => {
+                let expr = &arr[1];
+                if let Some(farr) = expr.as_array() {
+                    if farr[0].as_str().unwrap() == "func_call" {
+                        if let Some(func_arr) = farr[1].as_array() {
+                            if func_arr[0].as_str().unwrap() == "ident" {
+                                let name = func_arr[1].as_str().unwrap();
+                                // A closure's captures live in its `Value`,
+                                // not in `funcs`, so it can't go through the
+                                // static `TCALL <name>` fast path — fall
+                                // through to the general `LOAD`+`CALL_VALUE`
+                                // expression compile below instead.
+                                if !self.closure_names.contains(name) {
+                                    let args = farr[2].as_array().unwrap();
+                                    for a in args {
+                                        self.compile_expr(a);
+                                    }
+                                    if self.builtins.contains(name) {
+                                        self.emit(Op::Builtin, Some(Arg::Builtin(name.to_string(), args.len())));
+                                        self.emit(Op::Ret, None);
+                                    } else {
+                                        self.emit_const(Op::TCall, Arg::Str(name.to_string()));
+                                    }
+                                    return;
+                                }
+                            }
+                        }
+                    }
+                }
+                self.compile_expr(expr);
+                self.emit(Op::Ret, None);
+            }
+            "break" => {
+                if self.break_stack.is_empty() {
+                    panic!("'break' used outside of loop");
+                }
+                let j = self.emit_placeholder(Op::Jump);
+                self.break_stack.last_mut().unwrap().push(j);
+            }
+            "block" => {
+                let stmts = arr[1].as_array().unwrap();
+                self.compile_block(stmts);
+            }
+            _ => panic!("Unsupported statement: {:?}", stmt),
+        }
+    }
+
+    /// Compile a `["match", subject, [[caseval, block], ...], default_block]`
+    /// node (see `parser::parse_match`). When every `caseval` is a plain
+    /// integer literal and they're at least half-dense over their
+    /// `[min, max]` span, lower to a `SWITCH` jump table for O(1) dispatch;
+    /// otherwise fall back to the same `JUMP_IF_FALSE`-chain shape `if`/`elif`
+    /// already use.
+    fn compile_match(&mut self, arr: &[Value]) {
+        let subject = &arr[1];
+        let cases = arr[2].as_array().unwrap().clone();
+        let default_block = arr[3].clone();
+        let default_stmts = default_block.as_array().unwrap()[1].as_array().unwrap().clone();
+
+        let int_cases: Option<Vec<(i64, Vec<Value>)>> = cases
+            .iter()
+            .map(|c| {
+                let carr = c.as_array().unwrap();
+                let val_arr = carr[0].as_array().unwrap();
+                if val_arr[0].as_str().unwrap() != "number" {
+                    return None;
+                }
+                let block_stmts = carr[1].as_array().unwrap()[1].as_array().unwrap().clone();
+                Some((val_arr[1].as_i64().unwrap(), block_stmts))
+            })
+            .collect();
+
+        let dense = int_cases.as_ref().filter(|ic| !ic.is_empty()).and_then(|ic| {
+            let min = ic.iter().map(|(v, _)| *v).min().unwrap();
+            let max = ic.iter().map(|(v, _)| *v).max().unwrap();
+            let len = (max - min + 1) as usize;
+            if (ic.len() as f64) / (len as f64) >= 0.5 {
+                Some((min, len))
+            } else {
+                None
+            }
+        });
+
+        match (int_cases, dense) {
+            (Some(int_cases), Some((min, len))) => {
+                let mut slots: Vec<Option<Vec<Value>>> = vec![None; len];
+                for (v, block_stmts) in int_cases {
+                    slots[(v - min) as usize] = Some(block_stmts);
+                }
+
+                self.compile_expr(subject);
+                self.emit_const(Op::PushConst, Arg::Int(min));
+                self.emit(Op::Sub, None);
+                self.emit(Op::Switch, Some(Arg::Switch(min, len)));
+                let slot_jumps: Vec<usize> =
+                    (0..len).map(|_| self.emit_placeholder(Op::Jump)).collect();
+
+                let mut end_jumps = Vec::new();
+                for (slot, block_stmts) in slot_jumps.iter().zip(slots.into_iter()) {
+                    if let Some(stmts) = block_stmts {
+                        self.patch(*slot, self.code.len());
+                        self.compile_block(&stmts);
+                        end_jumps.push(self.emit_placeholder(Op::Jump));
+                    }
+                }
+                let default_addr = self.code.len();
+                for slot in &slot_jumps {
+                    if self.code[*slot].arg.is_none() {
+                        self.patch(*slot, default_addr);
+                    }
+                }
+                self.compile_block(&default_stmts);
+                for j in end_jumps {
+                    self.patch(j, self.code.len());
+                }
+            }
+            _ => {
+                let subject_var = self.gensym("match_subject");
+                self.compile_expr(subject);
+                self.emit_const(Op::Store, Arg::Str(subject_var.clone()));
+
+                let mut end_jumps = Vec::new();
+                for c in &cases {
+                    let carr = c.as_array().unwrap();
+                    self.emit_const(Op::Load, Arg::Str(subject_var.clone()));
+                    self.compile_expr(&carr[0]);
+                    self.emit(Op::Eq, None);
+                    let jf = self.emit_placeholder(Op::JumpIfFalse);
+                    let block_stmts = carr[1].as_array().unwrap()[1].as_array().unwrap().clone();
+                    self.compile_block(&block_stmts);
+                    end_jumps.push(self.emit_placeholder(Op::Jump));
+                    self.patch(jf, self.code.len());
+                }
+                self.compile_block(&default_stmts);
+                for j in end_jumps {
+                    self.patch(j, self.code.len());
+                }
+            }
+        }
+    }
+
+    /// Compile a function body, returning its code plus the names of any
+    /// free variables it reads (its upvalues) — every `LOAD` in the body
+    /// whose name isn't one of `params`, isn't `own_name`, and is never
+    /// `STORE`d within the body itself. If there are any, each such `LOAD`
+    /// is rewritten in place to a `LOAD_UPVALUE <idx>` indexing into the
+    /// closure's capture array (see the `"func_def"` arm of `compile_stmt`,
+    /// which emits the `MAKE_CLOSURE` that builds that array out of the
+    /// enclosing scope's current values).
+    ///
+    /// `own_name` is excluded from that scan: a self-reference (a recursive
+    /// call, or the function passed by name as a value) loads a name that
+    /// isn't bound to anything yet at the point `MAKE_CLOSURE`'s capture
+    /// loads run — the enclosing scope's `STORE own_name` only happens
+    /// *after* the closure is built. Treating it as an upvalue would load
+    /// garbage (or an unrelated outer variable of the same name) instead of
+    /// leaving the `LOAD` to resolve normally once the name is bound by the
+    /// time the function actually runs.
+    fn compile_function_body(
+        &mut self,
+        body: &Vec<Value>,
+        params: &[String],
+        own_name: &str,
+    ) -> (Vec<Instr>, Vec<(usize, u32)>, Vec<String>) {
+        self.fn_depth += 1;
+        let saved = std::mem::take(&mut self.code);
+        let saved_debug = std::mem::take(&mut self.debug);
+        self.compile_block(body);
+        self.emit(Op::Ret, None);
+        let mut body_code = std::mem::take(&mut self.code);
+        let body_debug = std::mem::take(&mut self.debug);
+        self.code = saved;
+        self.debug = saved_debug;
+        self.fn_depth -= 1;
+
+        let params_set: HashSet<String> = params.iter().cloned().collect();
+        let mut locals: HashSet<String> = HashSet::new();
+        for instr in &body_code {
+            if instr.op == Op::Store {
+                locals.insert(self.resolve_str_arg(instr));
+            }
+        }
+
+        let mut upvalues: Vec<String> = Vec::new();
+        let mut seen: HashSet<String> = HashSet::new();
+        for instr in &body_code {
+            if instr.op == Op::Load {
+                let name = self.resolve_str_arg(instr);
+                if name != own_name
+                    && !params_set.contains(&name)
+                    && !locals.contains(&name)
+                    && seen.insert(name.clone())
+                {
+                    upvalues.push(name);
+                }
+            }
+        }
+
+        if !upvalues.is_empty() {
+            let idx_of: HashMap<&str, usize> =
+                upvalues.iter().enumerate().map(|(i, n)| (n.as_str(), i)).collect();
+            for instr in body_code.iter_mut() {
+                if instr.op == Op::Load {
+                    let name = self.resolve_str_arg(instr);
+                    if let Some(&idx) = idx_of.get(name.as_str()) {
+                        *instr = Instr { op: Op::LoadUpvalue, arg: Some(Arg::Int(idx as i64)) };
+                    }
+                }
+            }
+        }
+
+        (body_code, body_debug, upvalues)
+    }
+
+    fn compile_expr(&mut self, node: &Value) {
+        let arr = node.as_array().expect("expr array");
+        let op = arr[0].as_str().unwrap();
+        match op {
+            "number" => {
+                self.emit_const(Op::PushConst, Arg::Int(arr[1].as_i64().unwrap()));
+            }
+            "float" => {
+                self.emit_const(Op::PushConst, Arg::Float(arr[1].as_f64().unwrap()));
+            }
+            "string" => {
+                self.emit_const(Op::PushConst, Arg::Str(arr[1].as_str().unwrap().to_string()));
+            }
+            "bool" => {
+                let v = if arr[1].as_bool().unwrap() { 1 } else { 0 };
+                self.emit(Op::PushBool, Some(Arg::Int(v)));
+            }
+            "ident" => {
+                self.emit_const(Op::Load, Arg::Str(arr[1].as_str().unwrap().to_string()));
+            }
+            "list" => {
+                let elems = arr[1].as_array().unwrap();
+                for e in elems {
+                    self.compile_expr(e);
+                }
+                self.emit(Op::BuildList, Some(Arg::Int(elems.len() as i64)));
+            }
+            "dict" => {
+                let pairs = arr[1].as_array().unwrap();
+                for p in pairs {
+                    let key = p.as_array().unwrap()[0].as_str().unwrap().to_string();
+                    let val = &p.as_array().unwrap()[1];
+                    self.emit_const(Op::PushConst, Arg::Str(key));
+                    self.compile_expr(val);
+                }
+                self.emit(Op::BuildDict, Some(Arg::Int(pairs.len() as i64)));
+            }
+            "index" => {
+                self.compile_expr(&arr[1]);
+                self.compile_expr(&arr[2]);
+                self.emit(Op::Index, None);
+            }
+            "slice" => {
+                self.compile_expr(&arr[1]);
+                self.compile_expr(&arr[2]);
+                if arr[3].is_null() {
+                    self.emit(Op::PushNone, None);
+                } else {
+                    self.compile_expr(&arr[3]);
+                }
+                self.emit(Op::Slice, None);
+            }
+            "dot" => {
+                self.compile_expr(&arr[1]);
+                self.emit_const(Op::Attr, Arg::Str(arr[2].as_str().unwrap().to_string()));
+            }
+            "func_call" => {
+                let func_node = &arr[1];
+                let args = arr[2].as_array().unwrap();
+                if let Some(farr) = func_node.as_array() {
+                    if farr[0].as_str().unwrap() == "ident" {
+                        let name = farr[1].as_str().unwrap();
+                        // Closures must go through CALL_VALUE below, since
+                        // their captures live in the `Value` on the stack,
+                        // not in `funcs` — see `compile_function_body`.
+                        if !self.closure_names.contains(name) {
+                            for a in args {
+                                self.compile_expr(a);
+                            }
+                            if self.builtins.contains(name) {
+                                self.emit(Op::Builtin, Some(Arg::Builtin(name.to_string(), args.len())));
+                            } else {
+                                self.emit_const(Op::Call, Arg::Str(name.to_string()));
+                            }
+                            return;
+                        }
+                    }
+                }
+                self.compile_expr(func_node);
+                for a in args {
+                    self.compile_expr(a);
+                }
+                self.emit(Op::CallValue, Some(Arg::Int(args.len() as i64)));
+            }
+            "quote" => {
+                self.compile_quoted(&arr[1]);
+            }
+            "unary" => {
+                let unary_op = arr[1].as_str().unwrap();
+                self.compile_expr(&arr[2]);
+                match unary_op {
+                    "sub" => self.emit(Op::Neg, None),
+                    "not_bits" => self.emit(Op::Not, None),
+                    "add" => {}
+                    _ => panic!("Unknown unary op {}", unary_op),
+                }
+            }
+            _ => {
+                // binary operations encoded as op name
+                let ops: HashMap<&'static str, Op> = [
+                    ("add", Op::Add),
+                    ("sub", Op::Sub),
+                    ("mul", Op::Mul),
+                    ("div", Op::Div),
+                    ("mod", Op::Mod),
+                    ("pow", Op::Pow),
+                    ("floordiv", Op::FloorDiv),
+                    ("eq", Op::Eq),
+                    ("ne", Op::Ne),
+                    ("gt", Op::Gt),
+                    ("lt", Op::Lt),
+                    ("ge", Op::Ge),
+                    ("le", Op::Le),
+                    ("and", Op::And),
+                    ("or", Op::Or),
+                    ("and_bits", Op::BAnd),
+                    ("or_bits", Op::BOr),
+                    ("xor_bits", Op::BXor),
+                    ("shl", Op::Shl),
+                    ("shr", Op::Shr),
+                ].into_iter().collect();
+                if let Some(opcode) = ops.get(op) {
+                    self.compile_expr(&arr[1]);
+                    self.compile_expr(&arr[2]);
+                    self.emit(*opcode, None);
+                } else {
+                    panic!("Unsupported expression node: {:?}", node);
+                }
+            }
+        }
+    }
+
+    /// Emit instructions that build `node` — a fragment of the
+    /// `serde_json::Value` AST shape `ast_node` produces — as a runtime
+    /// omglang list/string/number value instead of compiling it, so a
+    /// `quote { ... }` expression captures its block as data. The `eval`
+    /// builtin (see `main.rs`) is the reverse of this: it turns such a
+    /// value back into an AST and compiles that.
+    fn compile_quoted(&mut self, node: &Value) {
+        match node {
+            Value::String(s) => self.emit_const(Op::PushConst, Arg::Str(s.clone())),
+            Value::Bool(b) => self.emit(Op::PushBool, Some(Arg::Int(if *b { 1 } else { 0 }))),
+            Value::Null => self.emit(Op::PushNone, None),
+            Value::Number(n) => {
+                if let Some(i) = n.as_i64() {
+                    self.emit_const(Op::PushConst, Arg::Int(i));
+                } else {
+                    self.emit_const(Op::PushConst, Arg::Float(n.as_f64().unwrap_or(0.0)));
+                }
+            }
+            Value::Array(items) => {
+                for item in items {
+                    self.compile_quoted(item);
+                }
+                self.emit(Op::BuildList, Some(Arg::Int(items.len() as i64)));
+            }
+            Value::Object(_) => panic!("quote: unexpected object in AST: {:?}", node),
+        }
+    }
+
+    pub fn to_string(&self) -> String {
+        let mut lines: Vec<String> = Vec::new();
+        for c in &self.constants {
+            lines.push(format_const_line(c));
+        }
+        for f in &self.funcs {
+            let params = f.params.join(" ");
+            lines.push(format!("FUNC {} {} {} {} {}", f.name, f.params.len(), params, f.address, f.line));
+        }
+        for &(start, line) in &self.debug {
+            lines.push(format!("LINE {} {}", start, line));
+        }
+        for instr in &self.code {
+            match &instr.arg {
+                Some(Arg::Builtin(name, argc)) => {
+                    lines.push(format!("BUILTIN {} {}", name, argc));
+                }
+                Some(Arg::Switch(base, len)) => {
+                    lines.push(format!("SWITCH {} {}", base, len));
+                }
+                Some(Arg::Closure(name, n_upvalues)) => {
+                    lines.push(format!("MAKE_CLOSURE {} {}", name, n_upvalues));
+                }
+                Some(Arg::Str(s)) if instr.op == Op::PushStr => {
+                    lines.push(format!("PUSH_STR {}", serde_json::to_string(s).unwrap()));
+                }
+                Some(Arg::Str(s)) => {
+                    lines.push(format!("{} {}", instr.op.name(), s));
+                }
+                Some(Arg::Int(i)) => {
+                    lines.push(format!("{} {}", instr.op.name(), i));
+                }
+                Some(Arg::Float(f)) => {
+                    // `{:?}` (not `{}`) so the value round-trips through
+                    // `str::parse::<f64>` on the way back in, the same
+                    // shortest-round-trip guarantee Rust's `Debug` for `f64`
+                    // gives and `Display` doesn't (e.g. `2.0` vs `2`).
+                    lines.push(format!("{} {:?}", instr.op.name(), f));
+                }
+                None => lines.push(instr.op.name().to_string()),
+            }
+        }
+        lines.join("\n")
+    }
+
+    /// Encode the same program `to_string` writes as text into a compact
+    /// binary image instead: a `b"OMGB"` magic + version byte, the constant
+    /// pool (a type tag byte per entry, then its value), a function table,
+    /// the source-line table, then a one-byte opcode plus varint/length-prefixed operands per
+    /// instruction. Unlike `to_string`'s line-oriented format, nothing here
+    /// is parsed as text at VM load time — see `main::load_bytecode`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(MAGIC);
+        out.push(FORMAT_VERSION);
+
+        write_uvarint(&mut out, self.constants.len() as u64);
+        for c in &self.constants {
+            match c {
+                Arg::Int(i) => {
+                    out.push(0);
+                    write_ivarint(&mut out, *i);
+                }
+                Arg::Float(f) => {
+                    out.push(1);
+                    out.extend_from_slice(&f.to_le_bytes());
+                }
+                Arg::Str(s) => {
+                    out.push(2);
+                    write_str(&mut out, s);
+                }
+                Arg::Builtin(..) => unreachable!("builtins are never interned"),
+                Arg::Switch(..) => unreachable!("switch tables are never interned"),
+                Arg::Closure(..) => unreachable!("closures are never interned"),
+            }
+        }
+
+        write_uvarint(&mut out, self.funcs.len() as u64);
+        for f in &self.funcs {
+            write_str(&mut out, &f.name);
+            write_uvarint(&mut out, f.params.len() as u64);
+            for p in &f.params {
+                write_str(&mut out, p);
+            }
+            write_uvarint(&mut out, f.address as u64);
+            write_uvarint(&mut out, f.line as u64);
+        }
+
+        write_uvarint(&mut out, self.debug.len() as u64);
+        for &(start, line) in &self.debug {
+            write_uvarint(&mut out, start as u64);
+            write_uvarint(&mut out, line as u64);
+        }
+
+        write_uvarint(&mut out, self.code.len() as u64);
+        for instr in &self.code {
+            out.push(instr.op.byte());
+            match &instr.arg {
+                None => {}
+                Some(Arg::Int(i)) => write_ivarint(&mut out, *i),
+                Some(Arg::Float(f)) => out.extend_from_slice(&f.to_le_bytes()),
+                Some(Arg::Str(s)) => write_str(&mut out, s),
+                Some(Arg::Builtin(name, argc)) => {
+                    write_str(&mut out, name);
+                    write_uvarint(&mut out, *argc as u64);
+                }
+                Some(Arg::Switch(base, len)) => {
+                    write_ivarint(&mut out, *base);
+                    write_uvarint(&mut out, *len as u64);
+                }
+                Some(Arg::Closure(name, n_upvalues)) => {
+                    write_str(&mut out, name);
+                    write_uvarint(&mut out, *n_upvalues as u64);
+                }
+            }
+        }
+        out
+    }
+}
+
+/// `b"OMGB"` magic header identifying [`Compiler::to_bytes`]'s binary
+/// bytecode format, checked by both `main::load_bytecode` and
+/// [`disassemble`].
+pub const MAGIC: &[u8; 4] = b"OMGB";
+/// Bumped whenever the binary format's layout changes incompatibly.
+pub const FORMAT_VERSION: u8 = 1;
+
+/// The shape of the operand bytes that follow an opcode byte, for
+/// `main::load_bytecode` and [`disassemble`] to decode without needing the
+/// already-typed `Arg` a live `Compiler` has on hand while encoding.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum OperandShape {
+    None,
+    Int,
+    Float,
+    Str,
+    Builtin,
+    Switch,
+    Closure,
+}
+
+/// `&str`-based wrappers kept for `main::load_bytecode`/[`disassemble`],
+/// which only ever have a text mnemonic or a wire byte on hand, not a live
+/// `Op`; the [`Op`] enum above (and its `ops!` table) is the actual single
+/// source of truth these delegate to.
+pub fn opcode_byte(op: &str) -> u8 {
+    Op::from_name(op)
+        .unwrap_or_else(|| panic!("unknown opcode: {}", op))
+        .byte()
+}
+
+pub fn opcode_name(byte: u8) -> &'static str {
+    Op::from_byte(byte)
+        .unwrap_or_else(|| panic!("unknown opcode byte: {}", byte))
+        .name()
+}
+
+pub fn operand_shape(op: &str) -> OperandShape {
+    Op::from_name(op)
+        .unwrap_or_else(|| panic!("unknown opcode: {}", op))
+        .shape()
+}
+
+/// Write `v` as an unsigned LEB128 varint.
+pub fn write_uvarint(out: &mut Vec<u8>, mut v: u64) {
+    loop {
+        let byte = (v & 0x7f) as u8;
+        v >>= 7;
+        if v == 0 {
+            out.push(byte);
+            break;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+/// Read an unsigned LEB128 varint starting at `*pos`, advancing it past the
+/// bytes consumed.
+pub fn read_uvarint(bytes: &[u8], pos: &mut usize) -> u64 {
+    let mut result: u64 = 0;
+    let mut shift = 0;
+    loop {
+        let byte = bytes[*pos];
+        *pos += 1;
+        result |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            break;
+        }
+        shift += 7;
+    }
+    result
+}
+
+fn zigzag_encode(v: i64) -> u64 {
+    ((v << 1) ^ (v >> 63)) as u64
+}
+
+fn zigzag_decode(v: u64) -> i64 {
+    ((v >> 1) as i64) ^ -((v & 1) as i64)
+}
+
+/// Write `v` as a zigzag-encoded LEB128 varint, so negative operands (e.g.
+/// a literal `PUSH_INT -5`) don't blow up to the 10-byte worst case a plain
+/// unsigned varint would give them.
+pub fn write_ivarint(out: &mut Vec<u8>, v: i64) {
+    write_uvarint(out, zigzag_encode(v));
+}
+
+pub fn read_ivarint(bytes: &[u8], pos: &mut usize) -> i64 {
+    zigzag_decode(read_uvarint(bytes, pos))
+}
+
+/// Write `s` as a varint length prefix followed by its raw UTF-8 bytes.
+pub fn write_str(out: &mut Vec<u8>, s: &str) {
+    write_uvarint(out, s.len() as u64);
+    out.extend_from_slice(s.as_bytes());
+}
+
+pub fn read_str(bytes: &[u8], pos: &mut usize) -> String {
+    let len = read_uvarint(bytes, pos) as usize;
+    let s = std::str::from_utf8(&bytes[*pos..*pos + len])
+        .expect("non-UTF-8 string operand")
+        .to_string();
+    *pos += len;
+    s
+}
+
+/// Walk a [`Compiler::to_bytes`] image and reproduce `to_string`'s textual
+/// listing, decoding each opcode's operand via [`operand_shape`]. Gated
+/// behind the `disasm` feature so ordinary binaries — which only ever read
+/// bytecode, never print it — don't pay for the formatting code.
+#[cfg(feature = "disasm")]
+pub fn disassemble(bytes: &[u8]) -> String {
+    assert_eq!(&bytes[0..4], MAGIC, "not an OMGB binary bytecode image");
+    assert_eq!(bytes[4], FORMAT_VERSION, "unsupported bytecode version");
+    let mut pos = 5usize;
+    let mut lines: Vec<String> = Vec::new();
+
+    let n_consts = read_uvarint(bytes, &mut pos);
+    for _ in 0..n_consts {
+        let tag = bytes[pos];
+        pos += 1;
+        let c = match tag {
+            0 => Arg::Int(read_ivarint(bytes, &mut pos)),
+            1 => {
+                let mut buf = [0u8; 8];
+                buf.copy_from_slice(&bytes[pos..pos + 8]);
+                pos += 8;
+                Arg::Float(f64::from_le_bytes(buf))
+            }
+            2 => Arg::Str(read_str(bytes, &mut pos)),
+            _ => panic!("malformed binary bytecode: bad constant tag {}", tag),
+        };
+        lines.push(format_const_line(&c));
+    }
+
+    let n_funcs = read_uvarint(bytes, &mut pos);
+    for _ in 0..n_funcs {
+        let name = read_str(bytes, &mut pos);
+        let param_count = read_uvarint(bytes, &mut pos);
+        let params: Vec<String> = (0..param_count).map(|_| read_str(bytes, &mut pos)).collect();
+        let address = read_uvarint(bytes, &mut pos);
+        let line = read_uvarint(bytes, &mut pos);
+        lines.push(format!("FUNC {} {} {} {} {}", name, param_count, params.join(" "), address, line));
+    }
+
+    let n_debug = read_uvarint(bytes, &mut pos);
+    for _ in 0..n_debug {
+        let start = read_uvarint(bytes, &mut pos);
+        let line = read_uvarint(bytes, &mut pos);
+        lines.push(format!("LINE {} {}", start, line));
+    }
+
+    let n_instrs = read_uvarint(bytes, &mut pos);
+    for _ in 0..n_instrs {
+        let op = opcode_name(bytes[pos]);
+        pos += 1;
+        match operand_shape(op) {
+            OperandShape::None => lines.push(op.to_string()),
+            OperandShape::Int => lines.push(format!("{} {}", op, read_ivarint(bytes, &mut pos))),
+            OperandShape::Float => {
+                let mut buf = [0u8; 8];
+                buf.copy_from_slice(&bytes[pos..pos + 8]);
+                pos += 8;
+                lines.push(format!("{} {:?}", op, f64::from_le_bytes(buf)));
+            }
+            OperandShape::Str if op == "PUSH_STR" => {
+                lines.push(format!("PUSH_STR {}", serde_json::to_string(&read_str(bytes, &mut pos)).unwrap()));
+            }
+            OperandShape::Str => lines.push(format!("{} {}", op, read_str(bytes, &mut pos))),
+            OperandShape::Builtin => {
+                let name = read_str(bytes, &mut pos);
+                let argc = read_uvarint(bytes, &mut pos);
+                lines.push(format!("BUILTIN {} {}", name, argc));
+            }
+            OperandShape::Switch => {
+                let base = read_ivarint(bytes, &mut pos);
+                let len = read_uvarint(bytes, &mut pos);
+                lines.push(format!("SWITCH {} {}", base, len));
+            }
+            OperandShape::Closure => {
+                let name = read_str(bytes, &mut pos);
+                let n_upvalues = read_uvarint(bytes, &mut pos);
+                lines.push(format!("MAKE_CLOSURE {} {}", name, n_upvalues));
+            }
+        }
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fold_constants_collapses_literal_arithmetic() {
+        let mut c = Compiler::new();
+        c.emit_const(Op::PushConst, Arg::Int(2));
+        c.emit_const(Op::PushConst, Arg::Int(3));
+        c.emit(Op::Add, None);
+        c.emit(Op::Halt, None);
+
+        assert!(c.fold_constants());
+        let text = c.to_string();
+        assert!(text.contains("CONST INT 5"), "expected folded literal 5, got:\n{text}");
+        assert!(!text.contains("ADD"), "ADD should have been folded away, got:\n{text}");
+    }
+
+    #[test]
+    fn thread_jumps_collapses_jump_to_jump_chains() {
+        let mut c = Compiler::new();
+        c.emit(Op::Jump, Some(Arg::Int(1))); // idx 0 -> idx 1
+        c.emit(Op::Jump, Some(Arg::Int(2))); // idx 1 -> idx 2
+        c.emit(Op::Halt, None); // idx 2
+
+        assert!(c.thread_jumps());
+        assert!(
+            matches!(c.code[0].arg, Some(Arg::Int(2))),
+            "idx 0 should thread straight through to idx 2"
+        );
+    }
+
+    #[test]
+    fn eliminate_dead_code_drops_unreachable_instructions_after_unconditional_jump() {
+        let mut c = Compiler::new();
+        c.emit(Op::Jump, Some(Arg::Int(2))); // idx 0, jumps past idx 1
+        c.emit_const(Op::PushConst, Arg::Int(99)); // idx 1, unreachable
+        c.emit(Op::Halt, None); // idx 2, a jump target
+
+        assert!(c.eliminate_dead_code());
+        assert_eq!(c.code.len(), 2, "the unreachable PUSH_CONST should have been dropped");
+        assert!(
+            !c.to_string().lines().any(|l| l.starts_with("PUSH_CONST")),
+            "dead PUSH_CONST 99 should have been removed"
+        );
+    }
+
+    #[test]
+    fn nested_proc_capturing_outer_variable_emits_closure_and_upvalue_load() {
+        let source = "proc outer() {\n    alloc y := 2\n    proc inner() {\n        emit y\n    }\n}\n";
+        let ast = crate::parser::parse(source).expect("source should parse");
+        let mut compiler = Compiler::new();
+        compiler.compile(&ast);
+        let text = compiler.to_string();
+        assert!(
+            text.contains("MAKE_CLOSURE inner 1"),
+            "expected a MAKE_CLOSURE for inner capturing 1 upvalue, got:\n{text}"
+        );
+        assert!(
+            text.contains("LOAD_UPVALUE 0"),
+            "expected inner's body to read its capture by index, got:\n{text}"
+        );
+    }
+
+    #[test]
+    fn self_recursive_nested_closure_does_not_capture_its_own_name() {
+        // `fact` captures `step` (a genuine upvalue) and also refers to
+        // itself by name — both as a recursive call and passed by value.
+        // Neither reference should be rewritten to LOAD_UPVALUE, since
+        // `fact` isn't bound to anything yet at the point the closure's
+        // capture loads run.
+        let source = "proc outer() {\n    alloc step := 1\n    proc fact(n) {\n        if n <= 1 {\n            return step\n        }\n        emit fact\n        return n * fact(n - step)\n    }\n}\n";
+        let ast = crate::parser::parse(source).expect("source should parse");
+        let mut compiler = Compiler::new();
+        compiler.compile(&ast);
+        let text = compiler.to_string();
+        assert!(
+            text.contains("MAKE_CLOSURE fact 1"),
+            "expected fact to capture exactly 1 upvalue (step), got:\n{text}"
+        );
+        assert!(
+            !text.contains("LOAD_UPVALUE 1"),
+            "fact's own name must not be captured as a second upvalue, got:\n{text}"
+        );
+    }
+}
@@ -0,0 +1,277 @@
+// Hand-rolled lexer for the bootstrap OMG front end.
+//
+// There's no incremental API, since its only consumers are a one-shot
+// `tokenize(source)` call from `parser::parse` and the REPL's
+// re-tokenize-on-every-keystroke usage.
+
+/// One lexical token.
+#[derive(Clone)]
+pub enum Token {
+    Symbol(String),
+    Kw(String),
+    Ident(String),
+    Number(i64),
+    Float(f64),
+    Bool(bool),
+    Str(String),
+}
+
+/// A [`Token`] paired with the 1-indexed `(line, col)` of its first
+/// character, so [`crate::parser`] can attach a source location to a parse
+/// error instead of just reporting "unexpected token".
+#[derive(Clone)]
+pub struct PosToken {
+    pub tok: Token,
+    pub line: usize,
+    pub col: usize,
+}
+
+fn is_digit(ch: char) -> bool {
+    ch >= '0' && ch <= '9'
+}
+
+fn is_alpha(ch: char) -> bool {
+    (ch >= 'a' && ch <= 'z') || (ch >= 'A' && ch <= 'Z') || ch == '_'
+}
+
+fn is_alnum(ch: char) -> bool {
+    is_alpha(ch) || is_digit(ch)
+}
+
+/// Read an integer literal in `radix` (2, 8, or 16), allowing `_` as a
+/// visual digit separator (`1_000`, `0xFF_FF`). Stops at the first
+/// character that's neither a digit of `radix` nor `_`.
+fn read_radix(src: &[char], mut i: usize, radix: u32) -> (i64, usize) {
+    let mut digits = String::new();
+    while i < src.len() && (src[i].to_digit(radix).is_some() || src[i] == '_') {
+        if src[i] != '_' {
+            digits.push(src[i]);
+        }
+        i += 1;
+    }
+    (i64::from_str_radix(&digits, radix).unwrap_or(0), i)
+}
+
+/// Read a decimal integer or float literal starting at `i`, allowing `_` as
+/// a digit separator and an optional `.fraction` and `e`/`E` exponent. Only
+/// becomes a [`Token::Float`] if a `.` is followed by a digit (so a bare
+/// trailing `.` stays the `.` attribute-access symbol) or a recognized
+/// exponent is present.
+fn read_decimal_or_float(src: &[char], mut i: usize) -> (Token, usize) {
+    let mut text = String::new();
+    while i < src.len() && (is_digit(src[i]) || src[i] == '_') {
+        if src[i] != '_' {
+            text.push(src[i]);
+        }
+        i += 1;
+    }
+
+    let mut is_float = false;
+    if i + 1 < src.len() && src[i] == '.' && is_digit(src[i + 1]) {
+        is_float = true;
+        text.push('.');
+        i += 1;
+        while i < src.len() && (is_digit(src[i]) || src[i] == '_') {
+            if src[i] != '_' {
+                text.push(src[i]);
+            }
+            i += 1;
+        }
+    }
+
+    if i < src.len() && (src[i] == 'e' || src[i] == 'E') {
+        let mut k = i + 1;
+        let mut exponent = String::new();
+        exponent.push('e');
+        if k < src.len() && (src[k] == '+' || src[k] == '-') {
+            exponent.push(src[k]);
+            k += 1;
+        }
+        if k < src.len() && is_digit(src[k]) {
+            while k < src.len() && (is_digit(src[k]) || src[k] == '_') {
+                if src[k] != '_' {
+                    exponent.push(src[k]);
+                }
+                k += 1;
+            }
+            text.push_str(&exponent);
+            i = k;
+            is_float = true;
+        }
+    }
+
+    if is_float {
+        (Token::Float(text.parse::<f64>().unwrap_or(0.0)), i)
+    } else {
+        (Token::Number(text.parse::<i64>().unwrap_or(0)), i)
+    }
+}
+
+fn read_ident(src: &[char], mut i: usize) -> (String, usize) {
+    let mut s = String::new();
+    while i < src.len() && is_alnum(src[i]) {
+        s.push(src[i]);
+        i += 1;
+    }
+    (s, i)
+}
+
+/// Keywords recognized by [`tokenize`]; kept alongside it so a REPL helper
+/// (or anything else wanting "is this word a keyword") has one place to ask,
+/// rather than re-deriving the set from `tokenize`'s match arm.
+pub const KEYWORDS: &[&str] = &[
+    "alloc", "emit", "proc", "return", "if", "else", "elif", "loop", "break", "and", "or",
+    "facts", "import", "as", "quote", "match", "case",
+];
+
+/// Count newlines in `src[..idx]` to turn a flat character index into a
+/// 1-indexed `(line, col)` pair. `tokenize` only has a handful of tokens per
+/// line in practice, so the O(idx) rescan per token is not worth avoiding
+/// with a running line/col counter threaded through every branch below.
+fn line_col(src: &[char], idx: usize) -> (usize, usize) {
+    let mut line = 1;
+    let mut col = 1;
+    for &c in &src[..idx] {
+        if c == '\n' {
+            line += 1;
+            col = 1;
+        } else {
+            col += 1;
+        }
+    }
+    (line, col)
+}
+
+fn push(tokens: &mut Vec<PosToken>, src: &[char], start: usize, tok: Token) {
+    let (line, col) = line_col(src, start);
+    tokens.push(PosToken { tok, line, col });
+}
+
+/// Turn `source` into a flat token stream. Strips the optional `;;;omg`
+/// shebang-style header line if present, skips `#`-line and `/* */` block
+/// comments, recognizes `0b`/`0x`/`0o`-prefixed integer literals and
+/// `_` digit separators (`1_000_000`) alongside plain decimal ones, plus
+/// decimal float literals with an optional exponent (`1.5`, `2.0e-3`), and
+/// the two-char `**` (exponentiation) and `//` (floor division) symbols
+/// ahead of the single-char `*`/`/` ones.
+pub fn tokenize(source: &str) -> Vec<PosToken> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0usize;
+    let mut src = &chars[..];
+    if chars.len() >= 6
+        && chars[0] == ';'
+        && chars[1] == ';'
+        && chars[2] == ';'
+        && chars[3] == 'o'
+        && chars[4] == 'm'
+        && chars[5] == 'g'
+    {
+        i = 6;
+        if i < chars.len() && chars[i] == '\r' {
+            i += 1;
+        }
+        if i < chars.len() && chars[i] == '\n' {
+            i += 1;
+        }
+        src = &chars[i..];
+        i = 0;
+    }
+    let src_len = src.len();
+    while i < src_len {
+        let start = i;
+        let c = src[i];
+        if c == ' ' || c == '\t' || c == '\r' || c == '\n' {
+            i += 1;
+        } else if c == '#' {
+            while i < src_len && src[i] != '\n' {
+                i += 1;
+            }
+        } else if c == ':' && i + 1 < src_len && src[i + 1] == '=' {
+            push(&mut tokens, src, start, Token::Symbol(":=".to_string()));
+            i += 2;
+        } else if c == ':' {
+            push(&mut tokens, src, start, Token::Symbol(":".to_string()));
+            i += 1;
+        } else if c == '=' && i + 1 < src_len && src[i + 1] == '=' {
+            push(&mut tokens, src, start, Token::Symbol("==".to_string()));
+            i += 2;
+        } else if c == '!' && i + 1 < src_len && src[i + 1] == '=' {
+            push(&mut tokens, src, start, Token::Symbol("!=".to_string()));
+            i += 2;
+        } else if c == '<' && i + 1 < src_len && src[i + 1] == '=' {
+            push(&mut tokens, src, start, Token::Symbol("<=".to_string()));
+            i += 2;
+        } else if c == '>' && i + 1 < src_len && src[i + 1] == '=' {
+            push(&mut tokens, src, start, Token::Symbol(">=".to_string()));
+            i += 2;
+        } else if c == '<' && i + 1 < src_len && src[i + 1] == '<' {
+            push(&mut tokens, src, start, Token::Symbol("<<".to_string()));
+            i += 2;
+        } else if c == '>' && i + 1 < src_len && src[i + 1] == '>' {
+            push(&mut tokens, src, start, Token::Symbol(">>".to_string()));
+            i += 2;
+        } else if c == '/' && i + 1 < src_len && src[i + 1] == '*' {
+            i += 2;
+            while i + 1 < src_len && !(src[i] == '*' && src[i + 1] == '/') {
+                i += 1;
+            }
+            i += 2;
+        } else if c == '*' && i + 1 < src_len && src[i + 1] == '*' {
+            push(&mut tokens, src, start, Token::Symbol("**".to_string()));
+            i += 2;
+        } else if c == '/' && i + 1 < src_len && src[i + 1] == '/' {
+            push(&mut tokens, src, start, Token::Symbol("//".to_string()));
+            i += 2;
+        } else if [ '(', ')', '{', '}', ',', '+', '-', '*', '/', '%', '<', '>', '[', ']', '&', '|', '^', '~', '.' ].contains(&c) {
+            push(&mut tokens, src, start, Token::Symbol(c.to_string()));
+            i += 1;
+        } else if c == '0' && i + 1 < src_len && (src[i + 1] == 'b' || src[i + 1] == 'B') {
+            i += 2;
+            let (num, ni) = read_radix(src, i, 2);
+            push(&mut tokens, src, start, Token::Number(num));
+            i = ni;
+        } else if c == '0' && i + 1 < src_len && (src[i + 1] == 'x' || src[i + 1] == 'X') {
+            i += 2;
+            let (num, ni) = read_radix(src, i, 16);
+            push(&mut tokens, src, start, Token::Number(num));
+            i = ni;
+        } else if c == '0' && i + 1 < src_len && (src[i + 1] == 'o' || src[i + 1] == 'O') {
+            i += 2;
+            let (num, ni) = read_radix(src, i, 8);
+            push(&mut tokens, src, start, Token::Number(num));
+            i = ni;
+        } else if is_digit(c) {
+            let (tok, ni) = read_decimal_or_float(src, i);
+            push(&mut tokens, src, start, tok);
+            i = ni;
+        } else if c == '"' {
+            i += 1;
+            let mut s = String::new();
+            while i < src_len && src[i] != '"' {
+                if src[i] == '\\' && i + 1 < src_len && src[i + 1] == 'n' {
+                    s.push('\n');
+                    i += 2;
+                } else {
+                    s.push(src[i]);
+                    i += 1;
+                }
+            }
+            i += 1;
+            push(&mut tokens, src, start, Token::Str(s));
+        } else {
+            let (word, ni) = read_ident(src, i);
+            i = ni;
+            if KEYWORDS.contains(&word.as_str()) {
+                push(&mut tokens, src, start, Token::Kw(word));
+            } else {
+                match word.as_str() {
+                    "true" => push(&mut tokens, src, start, Token::Bool(true)),
+                    "false" => push(&mut tokens, src, start, Token::Bool(false)),
+                    _ => push(&mut tokens, src, start, Token::Ident(word)),
+                }
+            }
+        }
+    }
+    tokens
+}
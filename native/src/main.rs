@@ -2,24 +2,102 @@ use std::cell::RefCell;
 use std::collections::HashMap;
 use std::env;
 use std::fs;
+use std::io::Write;
 use std::rc::Rc;
 use serde_json;
 
+mod compiler;
+mod lexer;
+mod parser;
+mod repl;
+
 /// Representation of a compiled function.
 #[derive(Clone)]
 struct Function {
     params: Vec<String>,
     address: usize,
+    /// Source line of the defining `proc` — see `line_for_pc`'s `line_table`
+    /// for the equivalent per-instruction mapping, and `function_for_pc` for
+    /// how a fault's call stack resolves a `pc` back to a function at all.
+    line: u32,
+}
+
+/// One run of consecutive instructions sharing a source line — `(start pc,
+/// line)`, sorted ascending by `start`, as written by
+/// `compiler::Compiler::to_string`'s `LINE` section (or its binary
+/// equivalent). A run covers every `pc` from its `start` up to (but not
+/// including) the next run's `start`, or the end of the program for the
+/// last run — see `line_for_pc`.
+type LineTable = Vec<(usize, u32)>;
+
+/// Binary-search `table` for the source line covering instruction `pc` — the
+/// run with the greatest `start <= pc`. `None` if `pc` precedes every run
+/// (or `table` is empty, e.g. the synthetic bytecode `apply()` builds on the
+/// fly, which has no source line to report).
+fn line_for_pc(table: &LineTable, pc: usize) -> Option<u32> {
+    match table.binary_search_by_key(&pc, |&(start, _)| start) {
+        Ok(i) => Some(table[i].1),
+        Err(0) => None,
+        Err(i) => Some(table[i - 1].1),
+    }
+}
+
+/// Resolve the function whose body contains `pc` — the one with the
+/// greatest `address <= pc` among `funcs` — or `None` if `pc` precedes every
+/// function's body (i.e. it's in top-level code, not a `proc`).
+fn function_for_pc(funcs: &HashMap<String, Function>, pc: usize) -> Option<&str> {
+    funcs
+        .iter()
+        .filter(|(_, f)| f.address <= pc)
+        .max_by_key(|(_, f)| f.address)
+        .map(|(name, _)| name.as_str())
+}
+
+/// Render a best-effort call stack for a VM fault at `pc`: that frame first
+/// (innermost), then each return address on `ret_stack` from most to least
+/// recent — printed by `Instr::Assert`'s failure path to turn an opaque
+/// "Assertion failed" into something diagnosable against `interpreter.omg`.
+fn render_call_stack(
+    funcs: &HashMap<String, Function>,
+    line_table: &LineTable,
+    pc: usize,
+    ret_stack: &[usize],
+) -> String {
+    let mut frames = vec![pc];
+    frames.extend(ret_stack.iter().rev().copied());
+    frames
+        .into_iter()
+        .map(|p| {
+            let line = line_for_pc(line_table, p)
+                .map(|l| l.to_string())
+                .unwrap_or_else(|| "?".to_string());
+            let func = function_for_pc(funcs, p).unwrap_or("<script>");
+            format!("  at line {} in {}", line, func)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A function value that closed over some of its defining scope's variables
+/// — built by `Instr::MakeClosure` out of a `Function`'s address/params plus
+/// a snapshot of the captured names' current values, and called the same
+/// way a plain `Value::Str` function reference is, via `Instr::CallValue`.
+struct Closure {
+    address: usize,
+    params: Vec<String>,
+    captures: Vec<Value>,
 }
 
 /// Value type for the VM stack.
 #[derive(Clone)]
 enum Value {
     Int(i64),
+    Float(f64),
     Str(String),
     Bool(bool),
     List(Rc<RefCell<Vec<Value>>>),
     Dict(Rc<RefCell<HashMap<String, Value>>>),
+    Closure(Rc<Closure>),
     None,
 }
 
@@ -27,6 +105,7 @@ impl Value {
     fn as_int(&self) -> i64 {
         match self {
             Value::Int(i) => *i,
+            Value::Float(f) => *f as i64,
             Value::Str(s) => s.parse::<i64>().unwrap_or(0),
             Value::Bool(b) => {
                 if *b {
@@ -37,22 +116,47 @@ impl Value {
             }
             Value::List(l) => l.borrow().len() as i64,
             Value::Dict(d) => d.borrow().len() as i64,
+            Value::Closure(_) => 0,
             Value::None => 0,
         }
     }
+    /// Widen to `f64` for arithmetic/comparison promotion — any numeric op
+    /// with at least one `Value::Float` operand computes in `f64` rather
+    /// than truncating it down to `as_int`.
+    fn as_float(&self) -> f64 {
+        match self {
+            Value::Int(i) => *i as f64,
+            Value::Float(f) => *f,
+            Value::Str(s) => s.parse::<f64>().unwrap_or(0.0),
+            Value::Bool(b) => {
+                if *b {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            Value::List(l) => l.borrow().len() as f64,
+            Value::Dict(d) => d.borrow().len() as f64,
+            Value::Closure(_) => 0.0,
+            Value::None => 0.0,
+        }
+    }
     fn as_bool(&self) -> bool {
         match self {
             Value::Bool(b) => *b,
             Value::Int(i) => *i != 0,
+            Value::Float(f) => *f != 0.0,
             Value::Str(s) => !s.is_empty(),
             Value::List(l) => !l.borrow().is_empty(),
             Value::Dict(d) => !d.borrow().is_empty(),
+            Value::Closure(_) => true,
             Value::None => false,
         }
     }
     fn to_string(&self) -> String {
         match self {
             Value::Int(i) => i.to_string(),
+            Value::Float(f) => f.to_string(),
             Value::Str(s) => s.clone(),
             Value::Bool(b) => b.to_string(),
             Value::List(list) => {
@@ -71,6 +175,7 @@ impl Value {
                     .collect();
                 format!("{{{}}}", inner.join(", "))
             }
+            Value::Closure(_) => "<closure>".to_string(),
             Value::None => "".to_string(),
         }
     }
@@ -79,6 +184,7 @@ impl Value {
 /// Instruction set for the OMG stack VM.
 enum Instr {
     PushInt(i64),
+    PushFloat(f64),
     PushStr(String),
     PushBool(bool),
     BuildList(usize),
@@ -90,6 +196,8 @@ enum Instr {
     Mul,
     Div,
     Mod,
+    Pow,
+    FloorDiv,
     Eq,
     Ne,
     Lt,
@@ -123,18 +231,71 @@ enum Instr {
     Import,
     Assert,
     CallValue(usize),
+    Switch(i64, usize),
+    MakeClosure(String, usize),
+    LoadUpvalue(usize),
+}
+
+/// A decoded constant-pool entry, as read back from a `CONST ` line (text
+/// format) or the pool section of [`compiler::Compiler::to_bytes`] (binary
+/// format) — `PUSH_CONST`/`LOAD`/`STORE`/`ATTR`/`STORE_ATTR`/`CALL`/`TCALL`
+/// operands are indices into a `Vec` of these rather than inline values.
+enum Const {
+    Int(i64),
+    Float(f64),
+    Str(String),
+}
+
+/// Resolve constant-pool index `idx` to the string it names, for the
+/// identifier-bearing opcodes (`LOAD`, `STORE`, `ATTR`, `STORE_ATTR`,
+/// `CALL`, `TCALL`) whose operand is always a `Const::Str`.
+fn const_str(constants: &[Const], idx: i64) -> String {
+    match constants.get(idx as usize) {
+        Some(Const::Str(s)) => s.clone(),
+        _ => panic!("malformed bytecode: bad string constant index {}", idx),
+    }
 }
 
-/// Parse a textual bytecode file into instructions.
-fn parse_bytecode(src: &str) -> (Vec<Instr>, HashMap<String, Function>) {
+/// Resolve constant-pool index `idx` to the `Push*` instruction it names,
+/// for `PUSH_CONST`.
+fn const_push_instr(constants: &[Const], idx: i64) -> Instr {
+    match constants.get(idx as usize) {
+        Some(Const::Int(i)) => Instr::PushInt(*i),
+        Some(Const::Float(f)) => Instr::PushFloat(*f),
+        Some(Const::Str(s)) => Instr::PushStr(s.clone()),
+        None => panic!("malformed bytecode: bad constant index {}", idx),
+    }
+}
+
+/// Parse a textual bytecode file into instructions, its function table, and
+/// its source-line table (see `LineTable`).
+fn parse_bytecode(src: &str) -> (Vec<Instr>, HashMap<String, Function>, LineTable) {
     let mut code = Vec::new();
     let mut funcs: HashMap<String, Function> = HashMap::new();
+    let mut line_table: LineTable = Vec::new();
+    let mut constants: Vec<Const> = Vec::new();
     for line in src.lines() {
         let trimmed = line.trim_start();
         if trimmed.is_empty() {
             continue;
         }
-        if let Some(rest) = trimmed.strip_prefix("FUNC ") {
+        if let Some(rest) = trimmed.strip_prefix("CONST ") {
+            let mut parts = rest.splitn(2, ' ');
+            let kind = parts.next().unwrap_or("");
+            let value = parts.next().unwrap_or("");
+            match kind {
+                "INT" => constants.push(Const::Int(value.parse().unwrap_or(0))),
+                "FLOAT" => constants.push(Const::Float(value.parse().unwrap_or(0.0))),
+                "STR" => {
+                    constants.push(Const::Str(serde_json::from_str(value).unwrap_or_default()))
+                }
+                _ => {}
+            }
+        } else if let Some(rest) = trimmed.strip_prefix("PUSH_CONST ") {
+            if let Ok(idx) = rest.trim().parse::<i64>() {
+                code.push(const_push_instr(&constants, idx));
+            }
+        } else if let Some(rest) = trimmed.strip_prefix("FUNC ") {
             let parts: Vec<&str> = rest.split_whitespace().collect();
             if parts.len() >= 3 {
                 let name = parts[0].to_string();
@@ -145,12 +306,24 @@ fn parse_bytecode(src: &str) -> (Vec<Instr>, HashMap<String, Function>) {
                     .collect::<Vec<_>>();
                 let addr_idx = 2 + param_count;
                 let address: usize = parts[addr_idx].parse().unwrap_or(0);
-                funcs.insert(name, Function { params, address });
+                let line: u32 = parts.get(addr_idx + 1).and_then(|s| s.parse().ok()).unwrap_or(0);
+                funcs.insert(name, Function { params, address, line });
+            }
+        } else if let Some(rest) = trimmed.strip_prefix("LINE ") {
+            let parts: Vec<&str> = rest.split_whitespace().collect();
+            if parts.len() == 2 {
+                if let (Ok(start), Ok(line)) = (parts[0].parse::<usize>(), parts[1].parse::<u32>()) {
+                    line_table.push((start, line));
+                }
             }
         } else if let Some(rest) = trimmed.strip_prefix("PUSH_INT ") {
             if let Ok(v) = rest.parse::<i64>() {
                 code.push(Instr::PushInt(v));
             }
+        } else if let Some(rest) = trimmed.strip_prefix("PUSH_FLOAT ") {
+            if let Ok(v) = rest.parse::<f64>() {
+                code.push(Instr::PushFloat(v));
+            }
         } else if let Some(rest) = trimmed.strip_prefix("PUSH_STR ") {
             if let Ok(s) = serde_json::from_str::<String>(rest) {
                 code.push(Instr::PushStr(s));
@@ -167,9 +340,13 @@ fn parse_bytecode(src: &str) -> (Vec<Instr>, HashMap<String, Function>) {
                 code.push(Instr::BuildDict(n));
             }
         } else if let Some(rest) = trimmed.strip_prefix("LOAD ") {
-            code.push(Instr::Load(rest.to_string()));
+            if let Ok(idx) = rest.trim().parse::<i64>() {
+                code.push(Instr::Load(const_str(&constants, idx)));
+            }
         } else if let Some(rest) = trimmed.strip_prefix("STORE ") {
-            code.push(Instr::Store(rest.to_string()));
+            if let Ok(idx) = rest.trim().parse::<i64>() {
+                code.push(Instr::Store(const_str(&constants, idx)));
+            }
         } else if trimmed == "ADD" {
             code.push(Instr::Add);
         } else if trimmed == "SUB" {
@@ -180,6 +357,10 @@ fn parse_bytecode(src: &str) -> (Vec<Instr>, HashMap<String, Function>) {
             code.push(Instr::Div);
         } else if trimmed == "MOD" {
             code.push(Instr::Mod);
+        } else if trimmed == "POW" {
+            code.push(Instr::Pow);
+        } else if trimmed == "FLOORDIV" {
+            code.push(Instr::FloorDiv);
         } else if trimmed == "EQ" {
             code.push(Instr::Eq);
         } else if trimmed == "NE" {
@@ -217,9 +398,13 @@ fn parse_bytecode(src: &str) -> (Vec<Instr>, HashMap<String, Function>) {
         } else if trimmed == "STORE_INDEX" {
             code.push(Instr::StoreIndex);
         } else if let Some(rest) = trimmed.strip_prefix("ATTR ") {
-            code.push(Instr::Attr(rest.to_string()));
+            if let Ok(idx) = rest.trim().parse::<i64>() {
+                code.push(Instr::Attr(const_str(&constants, idx)));
+            }
         } else if let Some(rest) = trimmed.strip_prefix("STORE_ATTR ") {
-            code.push(Instr::StoreAttr(rest.to_string()));
+            if let Ok(idx) = rest.trim().parse::<i64>() {
+                code.push(Instr::StoreAttr(const_str(&constants, idx)));
+            }
         } else if trimmed == "IMPORT" {
             code.push(Instr::Import);
         } else if trimmed == "ASSERT" {
@@ -236,10 +421,21 @@ fn parse_bytecode(src: &str) -> (Vec<Instr>, HashMap<String, Function>) {
             if let Ok(t) = rest.parse::<usize>() {
                 code.push(Instr::Jump(t));
             }
+        } else if let Some(rest) = trimmed.strip_prefix("SWITCH ") {
+            let parts: Vec<&str> = rest.split_whitespace().collect();
+            if parts.len() == 2 {
+                if let (Ok(base), Ok(len)) = (parts[0].parse::<i64>(), parts[1].parse::<usize>()) {
+                    code.push(Instr::Switch(base, len));
+                }
+            }
         } else if let Some(rest) = trimmed.strip_prefix("CALL ") {
-            code.push(Instr::Call(rest.to_string()));
+            if let Ok(idx) = rest.trim().parse::<i64>() {
+                code.push(Instr::Call(const_str(&constants, idx)));
+            }
         } else if let Some(rest) = trimmed.strip_prefix("TCALL ") {
-            code.push(Instr::TailCall(rest.to_string()));
+            if let Ok(idx) = rest.trim().parse::<i64>() {
+                code.push(Instr::TailCall(const_str(&constants, idx)));
+            }
         } else if let Some(rest) = trimmed.strip_prefix("BUILTIN ") {
             let parts: Vec<&str> = rest.split_whitespace().collect();
             if parts.len() == 2 {
@@ -247,6 +443,17 @@ fn parse_bytecode(src: &str) -> (Vec<Instr>, HashMap<String, Function>) {
                     code.push(Instr::CallBuiltin(parts[0].to_string(), argc));
                 }
             }
+        } else if let Some(rest) = trimmed.strip_prefix("MAKE_CLOSURE ") {
+            let parts: Vec<&str> = rest.split_whitespace().collect();
+            if parts.len() == 2 {
+                if let Ok(n_upvalues) = parts[1].parse::<usize>() {
+                    code.push(Instr::MakeClosure(parts[0].to_string(), n_upvalues));
+                }
+            }
+        } else if let Some(rest) = trimmed.strip_prefix("LOAD_UPVALUE ") {
+            if let Ok(idx) = rest.trim().parse::<usize>() {
+                code.push(Instr::LoadUpvalue(idx));
+            }
         } else if trimmed == "RET" {
             code.push(Instr::Ret);
         } else if trimmed == "EMIT" {
@@ -259,11 +466,239 @@ fn parse_bytecode(src: &str) -> (Vec<Instr>, HashMap<String, Function>) {
             code.push(Instr::PushNone);
         }
     }
-    (code, funcs)
+    (code, funcs, line_table)
+}
+
+/// An operand already decoded off the byte stream, generic over the
+/// [`compiler::OperandShape`] it came from — a stepping stone between
+/// `compiler::operand_shape`'s shape lookup and the per-opcode `Instr` it
+/// gets folded into by `decode_instr`.
+enum DecodedOperand {
+    None,
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Builtin(String, usize),
+    Switch(i64, usize),
+    Closure(String, usize),
+}
+
+fn decode_operand(op: &str, bytes: &[u8], pos: &mut usize) -> DecodedOperand {
+    match compiler::operand_shape(op) {
+        compiler::OperandShape::None => DecodedOperand::None,
+        compiler::OperandShape::Int => DecodedOperand::Int(compiler::read_ivarint(bytes, pos)),
+        compiler::OperandShape::Float => {
+            let mut buf = [0u8; 8];
+            buf.copy_from_slice(&bytes[*pos..*pos + 8]);
+            *pos += 8;
+            DecodedOperand::Float(f64::from_le_bytes(buf))
+        }
+        compiler::OperandShape::Str => DecodedOperand::Str(compiler::read_str(bytes, pos)),
+        compiler::OperandShape::Builtin => {
+            let name = compiler::read_str(bytes, pos);
+            let argc = compiler::read_uvarint(bytes, pos) as usize;
+            DecodedOperand::Builtin(name, argc)
+        }
+        compiler::OperandShape::Switch => {
+            let base = compiler::read_ivarint(bytes, pos);
+            let len = compiler::read_uvarint(bytes, pos) as usize;
+            DecodedOperand::Switch(base, len)
+        }
+        compiler::OperandShape::Closure => {
+            let name = compiler::read_str(bytes, pos);
+            let n_upvalues = compiler::read_uvarint(bytes, pos) as usize;
+            DecodedOperand::Closure(name, n_upvalues)
+        }
+    }
 }
 
-/// Execute bytecode on a stack-based virtual machine.
-fn run(code: &[Instr], funcs: &HashMap<String, Function>, program_args: &[String]) {
+/// Fold a mnemonic and its already-decoded operand into the `Instr` it
+/// names — the binary-format counterpart of [`parse_bytecode`]'s
+/// `"OPNAME "` string-prefix dispatch. `constants` resolves the
+/// pool-index operands `PUSH_CONST`/`LOAD`/`STORE`/`ATTR`/`STORE_ATTR`/
+/// `CALL`/`TCALL` carry instead of an inline value.
+fn decode_instr(op: &str, operand: DecodedOperand, constants: &[Const]) -> Instr {
+    match (op, operand) {
+        ("PUSH_INT", DecodedOperand::Int(v)) => Instr::PushInt(v),
+        ("PUSH_FLOAT", DecodedOperand::Float(v)) => Instr::PushFloat(v),
+        ("PUSH_STR", DecodedOperand::Str(s)) => Instr::PushStr(s),
+        ("PUSH_CONST", DecodedOperand::Int(idx)) => const_push_instr(constants, idx),
+        ("PUSH_BOOL", DecodedOperand::Int(v)) => Instr::PushBool(v != 0),
+        ("PUSH_NONE", DecodedOperand::None) => Instr::PushNone,
+        ("BUILD_LIST", DecodedOperand::Int(v)) => Instr::BuildList(v as usize),
+        ("BUILD_DICT", DecodedOperand::Int(v)) => Instr::BuildDict(v as usize),
+        ("LOAD", DecodedOperand::Int(idx)) => Instr::Load(const_str(constants, idx)),
+        ("STORE", DecodedOperand::Int(idx)) => Instr::Store(const_str(constants, idx)),
+        ("ADD", DecodedOperand::None) => Instr::Add,
+        ("SUB", DecodedOperand::None) => Instr::Sub,
+        ("MUL", DecodedOperand::None) => Instr::Mul,
+        ("DIV", DecodedOperand::None) => Instr::Div,
+        ("MOD", DecodedOperand::None) => Instr::Mod,
+        ("POW", DecodedOperand::None) => Instr::Pow,
+        ("FLOORDIV", DecodedOperand::None) => Instr::FloorDiv,
+        ("EQ", DecodedOperand::None) => Instr::Eq,
+        ("NE", DecodedOperand::None) => Instr::Ne,
+        ("LT", DecodedOperand::None) => Instr::Lt,
+        ("LE", DecodedOperand::None) => Instr::Le,
+        ("GT", DecodedOperand::None) => Instr::Gt,
+        ("GE", DecodedOperand::None) => Instr::Ge,
+        ("BAND", DecodedOperand::None) => Instr::BAnd,
+        ("BOR", DecodedOperand::None) => Instr::BOr,
+        ("BXOR", DecodedOperand::None) => Instr::BXor,
+        ("SHL", DecodedOperand::None) => Instr::Shl,
+        ("SHR", DecodedOperand::None) => Instr::Shr,
+        ("AND", DecodedOperand::None) => Instr::And,
+        ("OR", DecodedOperand::None) => Instr::Or,
+        ("NOT", DecodedOperand::None) => Instr::Not,
+        ("NEG", DecodedOperand::None) => Instr::Neg,
+        ("INDEX", DecodedOperand::None) => Instr::Index,
+        ("SLICE", DecodedOperand::None) => Instr::Slice,
+        ("STORE_INDEX", DecodedOperand::None) => Instr::StoreIndex,
+        ("ATTR", DecodedOperand::Int(idx)) => Instr::Attr(const_str(constants, idx)),
+        ("STORE_ATTR", DecodedOperand::Int(idx)) => Instr::StoreAttr(const_str(constants, idx)),
+        ("IMPORT", DecodedOperand::None) => Instr::Import,
+        ("ASSERT", DecodedOperand::None) => Instr::Assert,
+        ("CALL_VALUE", DecodedOperand::Int(v)) => Instr::CallValue(v as usize),
+        ("JUMP", DecodedOperand::Int(v)) => Instr::Jump(v as usize),
+        ("JUMP_IF_FALSE", DecodedOperand::Int(v)) => Instr::JumpIfFalse(v as usize),
+        ("CALL", DecodedOperand::Int(idx)) => Instr::Call(const_str(constants, idx)),
+        ("TCALL", DecodedOperand::Int(idx)) => Instr::TailCall(const_str(constants, idx)),
+        ("BUILTIN", DecodedOperand::Builtin(name, argc)) => Instr::CallBuiltin(name, argc),
+        ("SWITCH", DecodedOperand::Switch(base, len)) => Instr::Switch(base, len),
+        ("MAKE_CLOSURE", DecodedOperand::Closure(name, n_upvalues)) => Instr::MakeClosure(name, n_upvalues),
+        ("LOAD_UPVALUE", DecodedOperand::Int(idx)) => Instr::LoadUpvalue(idx as usize),
+        ("RET", DecodedOperand::None) => Instr::Ret,
+        ("EMIT", DecodedOperand::None) => Instr::Emit,
+        ("HALT", DecodedOperand::None) => Instr::Halt,
+        ("POP", DecodedOperand::None) => Instr::Pop,
+        (op, _) => panic!("malformed binary bytecode: bad operand for {}", op),
+    }
+}
+
+/// Load a program from [`compiler::Compiler::to_bytes`]'s binary format —
+/// the binary counterpart of [`parse_bytecode`]'s text parser, read
+/// directly by the VM without ever formatting an instruction back to a
+/// string (that's what the separate, `disasm`-gated `compiler::disassemble`
+/// is for).
+fn load_bytecode(bytes: &[u8]) -> (Vec<Instr>, HashMap<String, Function>, LineTable) {
+    assert_eq!(&bytes[0..4], compiler::MAGIC, "not an OMGB binary bytecode image");
+    assert_eq!(bytes[4], compiler::FORMAT_VERSION, "unsupported bytecode version");
+    let mut pos = 5usize;
+
+    let mut constants: Vec<Const> = Vec::new();
+    let n_consts = compiler::read_uvarint(bytes, &mut pos);
+    for _ in 0..n_consts {
+        let tag = bytes[pos];
+        pos += 1;
+        constants.push(match tag {
+            0 => Const::Int(compiler::read_ivarint(bytes, &mut pos)),
+            1 => {
+                let mut buf = [0u8; 8];
+                buf.copy_from_slice(&bytes[pos..pos + 8]);
+                pos += 8;
+                Const::Float(f64::from_le_bytes(buf))
+            }
+            2 => Const::Str(compiler::read_str(bytes, &mut pos)),
+            _ => panic!("malformed binary bytecode: bad constant tag {}", tag),
+        });
+    }
+
+    let mut funcs: HashMap<String, Function> = HashMap::new();
+    let n_funcs = compiler::read_uvarint(bytes, &mut pos);
+    for _ in 0..n_funcs {
+        let name = compiler::read_str(bytes, &mut pos);
+        let param_count = compiler::read_uvarint(bytes, &mut pos);
+        let params: Vec<String> =
+            (0..param_count).map(|_| compiler::read_str(bytes, &mut pos)).collect();
+        let address = compiler::read_uvarint(bytes, &mut pos) as usize;
+        let line = compiler::read_uvarint(bytes, &mut pos) as u32;
+        funcs.insert(name, Function { params, address, line });
+    }
+
+    let mut line_table: LineTable = Vec::new();
+    let n_debug = compiler::read_uvarint(bytes, &mut pos);
+    for _ in 0..n_debug {
+        let start = compiler::read_uvarint(bytes, &mut pos) as usize;
+        let line = compiler::read_uvarint(bytes, &mut pos) as u32;
+        line_table.push((start, line));
+    }
+
+    let n_instrs = compiler::read_uvarint(bytes, &mut pos);
+    let mut code = Vec::with_capacity(n_instrs as usize);
+    for _ in 0..n_instrs {
+        let op = compiler::opcode_name(bytes[pos]);
+        pos += 1;
+        let operand = decode_operand(op, bytes, &mut pos);
+        code.push(decode_instr(op, operand, &constants));
+    }
+    (code, funcs, line_table)
+}
+
+/// Convert a VM [`Value`] back into the `serde_json::Value` AST shape
+/// `parser::parse` produces — the reverse of `Compiler::compile_quoted` —
+/// so the `eval` builtin can turn a `quote`d list back into something
+/// `compiler::Compiler::compile` can consume.
+fn runtime_value_to_json(v: &Value) -> serde_json::Value {
+    match v {
+        Value::Int(i) => serde_json::Value::Number((*i).into()),
+        Value::Float(f) => serde_json::Number::from_f64(*f)
+            .map(serde_json::Value::Number)
+            .unwrap_or(serde_json::Value::Null),
+        Value::Str(s) => serde_json::Value::String(s.clone()),
+        Value::Bool(b) => serde_json::Value::Bool(*b),
+        Value::None => serde_json::Value::Null,
+        Value::List(list) => {
+            serde_json::Value::Array(list.borrow().iter().map(runtime_value_to_json).collect())
+        }
+        Value::Dict(_) => serde_json::Value::Null,
+        Value::Closure(_) => serde_json::Value::Null,
+    }
+}
+
+/// Emit a short instruction sequence that reconstructs `v` on the VM stack —
+/// used by the `apply` builtin to turn a runtime argument list back into
+/// `Push*`/`BuildList`/`BuildDict` instructions ahead of a synthesized
+/// `Call`.
+fn build_value_instrs(code: &mut Vec<Instr>, v: &Value) {
+    match v {
+        Value::Int(i) => code.push(Instr::PushInt(*i)),
+        Value::Float(f) => code.push(Instr::PushFloat(*f)),
+        Value::Str(s) => code.push(Instr::PushStr(s.clone())),
+        Value::Bool(b) => code.push(Instr::PushBool(*b)),
+        Value::None => code.push(Instr::PushNone),
+        Value::List(list) => {
+            let items = list.borrow();
+            for item in items.iter() {
+                build_value_instrs(code, item);
+            }
+            code.push(Instr::BuildList(items.len()));
+        }
+        Value::Dict(map) => {
+            let items = map.borrow();
+            for (k, val) in items.iter() {
+                code.push(Instr::PushStr(k.clone()));
+                build_value_instrs(code, val);
+            }
+            code.push(Instr::BuildDict(items.len()));
+        }
+        Value::Closure(_) => panic!("apply() can't pass a closure as an argument"),
+    }
+}
+
+/// Execute bytecode on a stack-based virtual machine, writing `emit` output
+/// to `out` instead of directly to stdout — lets [`repl`] capture a run's
+/// output into a buffer it can diff against the previous turn's, the same
+/// way it captures `history`-replayed state. Returns whatever's left on top
+/// of the stack when the program halts (`Value::None` if the stack is
+/// empty), so a nested `run` — from `eval` or `apply` — can hand its result
+/// back to the call site that invoked it.
+fn run(
+    code: &[Instr],
+    funcs: &HashMap<String, Function>,
+    line_table: &LineTable,
+    program_args: &[String],
+    out: &mut dyn std::io::Write,
+) -> Value {
     let mut stack: Vec<Value> = Vec::new();
     let mut globals: HashMap<String, Value> = HashMap::new();
     // Expose command line arguments to bytecode programs via the global `args` list
@@ -278,10 +713,16 @@ fn run(code: &[Instr], funcs: &HashMap<String, Function>, program_args: &[String
     let mut env: HashMap<String, Value> = HashMap::new();
     let mut env_stack: Vec<HashMap<String, Value>> = Vec::new();
     let mut ret_stack: Vec<usize> = Vec::new();
+    // The current frame's captured values, read by `Instr::LoadUpvalue`;
+    // empty outside a closure call. Pushed/popped in lockstep with
+    // `env_stack` around `Call`/`CallValue` and `Ret`.
+    let mut upvalues: Vec<Value> = Vec::new();
+    let mut upvalue_stack: Vec<Vec<Value>> = Vec::new();
     let mut pc: usize = 0;
     while pc < code.len() {
         match &code[pc] {
             Instr::PushInt(v) => stack.push(Value::Int(*v)),
+            Instr::PushFloat(v) => stack.push(Value::Float(*v)),
             Instr::PushStr(s) => stack.push(Value::Str(s.clone())),
             Instr::PushBool(b) => stack.push(Value::Bool(*b)),
             Instr::BuildList(n) => {
@@ -337,29 +778,72 @@ fn run(code: &[Instr], funcs: &HashMap<String, Function>, program_args: &[String
                         }
                         stack.push(Value::List(la));
                     }
+                    (a, b) if matches!(a, Value::Float(_)) || matches!(b, Value::Float(_)) => {
+                        stack.push(Value::Float(a.as_float() + b.as_float()));
+                    }
                     (a, b) => stack.push(Value::Int(a.as_int() + b.as_int())),
                 }
             }
             Instr::Sub => {
-                let b = stack.pop().unwrap().as_int();
-                let a = stack.pop().unwrap().as_int();
-                stack.push(Value::Int(a - b));
+                let b = stack.pop().unwrap();
+                let a = stack.pop().unwrap();
+                if matches!(a, Value::Float(_)) || matches!(b, Value::Float(_)) {
+                    stack.push(Value::Float(a.as_float() - b.as_float()));
+                } else {
+                    stack.push(Value::Int(a.as_int() - b.as_int()));
+                }
             }
             Instr::Mul => {
-                let b = stack.pop().unwrap().as_int();
-                let a = stack.pop().unwrap().as_int();
-                stack.push(Value::Int(a.checked_mul(b).unwrap_or(0)));
+                let b = stack.pop().unwrap();
+                let a = stack.pop().unwrap();
+                if matches!(a, Value::Float(_)) || matches!(b, Value::Float(_)) {
+                    stack.push(Value::Float(a.as_float() * b.as_float()));
+                } else {
+                    stack.push(Value::Int(a.as_int().checked_mul(b.as_int()).unwrap_or(0)));
+                }
             }
             Instr::Div => {
-                let b = stack.pop().unwrap().as_int();
-                let a = stack.pop().unwrap().as_int();
-                stack.push(Value::Int(a / b));
+                let b = stack.pop().unwrap();
+                let a = stack.pop().unwrap();
+                // Int / int stays integer division for back-compat; either
+                // operand being a float promotes the whole division to f64.
+                if matches!(a, Value::Float(_)) || matches!(b, Value::Float(_)) {
+                    stack.push(Value::Float(a.as_float() / b.as_float()));
+                } else {
+                    stack.push(Value::Int(a.as_int() / b.as_int()));
+                }
             }
             Instr::Mod => {
                 let b = stack.pop().unwrap().as_int();
                 let a = stack.pop().unwrap().as_int();
                 stack.push(Value::Int(a % b));
             }
+            Instr::Pow => {
+                let b = stack.pop().unwrap();
+                let a = stack.pop().unwrap();
+                if matches!(a, Value::Float(_)) || matches!(b, Value::Float(_)) {
+                    stack.push(Value::Float(a.as_float().powf(b.as_float())));
+                } else {
+                    let exp = b.as_int();
+                    if exp >= 0 {
+                        stack.push(Value::Int(a.as_int().pow(exp as u32)));
+                    } else {
+                        stack.push(Value::Float(a.as_float().powf(exp as f64)));
+                    }
+                }
+            }
+            Instr::FloorDiv => {
+                let b = stack.pop().unwrap();
+                let a = stack.pop().unwrap();
+                if matches!(a, Value::Float(_)) || matches!(b, Value::Float(_)) {
+                    stack.push(Value::Float((a.as_float() / b.as_float()).floor()));
+                } else {
+                    let (ai, bi) = (a.as_int(), b.as_int());
+                    let q = ai / bi;
+                    let r = ai % bi;
+                    stack.push(Value::Int(if r != 0 && (r < 0) != (bi < 0) { q - 1 } else { q }));
+                }
+            }
             Instr::Eq => {
                 let b = stack.pop().unwrap().to_string();
                 let a = stack.pop().unwrap().to_string();
@@ -375,6 +859,9 @@ fn run(code: &[Instr], funcs: &HashMap<String, Function>, program_args: &[String
                 let a = stack.pop().unwrap();
                 let res = match (&a, &b) {
                     (Value::Str(sa), Value::Str(sb)) => sa < sb,
+                    _ if matches!(a, Value::Float(_)) || matches!(b, Value::Float(_)) => {
+                        a.as_float() < b.as_float()
+                    }
                     _ => a.as_int() < b.as_int(),
                 };
                 stack.push(Value::Bool(res));
@@ -384,6 +871,9 @@ fn run(code: &[Instr], funcs: &HashMap<String, Function>, program_args: &[String
                 let a = stack.pop().unwrap();
                 let res = match (&a, &b) {
                     (Value::Str(sa), Value::Str(sb)) => sa <= sb,
+                    _ if matches!(a, Value::Float(_)) || matches!(b, Value::Float(_)) => {
+                        a.as_float() <= b.as_float()
+                    }
                     _ => a.as_int() <= b.as_int(),
                 };
                 stack.push(Value::Bool(res));
@@ -393,6 +883,9 @@ fn run(code: &[Instr], funcs: &HashMap<String, Function>, program_args: &[String
                 let a = stack.pop().unwrap();
                 let res = match (&a, &b) {
                     (Value::Str(sa), Value::Str(sb)) => sa > sb,
+                    _ if matches!(a, Value::Float(_)) || matches!(b, Value::Float(_)) => {
+                        a.as_float() > b.as_float()
+                    }
                     _ => a.as_int() > b.as_int(),
                 };
                 stack.push(Value::Bool(res));
@@ -402,6 +895,9 @@ fn run(code: &[Instr], funcs: &HashMap<String, Function>, program_args: &[String
                 let a = stack.pop().unwrap();
                 let res = match (&a, &b) {
                     (Value::Str(sa), Value::Str(sb)) => sa >= sb,
+                    _ if matches!(a, Value::Float(_)) || matches!(b, Value::Float(_)) => {
+                        a.as_float() >= b.as_float()
+                    }
                     _ => a.as_int() >= b.as_int(),
                 };
                 stack.push(Value::Bool(res));
@@ -446,8 +942,12 @@ fn run(code: &[Instr], funcs: &HashMap<String, Function>, program_args: &[String
                 stack.push(Value::Int(!v));
             }
             Instr::Neg => {
-                let v = stack.pop().unwrap().as_int();
-                stack.push(Value::Int(-v));
+                let v = stack.pop().unwrap();
+                if let Value::Float(f) = v {
+                    stack.push(Value::Float(-f));
+                } else {
+                    stack.push(Value::Int(-v.as_int()));
+                }
             }
             Instr::Index => {
                 let idx = stack.pop().unwrap();
@@ -549,8 +1049,8 @@ fn run(code: &[Instr], funcs: &HashMap<String, Function>, program_args: &[String
                 let path_val = stack.pop().unwrap();
                 if let Value::Str(path) = path_val {
                     let src = fs::read_to_string(&path).expect("failed to read module");
-                    let (code2, funcs2) = parse_bytecode(&src);
-                    run(&code2, &funcs2, &[]);
+                    let (code2, funcs2, line_table2) = parse_bytecode(&src);
+                    run(&code2, &funcs2, &line_table2, &[], out);
                     stack.push(Value::Dict(Rc::new(RefCell::new(HashMap::new()))));
                 } else {
                     panic!("IMPORT expects string path");
@@ -559,6 +1059,10 @@ fn run(code: &[Instr], funcs: &HashMap<String, Function>, program_args: &[String
             Instr::Assert => {
                 let cond = stack.pop().unwrap().as_bool();
                 if !cond {
+                    eprintln!(
+                        "Assertion failed\n{}",
+                        render_call_stack(funcs, line_table, pc, &ret_stack)
+                    );
                     panic!("Assertion failed");
                 }
             }
@@ -569,23 +1073,40 @@ fn run(code: &[Instr], funcs: &HashMap<String, Function>, program_args: &[String
                 }
                 args_vec.reverse();
                 let func_val = stack.pop().unwrap();
-                if let Value::Str(name) = func_val {
-                    if let Some(func) = funcs.get(&name) {
+                match func_val {
+                    Value::Str(name) => {
+                        if let Some(func) = funcs.get(&name) {
+                            let mut new_env = HashMap::new();
+                            for param in func.params.iter().rev() {
+                                let arg = args_vec.pop().unwrap();
+                                new_env.insert(param.clone(), arg);
+                            }
+                            env_stack.push(env);
+                            ret_stack.push(pc + 1);
+                            upvalue_stack.push(upvalues);
+                            env = new_env;
+                            upvalues = Vec::new();
+                            pc = func.address;
+                            continue;
+                        } else {
+                            panic!("Unknown function: {}", name);
+                        }
+                    }
+                    Value::Closure(closure) => {
                         let mut new_env = HashMap::new();
-                        for param in func.params.iter().rev() {
+                        for param in closure.params.iter().rev() {
                             let arg = args_vec.pop().unwrap();
                             new_env.insert(param.clone(), arg);
                         }
                         env_stack.push(env);
                         ret_stack.push(pc + 1);
+                        upvalue_stack.push(upvalues);
                         env = new_env;
-                        pc = func.address;
+                        upvalues = closure.captures.clone();
+                        pc = closure.address;
                         continue;
-                    } else {
-                        panic!("Unknown function: {}", name);
                     }
-                } else {
-                    panic!("CALL_VALUE expects function name");
+                    _ => panic!("CALL_VALUE expects a function name or closure"),
                 }
             }
             Instr::PushNone => {
@@ -602,6 +1123,31 @@ fn run(code: &[Instr], funcs: &HashMap<String, Function>, program_args: &[String
                     continue;
                 }
             }
+            Instr::Switch(_base, len) => {
+                let idx = stack.pop().unwrap().as_int();
+                if idx >= 0 && (idx as usize) < *len {
+                    pc = pc + 1 + idx as usize;
+                } else {
+                    pc = pc + 1 + *len;
+                }
+                continue;
+            }
+            Instr::MakeClosure(name, n_upvalues) => {
+                let mut captures: Vec<Value> = Vec::new();
+                for _ in 0..*n_upvalues {
+                    captures.push(stack.pop().unwrap());
+                }
+                captures.reverse();
+                let func = funcs.get(name).unwrap_or_else(|| panic!("Unknown function: {}", name));
+                stack.push(Value::Closure(Rc::new(Closure {
+                    address: func.address,
+                    params: func.params.clone(),
+                    captures,
+                })));
+            }
+            Instr::LoadUpvalue(idx) => {
+                stack.push(upvalues[*idx].clone());
+            }
             Instr::Call(name) => {
                 if let Some(func) = funcs.get(name) {
                     let mut new_env = HashMap::new();
@@ -611,7 +1157,9 @@ fn run(code: &[Instr], funcs: &HashMap<String, Function>, program_args: &[String
                     }
                     env_stack.push(env);
                     ret_stack.push(pc + 1);
+                    upvalue_stack.push(upvalues);
                     env = new_env;
+                    upvalues = Vec::new();
                     pc = func.address;
                     continue;
                 } else {
@@ -626,6 +1174,7 @@ fn run(code: &[Instr], funcs: &HashMap<String, Function>, program_args: &[String
                         new_env.insert(param.clone(), arg);
                     }
                     env = new_env;
+                    upvalues = Vec::new();
                     pc = func.address;
                     continue;
                 } else {
@@ -677,6 +1226,36 @@ fn run(code: &[Instr], funcs: &HashMap<String, Function>, program_args: &[String
                         }
                         _ => panic!("read_file() expects a file path"),
                     },
+                    "eval" => match args.as_slice() {
+                        [quoted] => {
+                            let json = runtime_value_to_json(quoted);
+                            let stmts = json
+                                .as_array()
+                                .and_then(|arr| arr.get(1))
+                                .and_then(|v| v.as_array())
+                                .cloned()
+                                .unwrap_or_else(|| panic!("eval() expects a quoted block"));
+                            let mut sub_compiler = compiler::Compiler::new();
+                            sub_compiler.compile(&stmts);
+                            let (code2, funcs2, line_table2) = parse_bytecode(&sub_compiler.to_string());
+                            run(&code2, &funcs2, &line_table2, &[], out)
+                        }
+                        _ => panic!("eval() expects a single quoted block"),
+                    },
+                    "apply" => match args.as_slice() {
+                        [Value::Str(name), Value::List(arglist)] => {
+                            let mut code2 = Vec::new();
+                            for a in arglist.borrow().iter() {
+                                build_value_instrs(&mut code2, a);
+                            }
+                            code2.push(Instr::Call(name.clone()));
+                            code2.push(Instr::Halt);
+                            // No source to attribute this synthesized call
+                            // to — an empty line table is the honest answer.
+                            run(&code2, funcs, &[], &[], out)
+                        }
+                        _ => panic!("apply() expects a proc name and an argument list"),
+                    },
                     _ => panic!("unknown builtin: {}", name),
                 };
                 stack.push(result);
@@ -688,25 +1267,27 @@ fn run(code: &[Instr], funcs: &HashMap<String, Function>, program_args: &[String
                 let ret_val = stack.pop().unwrap_or(Value::Int(0));
                 pc = ret_stack.pop().unwrap();
                 env = env_stack.pop().unwrap();
+                upvalues = upvalue_stack.pop().unwrap();
                 stack.push(ret_val);
                 continue;
             }
             Instr::Emit => {
                 if let Some(v) = stack.pop() {
-                    println!("{}", v.to_string());
+                    let _ = writeln!(out, "{}", v.to_string());
                 }
             }
             Instr::Halt => break,
         }
         pc += 1;
     }
+    stack.pop().unwrap_or(Value::None)
 }
 
 fn main() {
     let args: Vec<String> = env::args().collect();
     if args.len() < 2 {
-        eprintln!("Usage: omg_native <bytecode_file> [--] [program args]");
-        std::process::exit(1);
+        repl::run_repl();
+        return;
     }
     let bc_path = &args[1];
     let program_args: &[String] = if args.len() > 2 {
@@ -718,7 +1299,12 @@ fn main() {
     } else {
         &[]
     };
-    let src = fs::read_to_string(bc_path).expect("failed to read bytecode file");
-    let (code, funcs) = parse_bytecode(&src);
-    run(&code, &funcs, program_args);
+    let raw = fs::read(bc_path).expect("failed to read bytecode file");
+    let (code, funcs, line_table) = if raw.starts_with(compiler::MAGIC) {
+        load_bytecode(&raw)
+    } else {
+        let src = String::from_utf8(raw).expect("bytecode file is not valid UTF-8 text");
+        parse_bytecode(&src)
+    };
+    run(&code, &funcs, &line_table, program_args, &mut std::io::stdout());
 }
@@ -0,0 +1,651 @@
+// Recursive-descent parser for the bootstrap OMG front end, producing a
+// plain `serde_json::Value` AST (see `ast_node`) rather than a dedicated
+// Rust AST type, since the only consumer is `compiler::Compiler`, which was
+// written against this shape from the start.
+//
+// Malformed input is reported as a `ParseError` carrying the offending
+// token's `(line, col)` rather than a `panic!` — see `render_parse_error`
+// for turning one into a caret-underlined diagnostic. `compiler::Compiler`
+// downstream still `panic!`s on an AST it can't compile — a `break` outside
+// a loop, or a malformed AST handed to the `eval` builtin in `main.rs` —
+// but not on a plain parse failure anymore.
+
+use serde_json::Value;
+
+use crate::lexer::{PosToken, Token};
+
+fn ast_node(kind: &str, parts: Vec<Value>) -> Value {
+    let mut v = Vec::with_capacity(1 + parts.len());
+    v.push(Value::String(kind.to_string()));
+    v.extend(parts);
+    Value::Array(v)
+}
+
+/// A parse failure at a specific source location, with a human-readable
+/// description of what was expected there.
+#[derive(Debug, Clone)]
+pub struct ParseError {
+    pub line: usize,
+    pub col: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.col, self.message)
+    }
+}
+
+/// Render `err` against the original `source` as a caret-underlined
+/// diagnostic, e.g.:
+/// ```text
+/// 3:14: expected ')' after parameters
+/// proc add(a, b
+///              ^
+/// ```
+pub fn render_parse_error(source: &str, err: &ParseError) -> String {
+    let line_text = source.lines().nth(err.line.saturating_sub(1)).unwrap_or("");
+    let caret_pad = " ".repeat(err.col.saturating_sub(1));
+    format!("{}\n{}\n{}^", err, line_text, caret_pad)
+}
+
+/// The `(line, col)` a diagnostic should point at for token index `i`: the
+/// token itself if it exists, or just past the last real token (end of
+/// input) otherwise.
+fn position_at(tokens: &[PosToken], i: usize) -> (usize, usize) {
+    if let Some(t) = tokens.get(i) {
+        (t.line, t.col)
+    } else if let Some(last) = tokens.last() {
+        (last.line, last.col + 1)
+    } else {
+        (1, 1)
+    }
+}
+
+fn err_at(tokens: &[PosToken], i: usize, message: impl Into<String>) -> ParseError {
+    let (line, col) = position_at(tokens, i);
+    ParseError { line, col, message: message.into() }
+}
+
+/// Fetch the token at `i`, or a `ParseError` describing what was expected
+/// there if the stream ran out first.
+fn tok_at<'t>(tokens: &'t [PosToken], i: usize, expected: &str) -> Result<&'t Token, ParseError> {
+    tokens
+        .get(i)
+        .map(|t| &t.tok)
+        .ok_or_else(|| err_at(tokens, i, format!("expected {}, found end of input", expected)))
+}
+
+/// `true` if the token at `i` is `Token::Symbol(sym)`.
+fn at_symbol(tokens: &[PosToken], i: usize, sym: &str) -> bool {
+    matches!(tokens.get(i).map(|t| &t.tok), Some(Token::Symbol(s)) if s == sym)
+}
+
+/// Tokenize and parse a whole program into a list of top-level statement AST
+/// nodes.
+pub fn parse(source: &str) -> Result<Vec<Value>, ParseError> {
+    let tokens = crate::lexer::tokenize(source);
+    let (stmts, _) = parse_program(&tokens, 0)?;
+    Ok(stmts)
+}
+
+fn parse_program(tokens: &[PosToken], mut i: usize) -> Result<(Vec<Value>, usize), ParseError> {
+    let mut stmts = Vec::new();
+    while i < tokens.len() {
+        let (stmt, ni) = parse_statement(tokens, i)?;
+        stmts.push(stmt);
+        i = ni;
+    }
+    Ok((stmts, i))
+}
+
+fn parse_block(tokens: &[PosToken], i: usize) -> Result<(Value, usize), ParseError> {
+    let mut j = i + 1; // skip '{'
+    let mut stmts = Vec::new();
+    while j < tokens.len() {
+        if at_symbol(tokens, j, "}") {
+            return Ok((ast_node("block", vec![Value::Array(stmts)]), j + 1));
+        }
+        let (stmt, nj) = parse_statement(tokens, j)?;
+        stmts.push(stmt);
+        j = nj;
+    }
+    Ok((ast_node("block", vec![Value::Array(stmts)]), j))
+}
+
+/// Parse one statement, then append its 1-indexed source line as the last
+/// element of the returned AST node — see `append_line`. `compiler::Compiler`
+/// reads this to build its `debug` instruction-index-to-line table; it's
+/// appended rather than threaded through every `ast_node` call in
+/// `parse_statement_inner` so expression-level nodes (which never carry a
+/// line) don't need a shape change too. One tradeoff: an `elif`/`else` tail
+/// folded into an outer `if` node by `parse_if`'s loop shares the outer `if`
+/// statement's line rather than getting its own, since only the outermost
+/// node returned here gets a line appended.
+fn parse_statement(tokens: &[PosToken], i: usize) -> Result<(Value, usize), ParseError> {
+    let (line, _) = position_at(tokens, i);
+    let (node, j) = parse_statement_inner(tokens, i)?;
+    Ok((append_line(node, line), j))
+}
+
+/// Append `line` as the last element of `node`'s array — see
+/// `parse_statement`.
+fn append_line(node: Value, line: usize) -> Value {
+    let mut arr = match node {
+        Value::Array(a) => a,
+        _ => unreachable!("ast_node always produces an array"),
+    };
+    arr.push(Value::Number((line as u64).into()));
+    Value::Array(arr)
+}
+
+fn parse_statement_inner(tokens: &[PosToken], i: usize) -> Result<(Value, usize), ParseError> {
+    match tok_at(tokens, i, "a statement")? {
+        Token::Kw(k) if k == "alloc" => {
+            if let Token::Ident(name) = tok_at(tokens, i + 1, "identifier after 'alloc'")? {
+                let name = name.clone();
+                let (expr, j) = parse_expression(tokens, i + 3)?;
+                Ok((ast_node("decl", vec![Value::String(name), expr]), j))
+            } else {
+                Err(err_at(tokens, i + 1, "expected identifier after 'alloc'"))
+            }
+        }
+        Token::Kw(k) if k == "emit" => {
+            let (expr, j) = parse_expression(tokens, i + 1)?;
+            Ok((ast_node("emit", vec![expr]), j))
+        }
+        Token::Kw(k) if k == "return" => {
+            let (expr, j) = parse_expression(tokens, i + 1)?;
+            Ok((ast_node("return", vec![expr]), j))
+        }
+        Token::Kw(k) if k == "break" => Ok((ast_node("break", vec![]), i + 1)),
+        Token::Kw(k) if k == "loop" => {
+            let (cond, j) = parse_expression(tokens, i + 1)?;
+            let (block, k) = parse_block(tokens, j)?;
+            Ok((ast_node("loop", vec![cond, block]), k))
+        }
+        Token::Kw(k) if k == "if" => parse_if(tokens, i),
+        Token::Kw(k) if k == "match" => parse_match(tokens, i),
+        Token::Kw(k) if k == "proc" => {
+            if let Token::Ident(name) = tok_at(tokens, i + 1, "function name after 'proc'")? {
+                let name = name.clone();
+                let mut j = i + 3; // skip name and '('
+                let mut params = Vec::new();
+                while let Some(Token::Ident(p)) = tokens.get(j).map(|t| &t.tok) {
+                    params.push(Value::String(p.clone()));
+                    j += 1;
+                    if at_symbol(tokens, j, ",") {
+                        j += 1;
+                        continue;
+                    }
+                    break;
+                }
+                if !at_symbol(tokens, j, ")") {
+                    return Err(err_at(tokens, j, "expected ')' after parameters"));
+                }
+                let (body, k) = parse_block(tokens, j + 1)?;
+                Ok((ast_node("func_def", vec![Value::String(name), Value::Array(params), body]), k))
+            } else {
+                Err(err_at(tokens, i + 1, "expected function name after 'proc'"))
+            }
+        }
+        Token::Kw(k) if k == "import" => {
+            if let Token::Str(path) = tok_at(tokens, i + 1, "module path string after 'import'")? {
+                let path = path.clone();
+                if !matches!(tok_at(tokens, i + 2, "'as'")?, Token::Kw(as_kw) if as_kw == "as") {
+                    return Err(err_at(tokens, i + 2, "expected 'as' in import"));
+                }
+                if let Token::Ident(alias) = tok_at(tokens, i + 3, "alias identifier after 'as'")? {
+                    Ok((
+                        ast_node("import", vec![Value::String(path), Value::String(alias.clone())]),
+                        i + 4,
+                    ))
+                } else {
+                    Err(err_at(tokens, i + 3, "expected alias identifier after 'as'"))
+                }
+            } else {
+                Err(err_at(tokens, i + 1, "expected module path string after 'import'"))
+            }
+        }
+        Token::Kw(k) if k == "facts" => {
+            let (expr, j) = parse_expression(tokens, i + 1)?;
+            Ok((ast_node("facts", vec![expr]), j))
+        }
+        Token::Ident(_) => {
+            let (lval, j) = parse_factor(tokens, i)?;
+            if at_symbol(tokens, j, ":=") {
+                let (rhs, k) = parse_expression(tokens, j + 1)?;
+                let arr = lval.as_array().unwrap();
+                let res = match arr[0].as_str().unwrap() {
+                    "ident" => ast_node("assign", vec![arr[1].clone(), rhs]),
+                    "dot" => ast_node("attr_assign", vec![arr[1].clone(), arr[2].clone(), rhs]),
+                    "index" => ast_node("index_assign", vec![arr[1].clone(), arr[2].clone(), rhs]),
+                    _ => return Err(err_at(tokens, i, "invalid assignment target")),
+                };
+                return Ok((res, k));
+            }
+            let (expr, k) = parse_expression(tokens, i)?;
+            Ok((ast_node("expr_stmt", vec![expr]), k))
+        }
+        _ => {
+            let (expr, j) = parse_expression(tokens, i)?;
+            Ok((ast_node("expr_stmt", vec![expr]), j))
+        }
+    }
+}
+
+fn parse_if(tokens: &[PosToken], i: usize) -> Result<(Value, usize), ParseError> {
+    let (cond, j) = parse_expression(tokens, i + 1)?;
+    let (then_block, mut k) = parse_block(tokens, j)?;
+    let mut elifs: Vec<(Value, Value)> = Vec::new();
+    let mut else_block: Value = Value::Null;
+    while k < tokens.len() {
+        match &tokens[k].tok {
+            Token::Kw(s) if s == "elif" => {
+                let (c, j2) = parse_expression(tokens, k + 1)?;
+                let (b, j3) = parse_block(tokens, j2)?;
+                elifs.push((c, b));
+                k = j3;
+            }
+            Token::Kw(s) if s == "else" => {
+                let (b, j2) = parse_block(tokens, k + 1)?;
+                else_block = b;
+                k = j2;
+                break;
+            }
+            _ => break,
+        }
+    }
+    let mut tail = else_block;
+    for (c, b) in elifs.into_iter().rev() {
+        tail = ast_node("if", vec![c, b, tail]);
+    }
+    Ok((ast_node("if", vec![cond, then_block, tail]), k))
+}
+
+/// `match <subject> { case <val> <block> ... [else <block>] }`, producing
+/// `["match", subject, [[caseval, block], ...], default_block]` — see
+/// `compiler::Compiler::compile_stmt`'s `"match"` arm for how `default_block`
+/// is always present (an empty one if no `else` clause appeared) so the
+/// compiler never has to special-case its absence.
+fn parse_match(tokens: &[PosToken], i: usize) -> Result<(Value, usize), ParseError> {
+    let (subject, j) = parse_expression(tokens, i + 1)?;
+    if !at_symbol(tokens, j, "{") {
+        return Err(err_at(tokens, j, "expected '{' after match subject"));
+    }
+    let mut k = j + 1;
+    let mut cases: Vec<Value> = Vec::new();
+    let mut default_block = ast_node("block", vec![Value::Array(vec![])]);
+    loop {
+        match tok_at(tokens, k, "'case', 'else', or '}' in match")? {
+            Token::Kw(kw) if kw == "case" => {
+                let (val, j2) = parse_expression(tokens, k + 1)?;
+                let (block, j3) = parse_block(tokens, j2)?;
+                cases.push(Value::Array(vec![val, block]));
+                k = j3;
+            }
+            Token::Kw(kw) if kw == "else" => {
+                let (block, j2) = parse_block(tokens, k + 1)?;
+                default_block = block;
+                k = j2;
+                if !at_symbol(tokens, k, "}") {
+                    return Err(err_at(tokens, k, "expected '}' after match's 'else' block"));
+                }
+                k += 1;
+                break;
+            }
+            Token::Symbol(s) if s == "}" => {
+                k += 1;
+                break;
+            }
+            _ => return Err(err_at(tokens, k, "expected 'case', 'else', or '}' in match")),
+        }
+    }
+    Ok((ast_node("match", vec![subject, Value::Array(cases), default_block]), k))
+}
+
+fn parse_expression(tokens: &[PosToken], i: usize) -> Result<(Value, usize), ParseError> {
+    parse_or(tokens, i)
+}
+
+fn parse_or(tokens: &[PosToken], i: usize) -> Result<(Value, usize), ParseError> {
+    let (mut left, mut j) = parse_and(tokens, i)?;
+    while j < tokens.len() {
+        if let Token::Kw(op) = &tokens[j].tok {
+            if op == "or" {
+                let (right, nj) = parse_and(tokens, j + 1)?;
+                left = ast_node("or", vec![left, right]);
+                j = nj;
+                continue;
+            }
+        }
+        break;
+    }
+    Ok((left, j))
+}
+
+fn parse_and(tokens: &[PosToken], i: usize) -> Result<(Value, usize), ParseError> {
+    let (mut left, mut j) = parse_comparison(tokens, i)?;
+    while j < tokens.len() {
+        if let Token::Kw(op) = &tokens[j].tok {
+            if op == "and" {
+                let (right, nj) = parse_comparison(tokens, j + 1)?;
+                left = ast_node("and", vec![left, right]);
+                j = nj;
+                continue;
+            }
+        }
+        break;
+    }
+    Ok((left, j))
+}
+
+fn parse_comparison(tokens: &[PosToken], i: usize) -> Result<(Value, usize), ParseError> {
+    let (mut left, mut j) = parse_bit_or(tokens, i)?;
+    while j < tokens.len() {
+        if let Token::Symbol(op) = &tokens[j].tok {
+            let op_name = match op.as_str() {
+                "<" => Some("lt"),
+                ">" => Some("gt"),
+                "<=" => Some("le"),
+                ">=" => Some("ge"),
+                "==" => Some("eq"),
+                "!=" => Some("ne"),
+                _ => None,
+            };
+            if let Some(name) = op_name {
+                let (right, nj) = parse_bit_or(tokens, j + 1)?;
+                left = ast_node(name, vec![left, right]);
+                j = nj;
+                continue;
+            }
+        }
+        break;
+    }
+    Ok((left, j))
+}
+
+fn parse_bit_or(tokens: &[PosToken], i: usize) -> Result<(Value, usize), ParseError> {
+    let (mut left, mut j) = parse_bit_xor(tokens, i)?;
+    while j < tokens.len() {
+        if let Token::Symbol(s) = &tokens[j].tok {
+            if s == "|" {
+                let (right, nj) = parse_bit_xor(tokens, j + 1)?;
+                left = ast_node("bor", vec![left, right]);
+                j = nj;
+                continue;
+            }
+        }
+        break;
+    }
+    Ok((left, j))
+}
+
+fn parse_bit_xor(tokens: &[PosToken], i: usize) -> Result<(Value, usize), ParseError> {
+    let (mut left, mut j) = parse_bit_and(tokens, i)?;
+    while j < tokens.len() {
+        if let Token::Symbol(s) = &tokens[j].tok {
+            if s == "^" {
+                let (right, nj) = parse_bit_and(tokens, j + 1)?;
+                left = ast_node("bxor", vec![left, right]);
+                j = nj;
+                continue;
+            }
+        }
+        break;
+    }
+    Ok((left, j))
+}
+
+fn parse_bit_and(tokens: &[PosToken], i: usize) -> Result<(Value, usize), ParseError> {
+    let (mut left, mut j) = parse_shift(tokens, i)?;
+    while j < tokens.len() {
+        if let Token::Symbol(s) = &tokens[j].tok {
+            if s == "&" {
+                let (right, nj) = parse_shift(tokens, j + 1)?;
+                left = ast_node("band", vec![left, right]);
+                j = nj;
+                continue;
+            }
+        }
+        break;
+    }
+    Ok((left, j))
+}
+
+fn parse_shift(tokens: &[PosToken], i: usize) -> Result<(Value, usize), ParseError> {
+    let (mut left, mut j) = parse_add_sub(tokens, i)?;
+    while j < tokens.len() {
+        if let Token::Symbol(s) = &tokens[j].tok {
+            if s == "<<" || s == ">>" {
+                let op_name = if s == "<<" { "shl" } else { "shr" };
+                let (right, nj) = parse_add_sub(tokens, j + 1)?;
+                left = ast_node(op_name, vec![left, right]);
+                j = nj;
+                continue;
+            }
+        }
+        break;
+    }
+    Ok((left, j))
+}
+
+fn parse_add_sub(tokens: &[PosToken], i: usize) -> Result<(Value, usize), ParseError> {
+    let (mut left, mut j) = parse_term(tokens, i)?;
+    while j < tokens.len() {
+        if let Token::Symbol(s) = &tokens[j].tok {
+            if s == "+" || s == "-" {
+                let op_name = if s == "+" { "add" } else { "sub" };
+                let (right, nj) = parse_term(tokens, j + 1)?;
+                left = ast_node(op_name, vec![left, right]);
+                j = nj;
+                continue;
+            }
+        }
+        break;
+    }
+    Ok((left, j))
+}
+
+fn parse_term(tokens: &[PosToken], i: usize) -> Result<(Value, usize), ParseError> {
+    let (mut left, mut j) = parse_power(tokens, i)?;
+    while j < tokens.len() {
+        if let Token::Symbol(s) = &tokens[j].tok {
+            if s == "*" || s == "/" || s == "%" || s == "//" {
+                let op_name = match s.as_str() {
+                    "*" => "mul",
+                    "/" => "div",
+                    "//" => "floordiv",
+                    _ => "mod",
+                };
+                let (right, nj) = parse_power(tokens, j + 1)?;
+                left = ast_node(op_name, vec![left, right]);
+                j = nj;
+                continue;
+            }
+        }
+        break;
+    }
+    Ok((left, j))
+}
+
+/// `**` sits between [`parse_term`] (`*`/`/`/`%`/`//`) and [`parse_factor`]
+/// (the postfix/atom level), and is right-associative: `2 ** 3 ** 2` groups
+/// as `2 ** (3 ** 2)`, so the right operand recurses back into
+/// `parse_power` rather than looping left-to-right like `parse_term` does.
+fn parse_power(tokens: &[PosToken], i: usize) -> Result<(Value, usize), ParseError> {
+    let (left, j) = parse_factor(tokens, i)?;
+    if at_symbol(tokens, j, "**") {
+        let (right, k) = parse_power(tokens, j + 1)?;
+        return Ok((ast_node("pow", vec![left, right]), k));
+    }
+    Ok((left, j))
+}
+
+fn parse_factor(tokens: &[PosToken], i: usize) -> Result<(Value, usize), ParseError> {
+    let (mut node, mut j) = match tok_at(tokens, i, "an expression")? {
+        Token::Symbol(s) if s == "-" => {
+            let (expr, j) = parse_factor(tokens, i + 1)?;
+            (ast_node("unary", vec![Value::String("sub".to_string()), expr]), j)
+        }
+        Token::Symbol(s) if s == "~" => {
+            let (expr, j) = parse_factor(tokens, i + 1)?;
+            (
+                ast_node(
+                    "unary",
+                    vec![Value::String("not_bits".to_string()), expr],
+                ),
+                j,
+            )
+        }
+        Token::Number(n) => (ast_node("number", vec![Value::Number((*n).into())]), i + 1),
+        Token::Float(f) => (
+            ast_node(
+                "float",
+                vec![Value::Number(serde_json::Number::from_f64(*f).unwrap_or_else(|| 0.into()))],
+            ),
+            i + 1,
+        ),
+        Token::Bool(b) => (ast_node("bool", vec![Value::Bool(*b)]), i + 1),
+        Token::Str(s) => (ast_node("string", vec![Value::String(s.clone())]), i + 1),
+        Token::Ident(name) => (ast_node("ident", vec![Value::String(name.clone())]), i + 1),
+        Token::Kw(kw) if kw == "quote" => {
+            let (block, j) = parse_block(tokens, i + 1)?;
+            (ast_node("quote", vec![block]), j)
+        }
+        Token::Symbol(s) if s == "[" => {
+            let mut elems = Vec::new();
+            let mut k = i + 1;
+            if at_symbol(tokens, k, "]") {
+                return Ok((ast_node("list", vec![Value::Array(elems)]), k + 1));
+            }
+            loop {
+                let (expr, nk) = parse_expression(tokens, k)?;
+                elems.push(expr);
+                k = nk;
+                if at_symbol(tokens, k, ",") {
+                    k += 1;
+                    continue;
+                } else if at_symbol(tokens, k, "]") {
+                    break;
+                } else {
+                    return Err(err_at(tokens, k, "expected ',' or ']' in list literal"));
+                }
+            }
+            (ast_node("list", vec![Value::Array(elems)]), k + 1)
+        }
+        Token::Symbol(s) if s == "{" => {
+            let mut pairs = Vec::new();
+            let mut k = i + 1;
+            if at_symbol(tokens, k, "}") {
+                return Ok((ast_node("dict", vec![Value::Array(pairs)]), k + 1));
+            }
+            loop {
+                let key = match tok_at(tokens, k, "a dict key")? {
+                    Token::Str(s) => {
+                        k += 1;
+                        s.clone()
+                    }
+                    Token::Ident(s) => {
+                        k += 1;
+                        s.clone()
+                    }
+                    _ => return Err(err_at(tokens, k, "invalid dict key")),
+                };
+                k += 1; // skip ':'
+                let (value, nk) = parse_expression(tokens, k)?;
+                pairs.push(Value::Array(vec![Value::String(key), value]));
+                k = nk;
+                if at_symbol(tokens, k, ",") {
+                    k += 1;
+                    continue;
+                } else if at_symbol(tokens, k, "}") {
+                    break;
+                } else {
+                    return Err(err_at(tokens, k, "expected ',' or '}' in dict literal"));
+                }
+            }
+            (ast_node("dict", vec![Value::Array(pairs)]), k + 1)
+        }
+        Token::Symbol(s) if s == "(" => {
+            let (expr, k) = parse_expression(tokens, i + 1)?;
+            if !at_symbol(tokens, k, ")") {
+                return Err(err_at(tokens, k, "expected ')' after parenthesized expression"));
+            }
+            (expr, k + 1)
+        }
+        _ => return Err(err_at(tokens, i, "unexpected token in expression")),
+    };
+
+    loop {
+        if j >= tokens.len() {
+            break;
+        }
+        match &tokens[j].tok {
+            Token::Symbol(s) if s == "(" => {
+                let mut k = j + 1;
+                let mut args = Vec::new();
+                if at_symbol(tokens, k, ")") {
+                    j = k + 1;
+                    node = ast_node("func_call", vec![node.clone(), Value::Array(args)]);
+                    continue;
+                }
+                loop {
+                    let (arg, nk) = parse_expression(tokens, k)?;
+                    args.push(arg);
+                    k = nk;
+                    if at_symbol(tokens, k, ",") {
+                        k += 1;
+                        continue;
+                    } else if at_symbol(tokens, k, ")") {
+                        break;
+                    } else {
+                        return Err(err_at(tokens, k, "expected ',' or ')' in call arguments"));
+                    }
+                }
+                j = k + 1;
+                node = ast_node("func_call", vec![node, Value::Array(args)]);
+            }
+            Token::Symbol(s) if s == "[" => {
+                let (start, mut k) = parse_expression(tokens, j + 1)?;
+                if at_symbol(tokens, k, ":") {
+                    k += 1;
+                    let end = if at_symbol(tokens, k, "]") {
+                        Value::Null
+                    } else {
+                        let (e, nk) = parse_expression(tokens, k)?;
+                        k = nk;
+                        e
+                    };
+                    if !at_symbol(tokens, k, "]") {
+                        return Err(err_at(tokens, k, "expected ']' after slice"));
+                    }
+                    j = k + 1;
+                    node = ast_node("slice", vec![node, start, end]);
+                } else if at_symbol(tokens, k, "]") {
+                    j = k + 1;
+                    node = ast_node("index", vec![node, start]);
+                } else {
+                    let (idx_expr, nk) = parse_expression(tokens, k)?;
+                    k = nk;
+                    if !at_symbol(tokens, k, "]") {
+                        return Err(err_at(tokens, k, "expected ']' after index"));
+                    }
+                    j = k + 1;
+                    node = ast_node("index", vec![node, idx_expr]);
+                }
+            }
+            Token::Symbol(s) if s == "." => {
+                if let Token::Ident(name) = tok_at(tokens, j + 1, "identifier after '.'")? {
+                    node = ast_node("dot", vec![node, Value::String(name.clone())]);
+                    j += 2;
+                } else {
+                    return Err(err_at(tokens, j + 1, "expected identifier after '.'"));
+                }
+            }
+            _ => break,
+        }
+    }
+
+    Ok((node, j))
+}
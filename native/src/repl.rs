@@ -0,0 +1,343 @@
+//! Interactive REPL for the bootstrap OMG front end, built on `rustyline`
+//! the same way `runtime/src/repl.rs` builds its two REPLs — real line
+//! editing, persisted history, and a `Helper` providing completion,
+//! highlighting, and multi-line validation.
+//!
+//! Unlike `runtime/src/repl.rs::repl_interpret`, this one lets `rustyline`
+//! itself drive multi-line continuation: [`NativeReplHelper`]'s `Validator`
+//! returns `ValidationResult::Incomplete` while brackets are unbalanced or a
+//! string/block comment is left open, so `editor.readline()` only returns
+//! once a whole block has been typed, rather than this module tracking
+//! brace depth itself turn by turn.
+//!
+//! The session's state (`proc` definitions, `alloc` bindings) persists the
+//! same way `runtime/src/repl.rs::repl_interpret`'s embedded-interpreter
+//! session does: there's no incremental compile/eval entry point in this
+//! toy front end, so each accepted block re-compiles and re-runs the full
+//! accumulated source (`history + block`), and output is diffed against the
+//! previous run so only newly produced lines are printed.
+
+use std::io;
+use std::path::PathBuf;
+
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::{ValidationContext, ValidationResult, Validator};
+use rustyline::{Context, Editor, Helper};
+
+use crate::compiler::Compiler;
+use crate::lexer::{tokenize, PosToken, Token, KEYWORDS};
+use crate::parser::{parse, render_parse_error};
+use crate::{parse_bytecode, run};
+
+/// `true` if `source`, read as a character stream independent of
+/// [`tokenize`], ends inside an unterminated `"..."` string or `/* */`
+/// comment. `tokenize` itself silently stops at end-of-input in both cases
+/// rather than signaling it, so unterminated-ness has
+/// to be checked this way instead of by inspecting its `Token` output.
+fn unterminated_string_or_comment(source: &str) -> bool {
+    let chars: Vec<char> = source.chars().collect();
+    let mut i = 0;
+    let mut in_string = false;
+    while i < chars.len() {
+        let c = chars[i];
+        if in_string {
+            if c == '\\' && i + 1 < chars.len() {
+                i += 2;
+                continue;
+            }
+            if c == '"' {
+                in_string = false;
+            }
+            i += 1;
+            continue;
+        }
+        if c == '"' {
+            in_string = true;
+            i += 1;
+        } else if c == '#' {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c == '/' && i + 1 < chars.len() && chars[i + 1] == '*' {
+            i += 2;
+            let mut closed = false;
+            while i + 1 < chars.len() {
+                if chars[i] == '*' && chars[i + 1] == '/' {
+                    closed = true;
+                    i += 2;
+                    break;
+                }
+                i += 1;
+            }
+            if !closed {
+                return true;
+            }
+        } else {
+            i += 1;
+        }
+    }
+    in_string
+}
+
+/// `true` if `tokens` has any `(`/`[`/`{` left unmatched by a closing
+/// counterpart. Mismatched-kind closes (e.g. `(]`) aren't distinguished from
+/// balanced input here — that belongs to real parse-error reporting, not
+/// this "is there more to type" check.
+fn brackets_unbalanced(tokens: &[PosToken]) -> bool {
+    let mut depth = 0i32;
+    for tok in tokens {
+        if let Token::Symbol(s) = &tok.tok {
+            match s.as_str() {
+                "(" | "[" | "{" => depth += 1,
+                ")" | "]" | "}" => depth -= 1,
+                _ => {}
+            }
+        }
+    }
+    depth > 0
+}
+
+/// Pull out `proc <name>` declarations from a token stream, for completion.
+fn extract_proc_names(tokens: &[PosToken]) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut prev_was_proc = false;
+    for tok in tokens {
+        match &tok.tok {
+            Token::Kw(k) if k == "proc" => prev_was_proc = true,
+            Token::Ident(name) if prev_was_proc => {
+                names.push(name.clone());
+                prev_was_proc = false;
+            }
+            _ => prev_was_proc = false,
+        }
+    }
+    names
+}
+
+/// `rustyline` helper for the native REPL: completion against builtins plus
+/// `proc` names seen so far this session, highlighting by re-lexing each
+/// whitespace-delimited word, and multi-line validation over the token
+/// stream plus the raw-character string/comment check above.
+struct NativeReplHelper {
+    builtins: Vec<String>,
+    procs: Vec<String>,
+}
+
+impl Completer for NativeReplHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let start = line[..pos]
+            .rfind(|c: char| !c.is_alphanumeric() && c != '_')
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        let word = &line[start..pos];
+
+        let mut candidates: Vec<Pair> = KEYWORDS
+            .iter()
+            .chain(self.builtins.iter().map(String::as_str))
+            .chain(self.procs.iter().map(String::as_str))
+            .filter(|name| name.starts_with(word))
+            .map(|name| Pair {
+                display: name.to_string(),
+                replacement: name.to_string(),
+            })
+            .collect();
+        candidates.dedup_by(|a, b| a.replacement == b.replacement);
+
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for NativeReplHelper {
+    type Hint = String;
+
+    fn hint(&self, _line: &str, _pos: usize, _ctx: &Context<'_>) -> Option<String> {
+        None
+    }
+}
+
+impl Highlighter for NativeReplHelper {
+    /// Color each whitespace-delimited word by what `tokenize` classifies it
+    /// as when lexed on its own. Words that straddle a symbol (e.g.
+    /// `foo(1)`) tokenize to more than one token and are left unstyled
+    /// rather than guessed at — `Token` carries no source spans to slice a
+    /// sub-word substring out correctly (see `unterminated_string_or_comment`
+    /// for the same limitation), and coloring the wrong bytes would desync
+    /// the cursor from what's drawn.
+    fn highlight<'l>(&self, line: &'l str, _pos: usize) -> std::borrow::Cow<'l, str> {
+        if line.is_empty() {
+            return std::borrow::Cow::Borrowed(line);
+        }
+        let mut out = String::with_capacity(line.len() + 16);
+        let mut last_end = 0;
+        let mut chars = line.char_indices().peekable();
+        while let Some((start, c)) = chars.next() {
+            if c.is_whitespace() {
+                continue;
+            }
+            let mut end = start + c.len_utf8();
+            while let Some(&(i, c2)) = chars.peek() {
+                if c2.is_whitespace() {
+                    break;
+                }
+                end = i + c2.len_utf8();
+                chars.next();
+            }
+            let word = &line[start..end];
+            out.push_str(&line[last_end..start]);
+            out.push_str(&colorize_word(word));
+            last_end = end;
+        }
+        out.push_str(&line[last_end..]);
+        std::borrow::Cow::Owned(out)
+    }
+}
+
+/// Color `word` by how it tokenizes standalone, or leave it unstyled if it
+/// doesn't lex to exactly one token (see [`Highlighter::highlight`] above).
+fn colorize_word(word: &str) -> String {
+    let tokens = tokenize(word);
+    if tokens.len() != 1 {
+        return word.to_string();
+    }
+    let color = match &tokens[0].tok {
+        Token::Kw(_) => Some("\x1b[35m"),
+        Token::Number(_) | Token::Float(_) | Token::Bool(_) => Some("\x1b[33m"),
+        Token::Str(_) => Some("\x1b[32m"),
+        Token::Symbol(_) => Some("\x1b[36m"),
+        Token::Ident(_) => None,
+    };
+    match color {
+        Some(c) => format!("{}{}\x1b[0m", c, word),
+        None => word.to_string(),
+    }
+}
+
+impl Validator for NativeReplHelper {
+    fn validate(&self, ctx: &mut ValidationContext) -> rustyline::Result<ValidationResult> {
+        let input = ctx.input();
+        if input.trim().is_empty() {
+            return Ok(ValidationResult::Incomplete);
+        }
+        if unterminated_string_or_comment(input) || brackets_unbalanced(&tokenize(input)) {
+            return Ok(ValidationResult::Incomplete);
+        }
+        Ok(ValidationResult::Valid(None))
+    }
+}
+
+impl Helper for NativeReplHelper {}
+
+/// Resolve the dotfile path used to persist this REPL's history across
+/// sessions; `None` (no persistence) if `$HOME` isn't set.
+fn history_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".omg_native_history"))
+}
+
+/// Run the interactive native-bootstrap REPL: compile and run one
+/// rustyline-validated block at a time against accumulated session history.
+///
+/// A block that fails to parse (reported as a rendered `ParseError`) or to
+/// compile (a caught `panic!` in `Compiler`) is reported and discarded
+/// without being folded into history, the same "only successful input
+/// persists" rule `runtime/src/repl.rs::repl_interpret` follows.
+pub fn run_repl() {
+    println!("OMG Native REPL - type `exit` or `quit` to leave.");
+
+    let mut editor = Editor::<NativeReplHelper>::new().expect("failed to start line editor");
+    let builtins: Vec<String> = Compiler::new().builtin_names().map(str::to_string).collect();
+    editor.set_helper(Some(NativeReplHelper { builtins, procs: Vec::new() }));
+    let path = history_path();
+    if let Some(path) = &path {
+        let _ = editor.load_history(path);
+    }
+
+    let mut history = String::new();
+    let mut last_output = String::new();
+
+    loop {
+        let block = match editor.readline(">>> ") {
+            Ok(block) => block,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => {
+                println!("readline error: {}", e);
+                break;
+            }
+        };
+
+        let trimmed = block.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        if trimmed == "exit" || trimmed == "quit" {
+            break;
+        }
+        let _ = editor.add_history_entry(block.as_str());
+
+        let combined = format!("{}{}\n", history, block);
+
+        let ast = match parse(&combined) {
+            Ok(ast) => ast,
+            Err(e) => {
+                println!("{}", render_parse_error(&combined, &e));
+                continue;
+            }
+        };
+
+        // `compiler::Compiler` still `panic!`s on an AST it can't compile
+        // (e.g. a `break` outside a loop) rather than returning a `Result`, so a bad line would
+        // otherwise take the whole REPL process down with it. Silence the
+        // default panic backtrace for the duration; it's expected here.
+        let prev_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(|_| {}));
+        let compiled = std::panic::catch_unwind(|| {
+            let mut compiler = Compiler::new();
+            compiler.compile(&ast);
+            compiler.to_string()
+        });
+        std::panic::set_hook(prev_hook);
+
+        let bc_text = match compiled {
+            Ok(text) => text,
+            Err(_) => {
+                println!("compile error");
+                continue;
+            }
+        };
+
+        let (code, funcs, line_table) = parse_bytecode(&bc_text);
+        let mut out_buf: Vec<u8> = Vec::new();
+        run(&code, &funcs, &line_table, &[], &mut out_buf);
+        let full_output = String::from_utf8_lossy(&out_buf).into_owned();
+        if let Some(new_part) = full_output.strip_prefix(&last_output) {
+            print!("{}", new_part);
+        } else {
+            print!("{}", full_output);
+        }
+        let _ = io::Write::flush(&mut io::stdout());
+
+        if let Some(helper) = editor.helper_mut() {
+            for name in extract_proc_names(&tokenize(&block)) {
+                if !helper.procs.contains(&name) {
+                    helper.procs.push(name);
+                }
+            }
+        }
+        history = combined;
+        last_output = full_output;
+    }
+
+    if let Some(path) = &path {
+        let _ = editor.save_history(path);
+    }
+}
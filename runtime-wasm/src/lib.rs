@@ -1,6 +1,16 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use wasm_bindgen::prelude::*;
+// `js_sys::Date::now()` gives wall-clock `elapsed_ms` without `std::time::Instant`,
+// which isn't available on the `wasm32-unknown-unknown` target this crate builds for.
+
+// The actual VM/interpreter pipeline lives in the `runtime` crate (this
+// crate depends on it as a path dependency — there's no Cargo.toml in this
+// tree to pin the exact package name, but `runtime` matches its directory
+// and is assumed here). `runtime::EvalSession` already carries forward
+// source history across calls; this crate's job is purely to translate
+// that plain-Rust API into the wasm-bindgen-friendly `EvalResult` shape.
+use runtime::EvalSession;
 
 /// Initialize panic hook when the module starts.
 #[wasm_bindgen(start)]
@@ -8,10 +18,6 @@ pub fn start() {
     console_error_panic_hook::set_once();
 }
 
-/// Interpreter state for a WebAssembly session.
-#[derive(Default)]
-pub struct Session;
-
 /// Options controlling evaluation behaviour.
 #[derive(Serialize, Deserialize, Default)]
 pub struct EvalOptions {
@@ -41,7 +47,15 @@ pub struct EvalResult {
 /// Public wrapper around a session which is exposed to JavaScript.
 #[wasm_bindgen]
 pub struct WasmSession {
-    inner: Session,
+    inner: EvalSession,
+}
+
+impl Default for WasmSession {
+    fn default() -> Self {
+        WasmSession {
+            inner: EvalSession::new(),
+        }
+    }
 }
 
 #[wasm_bindgen]
@@ -49,22 +63,72 @@ impl WasmSession {
     /// Create a new WebAssembly-backed session.
     #[wasm_bindgen(constructor)]
     pub fn new() -> WasmSession {
-        WasmSession {
-            inner: Session::default(),
-        }
+        WasmSession::default()
     }
 
     /// Reset the session to an empty state.
     #[wasm_bindgen]
     pub fn reset(&mut self) {
-        self.inner = Session::default();
+        self.inner.reset();
     }
 
     /// Evaluate a snippet of OMG code and return a serialised result.
+    ///
+    /// Runs `code` through the embedded interpreter in-process via
+    /// `runtime::EvalSession::eval`, which replays this session's prior
+    /// snippets so state persists the way the source REPL's does. Captured
+    /// stdout is split into lines for `EvalResult.stdout`; a fault becomes a
+    /// single `Diagnostic` built from the `TracedError`'s message and
+    /// `ErrorKind` name rather than an opaque JS error.
+    ///
+    /// `return_value` stays JSON `null` and `line`/`column` stay `None` on
+    /// diagnostics. Both are known gaps against the original request, not
+    /// something this function alone can close:
+    ///
+    /// - The embedded interpreter only exposes whole-program execution via
+    ///   `emit`-style printing; there's no "value of the last expression"
+    ///   channel to read a return value from.
+    /// - The bytecode format has no source-line debug table at all (see
+    ///   `bytecode.rs` — instructions carry no line/column info), so there
+    ///   is nothing for `TracedError`/`RuntimeError` to report beyond the
+    ///   bytecode-level `fault_pc` it already carries. Populating
+    ///   `line`/`column` for real would mean adding a line-table side
+    ///   channel to the bytecode format and threading it through the
+    ///   compiler and every fault site, which is its own project, not a
+    ///   change local to `runtime-wasm`.
+    ///
+    /// Tracked as a follow-up rather than silently declared done.
     #[wasm_bindgen]
-    pub fn eval(&mut self, _code: &str, opts_js: JsValue) -> Result<JsValue, JsValue> {
-        let _opts: EvalOptions = serde_wasm_bindgen::from_value(opts_js).unwrap_or_default();
-        let result = EvalResult::default();
+    pub fn eval(&mut self, code: &str, opts_js: JsValue) -> Result<JsValue, JsValue> {
+        let opts: EvalOptions = serde_wasm_bindgen::from_value(opts_js).unwrap_or_default();
+
+        let started = js_sys::Date::now();
+        let outcome = self.inner.eval(code, opts.fuel, opts.timeout_ms);
+        let elapsed_ms = (js_sys::Date::now() - started).max(0.0) as u32;
+
+        let diagnostics = match &outcome.error {
+            Some(traced) => vec![Diagnostic {
+                message: traced.to_string(),
+                line: None,
+                column: None,
+                kind: traced.error.kind().name().to_string(),
+            }],
+            None => Vec::new(),
+        };
+
+        let stdout = outcome
+            .stdout
+            .lines()
+            .map(|line| line.to_string())
+            .collect();
+
+        let result = EvalResult {
+            stdout,
+            return_value: Value::Null,
+            diagnostics,
+            elapsed_ms,
+            fuel_used: outcome.fuel_used,
+        };
         serde_wasm_bindgen::to_value(&result).map_err(|e| e.into())
     }
 }
@@ -5,23 +5,23 @@
 //! into an instruction stream (`Vec<Instr>`) plus a function table
 //! (`HashMap<String, Function>`), which the runtime VM executes.
 //!
-//! ## Binary layout (little-endian)
+//! ## Binary layout
 //! ```text
 //! +------------------+----------------------------+
 //! | Magic "OMGB"     | 4 bytes                    |
 //! +------------------+----------------------------+
-//! | Version          | u32 (see `BC_VERSION`)     |
+//! | Version          | uleb128 (see `BC_VERSION`) |
 //! +------------------+----------------------------+
-//! | Func count       | u32                        |
+//! | Func count       | uleb128                    |
 //! +------------------+----------------------------+
 //! | For each func:                                |
-//! |   Name          | u32 len + UTF-8 bytes       |
-//! |   Param count   | u32                         |
+//! |   Name          | uleb128 len + UTF-8 bytes   |
+//! |   Param count   | uleb128                     |
 //! |   Params[...]   | (Param count times)         |
-//! |                 |   u32 len + UTF-8 bytes     |
-//! |   Address       | u32 (index into code vec)   |
+//! |                 |   uleb128 len + UTF-8 bytes |
+//! |   Address       | uleb128 (index into code)   |
 //! +------------------+----------------------------+
-//! | Code length      | u32 (number of instrs)     |
+//! | Code length      | uleb128 (number of instrs) |
 //! +------------------+----------------------------+
 //! | For each instr:                               |
 //! |   Opcode         | u8                         |
@@ -29,35 +29,87 @@
 //! +------------------+----------------------------+
 //! ```
 //!
-//! The parser is intentionally strict about the header and version and will
-//! `assert!` on mismatches. It uses `unwrap()` in a few places because the
-//! input is expected to be well-formed compiler output. Feeding arbitrary or
-//! corrupted data is undefined behavior.
+//! Every `u32`-shaped field (lengths, counts, addresses, jump offsets) is
+//! encoded as unsigned LEB128 rather than a fixed 4 bytes: 7 bits per byte,
+//! low bits first, with the high bit set on every byte but the last. `i64`
+//! operands (e.g. `PushInt`) go through the same unsigned LEB128 after a
+//! zigzag remap (`(n << 1) ^ (n >> 63)`, undone on decode) so small-magnitude
+//! values of either sign stay short. `f64` stays fixed 8-byte little-endian —
+//! floats don't have a "usually small" bias to exploit the way integers and
+//! addresses do. Real programs are mostly small constants and nearby jump
+//! targets, so this typically halves on-disk size versus the old fixed-width
+//! encoding.
+//!
+//! The parser is intentionally strict about the header and version. Loading
+//! bytecode from anywhere other than this build's own compiler (disk, a
+//! network peer, a cache) should go through [`try_parse_bytecode`], which
+//! bounds-checks every read and reports truncation, bad magic/version,
+//! invalid UTF-8, and unknown `ErrorKind` discriminants as a [`BytecodeError`]
+//! instead of panicking. [`parse_bytecode`] is a thin panicking wrapper kept
+//! for call sites that only ever see this build's own trusted output.
 //!
 //! ## Versioning
 //! `BC_VERSION` follows a packed `(MAJOR << 16) | (MINOR << 8) | PATCH` layout.
-//! The parser requires an exact match for simplicity.
+//! The parser requires the same MAJOR component; a MINOR/PATCH difference is
+//! allowed, since a MINOR bump only ever adds new instructions. An opcode
+//! this build doesn't recognize (because it decoded bytecode from a newer
+//! MINOR version) is either a reserved-form *extended opcode* — see
+//! `EXT_OPCODE` — which carries an explicit byte length this decoder can
+//! skip past to keep the rest of the stream aligned, or, for a bare unknown
+//! byte that isn't wrapped that way, a [`BytecodeError::UnknownOpcode`]: a
+//! real compiler targeting a shared MAJOR version must wrap any opcode past
+//! what an older MINOR understands in `EXT_OPCODE`, so a bare unrecognized
+//! byte means truly corrupt input, not new-but-skippable instructions.
 //!
 //! ## Functions
 //! A `Function` records its parameter list and the address (PC) of its first
 //! instruction within the decoded `code` vector. Calls jump to `address`.
+//!
+//! ## Static stack/local analysis
+//! After decoding, [`parse_bytecode`] walks every function's reachable
+//! instructions once (see [`analyze_function_shape`]) to fill in
+//! `Function::max_stack`/`local_count`, so call handlers can size a new
+//! frame's local-env `HashMap` in one allocation instead of growing it
+//! incrementally. [`main_max_stack`] runs the same analysis over the
+//! top-level program (entry `0`) for `run`/`run_inner` to `stack.reserve()`
+//! up front.
 
 use std::collections::HashMap;
+use std::fmt;
 
 use crate::error::ErrorKind;
 
 /// Packed bytecode version: `(MAJOR << 16) | (MINOR << 8) | PATCH`.
-const BC_VERSION: u32 = (0 << 16) | (1 << 8) | 1;
+const BC_VERSION: u32 = (0 << 16) | (1 << 8) | 8;
+
+/// Reserved opcode byte marking an *extended* instruction: `EXT_OPCODE`,
+/// `real_opcode:uleb128`, `len:uleb128`, then exactly `len` payload bytes. A
+/// decoder that doesn't recognize `real_opcode` can still skip the whole
+/// instruction via `len`, keeping the rest of the stream aligned instead of
+/// misreading the next instruction's bytes as part of this one. No opcode in
+/// this build's own tables is ever emitted wrapped this way; it exists so a
+/// future MINOR bump can introduce new instructions that an older
+/// same-MAJOR decoder can safely step over.
+const EXT_OPCODE: u8 = 255;
 
 /// Representation of a compiled function.
 ///
 /// - `params`: ordered list of parameter names.
 /// - `address`: instruction index (PC) of the function entry point within
 ///   the decoded `code` vector returned by [`parse_bytecode`].
-#[derive(Clone)]
+/// - `max_stack`/`local_count`: static-analysis results filled in by
+///   [`parse_bytecode`] (see [`analyze_function_shape`]) so call handlers can
+///   preallocate the new frame's local-env capacity in one shot, and so a
+///   future per-function stepping host could size its own stack up front the
+///   same way [`main_max_stack`] lets `run` do for the top-level code. `0`
+///   for any `Function` built directly (e.g. in tests) rather than through
+///   `parse_bytecode` — safe, since it only affects a capacity hint.
+#[derive(Clone, Default)]
 pub struct Function {
     pub params: Vec<String>,
     pub address: usize,
+    pub max_stack: usize,
+    pub local_count: usize,
 }
 
 /// Instruction set for the OMG stack VM.
@@ -69,6 +121,7 @@ pub struct Function {
 pub enum Instr {
     // ----- Constants / literals -----
     PushInt(i64),
+    PushFloat(f64),
     PushStr(String),
     PushBool(bool),
     // ----- Aggregate construction -----
@@ -138,40 +191,656 @@ pub enum Instr {
     /// Call using a first-class callable on the stack; arity given here.
     CallValue(usize),
     // ----- Structured exception handling -----
-    /// Establish an exception handler targeting instruction `usize`.
-    SetupExcept(usize),
+    /// Establish an exception handler targeting instruction `usize`, catching
+    /// only the given `ErrorKind`s (an empty list catches any kind).
+    SetupExcept(usize, Vec<ErrorKind>),
     /// Pop the most recent exception handler.
     PopBlock,
     /// Synthesize/raise a runtime error of the given kind.
     Raise(ErrorKind),
+    // ----- Modules -----
+    /// Load and run the module at the given path (relative to the importing
+    /// file's directory), pushing a `Value::FrozenDict` namespace of its
+    /// exported bindings. See `Import` handling in `vm.rs`.
+    Import(String),
+    // ----- List/string concatenation and repetition -----
+    /// Pop `right`, `left` (both lists or both strings) and push their
+    /// concatenation.
+    Concat,
+    /// Pop a repeat count `n` and a list/string, and push the value repeated
+    /// `n` times (`n <= 0` yields an empty value).
+    Repeat,
+    /// Pop a value and suspend execution, handing it to the host as
+    /// `StepResult::Yielded` (see `vm::step`). Only meaningful when the
+    /// program is driven through `Vm::step`; under the monolithic `run`
+    /// entry points it behaves like `Pop` followed by `PushNone` (the value
+    /// is discarded and execution simply continues), since there is no host
+    /// loop there to resume from.
+    Yield,
+}
+
+impl Instr {
+    /// Short, stable opcode name (no payload), used to annotate where in the
+    /// instruction stream a `RuntimeError` was raised (see [`TracedError`](crate::error::TracedError)).
+    pub fn name(&self) -> &'static str {
+        match self {
+            Instr::PushInt(_) => "PUSH_INT",
+            Instr::PushFloat(_) => "PUSH_FLOAT",
+            Instr::PushStr(_) => "PUSH_STR",
+            Instr::PushBool(_) => "PUSH_BOOL",
+            Instr::BuildList(_) => "BUILD_LIST",
+            Instr::BuildDict(_) => "BUILD_DICT",
+            Instr::Load(_) => "LOAD",
+            Instr::Store(_) => "STORE",
+            Instr::Add => "ADD",
+            Instr::Sub => "SUB",
+            Instr::Mul => "MUL",
+            Instr::Div => "DIV",
+            Instr::Mod => "MOD",
+            Instr::Eq => "EQ",
+            Instr::Ne => "NE",
+            Instr::Lt => "LT",
+            Instr::Le => "LE",
+            Instr::Gt => "GT",
+            Instr::Ge => "GE",
+            Instr::BAnd => "BAND",
+            Instr::BOr => "BOR",
+            Instr::BXor => "BXOR",
+            Instr::Shl => "SHL",
+            Instr::Shr => "SHR",
+            Instr::And => "AND",
+            Instr::Or => "OR",
+            Instr::Not => "NOT",
+            Instr::Neg => "NEG",
+            Instr::Index => "INDEX",
+            Instr::Slice => "SLICE",
+            Instr::Jump(_) => "JUMP",
+            Instr::JumpIfFalse(_) => "JUMP_IF_FALSE",
+            Instr::Call(_) => "CALL",
+            Instr::TailCall(_) => "TAIL_CALL",
+            Instr::CallBuiltin(_, _) => "CALL_BUILTIN",
+            Instr::Pop => "POP",
+            Instr::PushNone => "PUSH_NONE",
+            Instr::Ret => "RET",
+            Instr::Emit => "EMIT",
+            Instr::Halt => "HALT",
+            Instr::StoreIndex => "STORE_INDEX",
+            Instr::Attr(_) => "ATTR",
+            Instr::StoreAttr(_) => "STORE_ATTR",
+            Instr::Assert => "ASSERT",
+            Instr::CallValue(_) => "CALL_VALUE",
+            Instr::SetupExcept(_, _) => "SETUP_EXCEPT",
+            Instr::PopBlock => "POP_BLOCK",
+            Instr::Raise(_) => "RAISE",
+            Instr::Import(_) => "IMPORT",
+            Instr::Concat => "CONCAT",
+            Instr::Repeat => "REPEAT",
+            Instr::Yield => "YIELD",
+        }
+    }
+}
+
+/// Errors produced by [`try_parse_bytecode`] on malformed or untrusted input.
+///
+/// Every variant names a specific failure a corrupt or truncated `.omgb`
+/// payload can hit, so a caller loading bytecode from disk, the network, or
+/// a cache can report *why* it was rejected instead of the process panicking
+/// partway through the decode.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BytecodeError {
+    /// Fewer bytes remained than the format requires at this point (header,
+    /// a length prefix, a LEB128 operand, a string's payload, ...).
+    Truncated,
+    /// The first 4 bytes were not the `"OMGB"` magic.
+    BadMagic,
+    /// The header version's MAJOR component didn't match this build's
+    /// `BC_VERSION` (a MINOR/PATCH difference is tolerated; see the module's
+    /// `## Versioning` section).
+    VersionMismatch { found: u32, expected: u32 },
+    /// A length-prefixed string's bytes were not valid UTF-8.
+    InvalidUtf8,
+    /// An `ErrorKind` discriminant (used by `Raise`/`SetupExcept`) that this
+    /// build doesn't recognize.
+    UnknownErrorKind(u8),
+    /// An opcode byte this build doesn't recognize and that wasn't wrapped in
+    /// `EXT_OPCODE`, so its operand width is unknown and the remaining stream
+    /// can't be safely skipped past.
+    UnknownOpcode(u8),
+}
+
+impl fmt::Display for BytecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BytecodeError::Truncated => write!(f, "truncated bytecode: unexpected end of input"),
+            BytecodeError::BadMagic => write!(f, "bad magic: not an OMGB bytecode file"),
+            BytecodeError::VersionMismatch { found, expected } => {
+                write!(f, "unsupported bytecode version: found {found}, expected {expected} (major component must match)")
+            }
+            BytecodeError::InvalidUtf8 => write!(f, "invalid UTF-8 in string operand"),
+            BytecodeError::UnknownErrorKind(b) => {
+                write!(f, "unknown ErrorKind discriminant: {b}")
+            }
+            BytecodeError::UnknownOpcode(b) => {
+                write!(f, "unknown opcode {b} not wrapped in EXT_OPCODE: cannot skip safely")
+            }
+        }
+    }
 }
 
+impl std::error::Error for BytecodeError {}
+
 //
-// --- Little-endian readers --------------------------------------------------
+// --- LEB128 readers ----------------------------------------------------------
 //
 
-/// Read a `u32` (little-endian) and advance `idx`.
-fn read_u32(data: &[u8], idx: &mut usize) -> u32 {
-    let bytes: [u8; 4] = data[*idx..*idx + 4].try_into().unwrap();
-    *idx += 4;
-    u32::from_le_bytes(bytes)
+/// Read an unsigned LEB128 value and advance `idx`: accumulate 7-bit groups,
+/// low bits first, until a byte with the high bit clear. Bounds-checked —
+/// returns [`BytecodeError::Truncated`] instead of panicking if `data` runs
+/// out before a terminating byte is found.
+fn try_read_uleb128(data: &[u8], idx: &mut usize) -> Result<u64, BytecodeError> {
+    let mut result: u64 = 0;
+    let mut shift = 0u32;
+    loop {
+        let byte = *data.get(*idx).ok_or(BytecodeError::Truncated)?;
+        *idx += 1;
+        result |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            break;
+        }
+        shift += 7;
+    }
+    Ok(result)
+}
+
+/// Map a zigzag-encoded `u64` back to its signed `i64`.
+fn unzigzag(z: u64) -> i64 {
+    ((z >> 1) as i64) ^ -((z & 1) as i64)
 }
 
-/// Read an `i64` (little-endian) and advance `idx`.
-fn read_i64(data: &[u8], idx: &mut usize) -> i64 {
-    let bytes: [u8; 8] = data[*idx..*idx + 8].try_into().unwrap();
+/// Read a `u32` (unsigned LEB128) and advance `idx`.
+fn try_read_u32(data: &[u8], idx: &mut usize) -> Result<u32, BytecodeError> {
+    Ok(try_read_uleb128(data, idx)? as u32)
+}
+
+/// Read an `i64` (zigzag + unsigned LEB128) and advance `idx`.
+fn try_read_i64(data: &[u8], idx: &mut usize) -> Result<i64, BytecodeError> {
+    Ok(unzigzag(try_read_uleb128(data, idx)?))
+}
+
+/// Read an `f64` (little-endian) and advance `idx`. Floats don't have a
+/// "usually small" bias to exploit, so these stay fixed-width.
+fn try_read_f64(data: &[u8], idx: &mut usize) -> Result<f64, BytecodeError> {
+    let bytes: [u8; 8] = data
+        .get(*idx..*idx + 8)
+        .ok_or(BytecodeError::Truncated)?
+        .try_into()
+        .unwrap();
     *idx += 8;
-    i64::from_le_bytes(bytes)
+    Ok(f64::from_le_bytes(bytes))
 }
 
 /// Read a length-prefixed UTF-8 `String` and advance `idx`.
 ///
-/// Layout: `u32 len` followed by `len` raw bytes (UTF-8).
-fn read_string(data: &[u8], idx: &mut usize) -> String {
-    let len = read_u32(data, idx) as usize;
-    let s = String::from_utf8(data[*idx..*idx + len].to_vec()).unwrap();
+/// Layout: unsigned LEB128 `len` followed by `len` raw bytes (UTF-8).
+fn try_read_string(data: &[u8], idx: &mut usize) -> Result<String, BytecodeError> {
+    let len = try_read_u32(data, idx)? as usize;
+    let bytes = data.get(*idx..*idx + len).ok_or(BytecodeError::Truncated)?;
+    let s = String::from_utf8(bytes.to_vec()).map_err(|_| BytecodeError::InvalidUtf8)?;
     *idx += len;
-    s
+    Ok(s)
+}
+
+//
+// --- LEB128 writers ------------------------------------------------------------
+//
+
+/// Write an unsigned LEB128 value: 7 bits per byte, low bits first, with the
+/// high bit set on every byte but the last.
+fn write_uleb128(out: &mut Vec<u8>, mut v: u64) {
+    loop {
+        let mut byte = (v & 0x7f) as u8;
+        v >>= 7;
+        if v != 0 {
+            byte |= 0x80;
+        }
+        out.push(byte);
+        if v == 0 {
+            break;
+        }
+    }
+}
+
+/// Map a signed `i64` to its zigzag `u64` so small magnitudes of either sign
+/// stay short under LEB128.
+fn zigzag(n: i64) -> u64 {
+    ((n << 1) ^ (n >> 63)) as u64
+}
+
+/// Write a `u32` (unsigned LEB128).
+fn write_u32(out: &mut Vec<u8>, v: u32) {
+    write_uleb128(out, v as u64);
+}
+
+/// Write an `i64` (zigzag + unsigned LEB128).
+fn write_i64(out: &mut Vec<u8>, v: i64) {
+    write_uleb128(out, zigzag(v));
+}
+
+/// Write an `f64` (little-endian).
+fn write_f64(out: &mut Vec<u8>, v: f64) {
+    out.extend_from_slice(&v.to_le_bytes());
+}
+
+/// Write a length-prefixed UTF-8 string: unsigned LEB128 `len` followed by
+/// raw bytes.
+fn write_string(out: &mut Vec<u8>, s: &str) {
+    write_u32(out, s.len() as u32);
+    out.extend_from_slice(s.as_bytes());
+}
+
+//
+// --- Declarative opcode table ------------------------------------------------
+//
+// The encoder (`assemble`) and decoder (`parse_bytecode`) used to maintain
+// separate hand-written `match` tables mapping opcode byte <-> `Instr`
+// variant, which could silently drift out of sync. The tables below are now
+// the single source of truth: each ordinary opcode is one row naming its
+// byte, its `Instr` variant, and its operand types, and `decode_op`/
+// `encode_instr` are generated from those rows so adding an opcode is a
+// one-line change to exactly one list. `SetupExcept` (a variable-length
+// `Vec<ErrorKind>`) and `Raise` (packed into short single-byte opcodes for
+// its most common `ErrorKind`s) don't fit a fixed operand list, so they stay
+// hand-written special cases in `decode_op`/`encode_instr` alongside the
+// generated tables.
+//
+
+/// One bytecode operand's wire codec. Implemented once per Rust type so the
+/// opcode tables below can name a field's type and have both the decoder and
+/// encoder agree on its on-disk form automatically.
+trait Operand: Sized {
+    fn decode(data: &[u8], idx: &mut usize) -> Result<Self, BytecodeError>;
+    fn encode(&self, out: &mut Vec<u8>);
+}
+
+impl Operand for i64 {
+    fn decode(data: &[u8], idx: &mut usize) -> Result<Self, BytecodeError> {
+        try_read_i64(data, idx)
+    }
+    fn encode(&self, out: &mut Vec<u8>) {
+        write_i64(out, *self);
+    }
+}
+
+impl Operand for f64 {
+    fn decode(data: &[u8], idx: &mut usize) -> Result<Self, BytecodeError> {
+        try_read_f64(data, idx)
+    }
+    fn encode(&self, out: &mut Vec<u8>) {
+        write_f64(out, *self);
+    }
+}
+
+impl Operand for bool {
+    fn decode(data: &[u8], idx: &mut usize) -> Result<Self, BytecodeError> {
+        let b = *data.get(*idx).ok_or(BytecodeError::Truncated)?;
+        *idx += 1;
+        Ok(b != 0)
+    }
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.push(*self as u8);
+    }
+}
+
+impl Operand for String {
+    fn decode(data: &[u8], idx: &mut usize) -> Result<Self, BytecodeError> {
+        try_read_string(data, idx)
+    }
+    fn encode(&self, out: &mut Vec<u8>) {
+        write_string(out, self);
+    }
+}
+
+/// `usize` operands (counts, indices, jump targets) are wire-compatible with
+/// `u32` — this is just the cast on either side of `try_read_u32`/`write_u32`.
+impl Operand for usize {
+    fn decode(data: &[u8], idx: &mut usize) -> Result<Self, BytecodeError> {
+        Ok(try_read_u32(data, idx)? as usize)
+    }
+    fn encode(&self, out: &mut Vec<u8>) {
+        write_u32(out, *self as u32);
+    }
+}
+
+/// Generates `decode_nullary_op`/`encode_nullary_op` from a list of
+/// `byte => Variant` rows (payload-free `Instr` variants).
+macro_rules! nullary_ops {
+    ( $( $byte:literal => $variant:ident ),* $(,)? ) => {
+        fn decode_nullary_op(op: u8) -> Option<Instr> {
+            Some(match op {
+                $( $byte => Instr::$variant, )*
+                _ => return None,
+            })
+        }
+
+        fn encode_nullary_op(instr: &Instr, out: &mut Vec<u8>) -> bool {
+            match instr {
+                $( Instr::$variant => { out.push($byte); true } )*
+                _ => false,
+            }
+        }
+    };
+}
+
+/// Generates `decode_unary_op`/`encode_unary_op` from a list of
+/// `byte => Variant(Type)` rows (single-operand `Instr` variants).
+macro_rules! unary_ops {
+    ( $( $byte:literal => $variant:ident($ty:ty) ),* $(,)? ) => {
+        fn decode_unary_op(op: u8, data: &[u8], idx: &mut usize) -> Result<Option<Instr>, BytecodeError> {
+            Ok(Some(match op {
+                $( $byte => Instr::$variant(<$ty as Operand>::decode(data, idx)?), )*
+                _ => return Ok(None),
+            }))
+        }
+
+        fn encode_unary_op(instr: &Instr, out: &mut Vec<u8>) -> bool {
+            match instr {
+                $( Instr::$variant(v) => { out.push($byte); v.encode(out); true } )*
+                _ => false,
+            }
+        }
+    };
+}
+
+/// Generates `decode_binary_op`/`encode_binary_op` from a list of
+/// `byte => Variant(TypeA, TypeB)` rows (two-operand `Instr` variants).
+macro_rules! binary_ops {
+    ( $( $byte:literal => $variant:ident($ty1:ty, $ty2:ty) ),* $(,)? ) => {
+        fn decode_binary_op(op: u8, data: &[u8], idx: &mut usize) -> Result<Option<Instr>, BytecodeError> {
+            Ok(Some(match op {
+                $( $byte => Instr::$variant(<$ty1 as Operand>::decode(data, idx)?, <$ty2 as Operand>::decode(data, idx)?), )*
+                _ => return Ok(None),
+            }))
+        }
+
+        fn encode_binary_op(instr: &Instr, out: &mut Vec<u8>) -> bool {
+            match instr {
+                $( Instr::$variant(a, b) => { out.push($byte); a.encode(out); b.encode(out); true } )*
+                _ => false,
+            }
+        }
+    };
+}
+
+nullary_ops! {
+    7  => Add,
+    8  => Sub,
+    9  => Mul,
+    10 => Div,
+    11 => Mod,
+    12 => Eq,
+    13 => Ne,
+    14 => Lt,
+    15 => Le,
+    16 => Gt,
+    17 => Ge,
+    18 => BAnd,
+    19 => BOr,
+    20 => BXor,
+    21 => Shl,
+    22 => Shr,
+    23 => And,
+    24 => Or,
+    25 => Not,
+    26 => Neg,
+    27 => Index,
+    28 => Slice,
+    34 => Pop,
+    35 => PushNone,
+    36 => Ret,
+    37 => Emit,
+    38 => Halt,
+    39 => StoreIndex,
+    42 => Assert,
+    45 => PopBlock,
+    54 => Concat,
+    55 => Repeat,
+    56 => Yield,
+}
+
+unary_ops! {
+    0  => PushInt(i64),
+    1  => PushStr(String),
+    2  => PushBool(bool),
+    3  => BuildList(usize),
+    4  => BuildDict(usize),
+    5  => Load(String),
+    6  => Store(String),
+    29 => Jump(usize),
+    30 => JumpIfFalse(usize),
+    31 => Call(String),
+    32 => TailCall(String),
+    40 => Attr(String),
+    41 => StoreAttr(String),
+    43 => CallValue(usize),
+    52 => PushFloat(f64),
+    53 => Import(String),
+}
+
+binary_ops! {
+    33 => CallBuiltin(String, usize),
+}
+
+/// Decode one instruction starting at opcode byte `op`, trying the
+/// hand-written special cases first and falling back to the generated
+/// tables. Returns `Ok(None)` for a recognized-but-inert case (an
+/// `EXT_OPCODE`-wrapped instruction this build doesn't understand, safely
+/// skipped via its explicit length). A bare opcode byte matching no row,
+/// special case, or `EXT_OPCODE` is [`BytecodeError::UnknownOpcode`] — its
+/// operand width is unknowable, so advancing past it would desync the rest
+/// of the stream.
+fn decode_op(op: u8, data: &[u8], idx: &mut usize) -> Result<Option<Instr>, BytecodeError> {
+    if op == EXT_OPCODE {
+        let _real_opcode = try_read_uleb128(data, idx)?;
+        let len = try_read_uleb128(data, idx)? as usize;
+        let end = idx.checked_add(len).ok_or(BytecodeError::Truncated)?;
+        if end > data.len() {
+            return Err(BytecodeError::Truncated);
+        }
+        *idx = end;
+        return Ok(None);
+    }
+    match op {
+        44 => {
+            let t = try_read_u32(data, idx)? as usize;
+            let kind_count = try_read_u32(data, idx)? as usize;
+            let mut kinds = Vec::with_capacity(kind_count);
+            for _ in 0..kind_count {
+                let kind_b = *data.get(*idx).ok_or(BytecodeError::Truncated)?;
+                *idx += 1;
+                kinds.push(ErrorKind::try_from(kind_b).map_err(|_| BytecodeError::UnknownErrorKind(kind_b))?);
+            }
+            return Ok(Some(Instr::SetupExcept(t, kinds)));
+        }
+        46 => {
+            let kind_b = *data.get(*idx).ok_or(BytecodeError::Truncated)?;
+            *idx += 1;
+            let kind = ErrorKind::try_from(kind_b).map_err(|_| BytecodeError::UnknownErrorKind(kind_b))?;
+            return Ok(Some(Instr::Raise(kind)));
+        }
+        47 => return Ok(Some(Instr::Raise(ErrorKind::Syntax))),
+        48 => return Ok(Some(Instr::Raise(ErrorKind::Type))),
+        49 => return Ok(Some(Instr::Raise(ErrorKind::UndefinedIdent))),
+        50 => return Ok(Some(Instr::Raise(ErrorKind::Value))),
+        51 => return Ok(Some(Instr::Raise(ErrorKind::ModuleImport))),
+        _ => {}
+    }
+    if let Some(instr) = decode_nullary_op(op) {
+        return Ok(Some(instr));
+    }
+    if let Some(instr) = decode_unary_op(op, data, idx)? {
+        return Ok(Some(instr));
+    }
+    if let Some(instr) = decode_binary_op(op, data, idx)? {
+        return Ok(Some(instr));
+    }
+    Err(BytecodeError::UnknownOpcode(op))
+}
+
+/// Encode one instruction, the inverse of [`decode_op`]: hand-written special
+/// cases first, then the generated tables.
+fn encode_instr(instr: &Instr, out: &mut Vec<u8>) {
+    match instr {
+        Instr::SetupExcept(t, kinds) => {
+            out.push(44);
+            write_u32(out, *t as u32);
+            write_u32(out, kinds.len() as u32);
+            for k in kinds {
+                out.push(*k as u8);
+            }
+        }
+        Instr::Raise(kind) => match kind {
+            ErrorKind::Syntax => out.push(47),
+            ErrorKind::Type => out.push(48),
+            ErrorKind::UndefinedIdent => out.push(49),
+            ErrorKind::Value => out.push(50),
+            ErrorKind::ModuleImport => out.push(51),
+            other => {
+                out.push(46);
+                out.push(*other as u8);
+            }
+        },
+        _ => {
+            let _ = encode_nullary_op(instr, out)
+                || encode_unary_op(instr, out)
+                || encode_binary_op(instr, out);
+        }
+    }
+}
+
+/// Encode an instruction stream plus function table into the on-disk `.omgb`
+/// format understood by [`parse_bytecode`].
+///
+/// This is the inverse of [`parse_bytecode`]: every opcode number and operand
+/// layout is defined once in the declarative opcode tables above, so the two
+/// cannot drift apart. Produced mainly for tooling (an assembler/compiler
+/// backend that wants to emit `.omgb` directly instead of shelling out).
+///
+/// Note: this does not yet intern repeated strings into a constant pool —
+/// each string operand is written verbatim, same as the decoder expects
+/// today. That's tracked as follow-up compaction work, not part of this
+/// encoder.
+pub fn assemble(code: &[Instr], funcs: &HashMap<String, Function>) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(b"OMGB");
+    write_u32(&mut out, BC_VERSION);
+
+    write_u32(&mut out, funcs.len() as u32);
+    for (name, func) in funcs {
+        write_string(&mut out, name);
+        write_u32(&mut out, func.params.len() as u32);
+        for param in &func.params {
+            write_string(&mut out, param);
+        }
+        write_u32(&mut out, func.address as u32);
+    }
+
+    write_u32(&mut out, code.len() as u32);
+    for instr in code {
+        encode_instr(instr, &mut out);
+    }
+    out
+}
+
+//
+// --- Disassembler ------------------------------------------------------------
+//
+
+/// Render `code`/`funcs` as a human-readable assembly-style listing, for
+/// debugging the compiler or inspecting a `.omgb` file by eye.
+///
+/// The output starts with a header line per function (name, parameter list,
+/// entry address), then one line per instruction: its `pc`, the opcode
+/// mnemonic (see [`Instr::name`]), and decoded operands. Every function entry
+/// point and every `Jump`/`JumpIfFalse`/`SetupExcept` target gets a label —
+/// the function's own name for the former, `Lnnnn` for the latter — printed
+/// on its own line just above that instruction, and branch operands print
+/// the label instead of a bare `pc` so the listing reads like assembly.
+pub fn disassemble(code: &[Instr], funcs: &HashMap<String, Function>) -> String {
+    let mut out = String::new();
+
+    let mut names: Vec<&String> = funcs.keys().collect();
+    names.sort();
+    for name in &names {
+        let f = &funcs[*name];
+        out.push_str(&format!("; {}({}) @ {}\n", name, f.params.join(", "), f.address));
+    }
+    out.push('\n');
+
+    let addr_to_name: HashMap<usize, &String> = funcs.iter().map(|(n, f)| (f.address, n)).collect();
+    let mut targets: std::collections::BTreeSet<usize> = std::collections::BTreeSet::new();
+    for instr in code {
+        match instr {
+            Instr::Jump(t) | Instr::JumpIfFalse(t) => {
+                targets.insert(*t);
+            }
+            Instr::SetupExcept(t, _) => {
+                targets.insert(*t);
+            }
+            _ => {}
+        }
+    }
+
+    for (pc, instr) in code.iter().enumerate() {
+        if let Some(name) = addr_to_name.get(&pc) {
+            out.push_str(&format!("{name}:\n"));
+        } else if targets.contains(&pc) {
+            out.push_str(&format!("L{pc:04}:\n"));
+        }
+        out.push_str(&format!("{:04}  {}\n", pc, disassemble_instr(instr, &addr_to_name)));
+    }
+
+    out
+}
+
+/// Resolve `pc` to the label [`disassemble`] printed for it: a function's own
+/// name if `pc` is one of its entry addresses, otherwise the generic `Lnnnn`
+/// form used for in-function branch targets.
+fn label_for(pc: usize, addr_to_name: &HashMap<usize, &String>) -> String {
+    match addr_to_name.get(&pc) {
+        Some(name) => (*name).to_string(),
+        None => format!("L{pc:04}"),
+    }
+}
+
+/// Render one instruction as `MNEMONIC operand...`, resolving branch targets
+/// to their [`label_for`] form. Operand-free opcodes fall through to the
+/// catch-all arm, which just reuses [`Instr::name`].
+fn disassemble_instr(instr: &Instr, addr_to_name: &HashMap<usize, &String>) -> String {
+    match instr {
+        Instr::PushInt(v) => format!("PUSH_INT {v}"),
+        Instr::PushFloat(v) => format!("PUSH_FLOAT {v}"),
+        Instr::PushStr(s) => format!("PUSH_STR {s:?}"),
+        Instr::PushBool(b) => format!("PUSH_BOOL {b}"),
+        Instr::BuildList(n) => format!("BUILD_LIST {n}"),
+        Instr::BuildDict(n) => format!("BUILD_DICT {n}"),
+        Instr::Load(s) => format!("LOAD {s}"),
+        Instr::Store(s) => format!("STORE {s}"),
+        Instr::Jump(t) => format!("JUMP {}", label_for(*t, addr_to_name)),
+        Instr::JumpIfFalse(t) => format!("JUMP_IF_FALSE {}", label_for(*t, addr_to_name)),
+        Instr::Call(s) => format!("CALL {s}"),
+        Instr::TailCall(s) => format!("TAIL_CALL {s}"),
+        Instr::CallBuiltin(name, argc) => format!("CALL_BUILTIN {name} {argc}"),
+        Instr::Attr(s) => format!("ATTR {s}"),
+        Instr::StoreAttr(s) => format!("STORE_ATTR {s}"),
+        Instr::CallValue(n) => format!("CALL_VALUE {n}"),
+        Instr::SetupExcept(t, kinds) => {
+            let kind_names: Vec<&str> = kinds.iter().map(|k| k.name()).collect();
+            format!("SETUP_EXCEPT {} [{}]", label_for(*t, addr_to_name), kind_names.join(", "))
+        }
+        Instr::Raise(kind) => format!("RAISE {}", kind.name()),
+        Instr::Import(path) => format!("IMPORT {path:?}"),
+        _ => instr.name().to_string(),
+    }
 }
 
 //
@@ -180,181 +849,521 @@ fn read_string(data: &[u8], idx: &mut usize) -> String {
 
 /// Parse binary bytecode into a linear instruction stream and a function table.
 ///
-/// This performs a single forward pass, verifying the magic header and exact
-/// version (`BC_VERSION`). The returned tuple is:
-///
-/// - `code`: `Vec<Instr>` that the VM executes with `pc` as an index
-/// - `funcs`: mapping from function name â†’ [`Function`] metadata
+/// A thin panicking wrapper over [`try_parse_bytecode`] for call sites that
+/// only ever feed this build's own trusted compiler output, where a malformed
+/// payload would indicate a bug in this codebase rather than untrusted input.
+/// Anything loading bytecode from disk, the network, or a cache should call
+/// [`try_parse_bytecode`] directly instead.
 ///
 /// ## Panics
 /// - If `data` is malformed: bad magic/version, truncated payloads, invalid
 ///   UTF-8, or unknown `ErrorKind` discriminants used by `Raise`
+pub fn parse_bytecode(data: &[u8]) -> (Vec<Instr>, HashMap<String, Function>) {
+    match try_parse_bytecode(data) {
+        Ok(result) => result,
+        Err(e) => panic!("corrupt bytecode: {e}"),
+    }
+}
+
+/// Parse binary bytecode into a linear instruction stream and a function
+/// table, or a [`BytecodeError`] describing why `data` couldn't be decoded.
+///
+/// This performs a single forward pass, verifying the magic header and
+/// MAJOR-matching version (`BC_VERSION`), with every read bounds-checked so
+/// truncated or corrupted input is reported rather than causing a panic or
+/// out-of-bounds access. The returned tuple (on success) is:
+///
+/// - `code`: `Vec<Instr>` that the VM executes with `pc` as an index
+/// - `funcs`: mapping from function name â†’ [`Function`] metadata
 ///
 /// ## Notes
-/// - The opcode-to-variant mapping is defined inline in the `match op` table.
+/// - The opcode-to-variant mapping lives in the declarative opcode tables
+///   above [`decode_op`], which this calls per instruction.
 /// - Op payloads are read *immediately* after the opcode in the specified order.
-/// - For forward compatibility, unknown opcodes currently get ignored (no push),
-///   but the index still advances past the opcode itself. In practice the encoder
-///   should never emit unknown opcodes for a matching version.
-pub fn parse_bytecode(data: &[u8]) -> (Vec<Instr>, HashMap<String, Function>) {
+/// - For forward compatibility, an opcode wrapped in `EXT_OPCODE` that this
+///   build doesn't recognize is skipped via its explicit length (no push);
+///   a bare unrecognized opcode byte is [`BytecodeError::UnknownOpcode`],
+///   since there's no way to know how many bytes to skip past it.
+pub fn try_parse_bytecode(data: &[u8]) -> Result<(Vec<Instr>, HashMap<String, Function>), BytecodeError> {
     let mut idx = 0;
 
     // ---- Header ----
-    assert!(&data[0..4] == b"OMGB");
+    if data.get(0..4) != Some(&b"OMGB"[..]) {
+        return Err(BytecodeError::BadMagic);
+    }
     idx += 4;
 
-    // Version check; reject incompatible bytecode.
-    let version = read_u32(data, &mut idx);
-    assert_eq!(version, BC_VERSION, "unsupported version");
+    // Version check; only the MAJOR component must match (a MINOR/PATCH
+    // difference means only new, EXT_OPCODE-skippable instructions may be
+    // present; see the module's `## Versioning` section).
+    let version = try_read_u32(data, &mut idx)?;
+    if (version >> 16) != (BC_VERSION >> 16) {
+        return Err(BytecodeError::VersionMismatch {
+            found: version,
+            expected: BC_VERSION,
+        });
+    }
 
     // ---- Function table ----
-    let func_count = read_u32(data, &mut idx) as usize;
+    let func_count = try_read_u32(data, &mut idx)? as usize;
     let mut funcs: HashMap<String, Function> = HashMap::new();
 
     for _ in 0..func_count {
         // Function name
-        let name = read_string(data, &mut idx);
+        let name = try_read_string(data, &mut idx)?;
         // Formal parameters
-        let param_count = read_u32(data, &mut idx) as usize;
+        let param_count = try_read_u32(data, &mut idx)? as usize;
         let mut params = Vec::new();
         for _ in 0..param_count {
-            params.push(read_string(data, &mut idx));
+            params.push(try_read_string(data, &mut idx)?);
         }
 
         // Entry-point address into the forthcoming code vector
-        let address = read_u32(data, &mut idx) as usize;
-        funcs.insert(name.clone(), Function { params, address });
+        let address = try_read_u32(data, &mut idx)? as usize;
+        funcs.insert(
+            name.clone(),
+            Function {
+                params,
+                address,
+                ..Default::default()
+            },
+        );
     }
 
     // ---- Code stream ----
-    let code_len = read_u32(data, &mut idx) as usize;
+    let code_len = try_read_u32(data, &mut idx)? as usize;
     let mut code = Vec::with_capacity(code_len);
     for _ in 0..code_len {
         // Single-byte opcode selector
-        let op = data[idx];
+        let op = *data.get(idx).ok_or(BytecodeError::Truncated)?;
         idx += 1;
-        // Decode one instruction based on opcode; consume any operands.
-        match op {
-            // 0..6: constants / variables
-            0 => {
-                let v = read_i64(data, &mut idx);
-                code.push(Instr::PushInt(v));
+        // Decode one instruction based on opcode (see the declarative opcode
+        // tables above `decode_op`); an EXT_OPCODE-wrapped instruction this
+        // build doesn't recognize is a no-op decode, with `idx` still
+        // advanced past its full payload for forward compatibility.
+        if let Some(instr) = decode_op(op, data, &mut idx)? {
+            code.push(instr);
+        }
+    }
+
+    // ---- Static stack/local analysis ----
+    // Computed in a separate pass (not inline above) because a function's
+    // entry `address` may be higher than its own definition's position in
+    // `code`, and `Instr::Call`'s operand count depends on *other* functions'
+    // `params`, all of which must already be in `funcs` before any of this
+    // can run.
+    let shapes: Vec<(String, usize, usize)> = funcs
+        .iter()
+        .map(|(name, func)| {
+            let (max_stack, local_count) = analyze_function_shape(&code, func.address, &funcs);
+            (name.clone(), max_stack, local_count + func.params.len())
+        })
+        .collect();
+    for (name, max_stack, local_count) in shapes {
+        if let Some(func) = funcs.get_mut(&name) {
+            func.max_stack = max_stack;
+            func.local_count = local_count;
+        }
+    }
+
+    Ok((code, funcs))
+}
+
+//
+// --- Static stack/local analysis -------------------------------------------
+//
+
+/// Net operand-stack effect of one instruction, as `(pops, pushes)`.
+///
+/// `Call`/`TailCall` look up the callee's arity in `funcs` (a function's
+/// param count is exactly how many values it pops off the caller's stack);
+/// an unresolved name pops `0` rather than panicking, since a dangling call
+/// target is a (separately reported) program bug, not something this sizing
+/// pass should choke on.
+fn instr_stack_effect(instr: &Instr, funcs: &HashMap<String, Function>) -> (usize, usize) {
+    match instr {
+        Instr::PushInt(_)
+        | Instr::PushFloat(_)
+        | Instr::PushStr(_)
+        | Instr::PushBool(_)
+        | Instr::PushNone
+        | Instr::Load(_)
+        | Instr::Import(_) => (0, 1),
+        Instr::BuildList(n) => (*n, 1),
+        Instr::BuildDict(n) => (2 * *n, 1),
+        Instr::Store(_)
+        | Instr::Assert
+        | Instr::JumpIfFalse(_)
+        | Instr::Pop
+        | Instr::Ret
+        | Instr::Emit
+        | Instr::Raise(_) => (1, 0),
+        Instr::Add
+        | Instr::Sub
+        | Instr::Mul
+        | Instr::Div
+        | Instr::Mod
+        | Instr::Eq
+        | Instr::Ne
+        | Instr::Lt
+        | Instr::Le
+        | Instr::Gt
+        | Instr::Ge
+        | Instr::BAnd
+        | Instr::BOr
+        | Instr::BXor
+        | Instr::Shl
+        | Instr::Shr
+        | Instr::And
+        | Instr::Or
+        | Instr::Index
+        | Instr::Concat
+        | Instr::Repeat => (2, 1),
+        Instr::Not | Instr::Neg | Instr::Attr(_) => (1, 1),
+        Instr::Slice => (4, 1),
+        Instr::StoreIndex => (3, 0),
+        Instr::StoreAttr(_) => (2, 0),
+        Instr::Jump(_) | Instr::Halt | Instr::SetupExcept(_, _) | Instr::PopBlock => (0, 0),
+        Instr::Call(name) => (funcs.get(name).map_or(0, |f| f.params.len()), 1),
+        Instr::TailCall(name) => (funcs.get(name).map_or(0, |f| f.params.len()), 0),
+        Instr::CallBuiltin(_, argc) => (*argc, 1),
+        Instr::CallValue(argc) => (*argc + 1, 1),
+        Instr::Yield => (1, 1),
+    }
+}
+
+/// `true` for instructions that never fall through to `pc + 1` within the
+/// same function — they either leave the function (`Ret`/`TailCall`), stop
+/// the program (`Halt`), or unwind to whatever `except` block's `SetupExcept`
+/// edge (already modeled separately) catches them (`Raise`).
+fn instr_is_terminal(instr: &Instr) -> bool {
+    matches!(instr, Instr::Ret | Instr::TailCall(_) | Instr::Halt | Instr::Raise(_))
+}
+
+/// Static analysis over one function's (or the top-level program's)
+/// reachable instructions, starting at `entry`: a worklist fixed-point walk
+/// computing the peak operand-stack depth (`max_stack`) and the number of
+/// distinct `Store` targets (to which `try_parse_bytecode` adds the
+/// parameters for `local_count`, an upper-bound estimate of how many local
+/// slots the frame will ever hold).
+///
+/// At a branch (`Jump`/`JumpIfFalse`) both successors are queued with the
+/// depth computed *after* the branching instruction's own effect, and a
+/// program point already reached at an equal-or-greater depth is not
+/// revisited — this is the "take the max over both arms" the pass needs at
+/// every merge point, and it terminates because a depth at a given `pc` only
+/// ever increases, bounded by `code.len()` times the largest single-op push.
+///
+/// `SetupExcept(target, _)`'s handler edge: the handler resumes with the
+/// stack truncated to its `Block::stack_size` — the depth in effect when the
+/// handler was set up — plus one pushed value (see `run_inner`'s unwind
+/// logic), so `target` is queued at that depth plus one, the same depth
+/// `verify_region` checks the handler at.
+///
+/// An instruction whose declared pop count exceeds the depth in hand (which
+/// would only happen for actually-malformed bytecode, not anything a real
+/// compiler emits) saturates to a depth of `0` rather than underflowing,
+/// per the same "never panic on untrusted/odd input" stance the rest of this
+/// parser takes.
+fn analyze_function_shape(
+    code: &[Instr],
+    entry: usize,
+    funcs: &HashMap<String, Function>,
+) -> (usize, usize) {
+    let mut depth_at: HashMap<usize, usize> = HashMap::new();
+    let mut worklist: Vec<(usize, usize)> = vec![(entry, 0)];
+    let mut max_depth = 0usize;
+    let mut locals: std::collections::HashSet<&str> = std::collections::HashSet::new();
+
+    while let Some((pc, depth)) = worklist.pop() {
+        if pc >= code.len() {
+            continue;
+        }
+        if let Some(&known) = depth_at.get(&pc) {
+            if depth <= known {
+                continue;
             }
-            1 => {
-                let s = read_string(data, &mut idx);
-                code.push(Instr::PushStr(s));
+        }
+        depth_at.insert(pc, depth);
+        max_depth = max_depth.max(depth);
+
+        let instr = &code[pc];
+        if let Instr::Store(name) = instr {
+            locals.insert(name.as_str());
+        }
+
+        let (pops, pushes) = instr_stack_effect(instr, funcs);
+        let after = depth.saturating_sub(pops) + pushes;
+        max_depth = max_depth.max(after);
+
+        match instr {
+            Instr::Jump(target) => worklist.push((*target, after)),
+            Instr::JumpIfFalse(target) => {
+                worklist.push((pc + 1, after));
+                worklist.push((*target, after));
             }
-            2 => {
-                let b = data[idx] != 0;
-                idx += 1;
-                code.push(Instr::PushBool(b));
+            Instr::SetupExcept(target, _) => {
+                worklist.push((pc + 1, after));
+                worklist.push((*target, depth + 1));
             }
-            3 => {
-                let n = read_u32(data, &mut idx) as usize;
-                code.push(Instr::BuildList(n));
+            _ if instr_is_terminal(instr) => {}
+            _ => worklist.push((pc + 1, after)),
+        }
+    }
+
+    (max_depth, locals.len())
+}
+
+/// Peak operand-stack depth of the top-level program (the code that runs
+/// before any `Call` jumps elsewhere), for `run`/`run_inner` to
+/// `stack.reserve()` up front. See [`analyze_function_shape`], called here
+/// with `entry = 0` — the top-level program always starts at instruction 0.
+pub fn main_max_stack(code: &[Instr], funcs: &HashMap<String, Function>) -> usize {
+    analyze_function_shape(code, 0, funcs).0
+}
+
+//
+// --- Verifier ---------------------------------------------------------------
+//
+
+/// Errors produced by [`verify`] when a decoded instruction stream isn't
+/// internally consistent enough to execute safely.
+///
+/// Every variant names the program counter at fault, mirroring how
+/// [`BytecodeError`] reports exactly where a raw decode went wrong.
+#[derive(Debug, Clone, PartialEq)]
+pub enum VerifyError {
+    /// A `Jump`/`JumpIfFalse`/`SetupExcept` at `pc` targets an index outside
+    /// `[0, code.len())`.
+    TargetOutOfBounds { pc: usize, target: usize },
+    /// A `Function`'s `address` falls outside `[0, code.len())`.
+    FunctionAddressOutOfBounds { name: String, address: usize },
+    /// `Call`/`TailCall` at `pc` names a function missing from `funcs`.
+    UnresolvedCall { pc: usize, name: String },
+    /// The instruction at `pc` would pop more operands than are on the stack
+    /// along this control-flow path.
+    StackUnderflow { pc: usize },
+    /// Two control-flow paths reach `pc` with different operand-stack
+    /// depths, so the bytecode isn't stack-safe.
+    StackDepthMismatch { pc: usize, expected: usize, found: usize },
+    /// A `PopBlock` at `pc` ran with no matching `SetupExcept` active on this
+    /// path (or two paths disagree on how many are active).
+    UnbalancedPopBlock { pc: usize },
+    /// The instruction at `pc` falls through to `pc + 1`, but `pc` is the
+    /// last decoded instruction in `code` — i.e. the stream doesn't end in
+    /// a terminal instruction (`Ret`/`TailCall`/`Halt`/`Raise`).
+    TruncatedCode { pc: usize },
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifyError::TargetOutOfBounds { pc, target } => {
+                write!(f, "pc {pc}: jump target {target} is out of bounds")
             }
-            4 => {
-                let n = read_u32(data, &mut idx) as usize;
-                code.push(Instr::BuildDict(n));
+            VerifyError::FunctionAddressOutOfBounds { name, address } => {
+                write!(f, "function `{name}`: address {address} is out of bounds")
             }
-            5 => {
-                let s = read_string(data, &mut idx);
-                code.push(Instr::Load(s));
+            VerifyError::UnresolvedCall { pc, name } => {
+                write!(f, "pc {pc}: call to undefined function `{name}`")
             }
-            6 => {
-                let s = read_string(data, &mut idx);
-                code.push(Instr::Store(s));
+            VerifyError::StackUnderflow { pc } => {
+                write!(f, "pc {pc}: instruction pops more values than are on the stack")
             }
-            // 7..26: arithmetic / comparison / bitwise / boolean / unary
-            7 => code.push(Instr::Add),
-            8 => code.push(Instr::Sub),
-            9 => code.push(Instr::Mul),
-            10 => code.push(Instr::Div),
-            11 => code.push(Instr::Mod),
-            12 => code.push(Instr::Eq),
-            13 => code.push(Instr::Ne),
-            14 => code.push(Instr::Lt),
-            15 => code.push(Instr::Le),
-            16 => code.push(Instr::Gt),
-            17 => code.push(Instr::Ge),
-            18 => code.push(Instr::BAnd),
-            19 => code.push(Instr::BOr),
-            20 => code.push(Instr::BXor),
-            21 => code.push(Instr::Shl),
-            22 => code.push(Instr::Shr),
-            23 => code.push(Instr::And),
-            24 => code.push(Instr::Or),
-            25 => code.push(Instr::Not),
-            26 => code.push(Instr::Neg),
-            // 27..28: indexing / slicing
-            27 => code.push(Instr::Index),
-            28 => code.push(Instr::Slice),
-            // 29..30: branches
-            29 => {
-                let t = read_u32(data, &mut idx) as usize;
-                code.push(Instr::Jump(t));
+            VerifyError::StackDepthMismatch { pc, expected, found } => {
+                write!(
+                    f,
+                    "pc {pc}: inconsistent stack depth (expected {expected}, found {found} on another path)"
+                )
             }
-            30 => {
-                let t = read_u32(data, &mut idx) as usize;
-                code.push(Instr::JumpIfFalse(t));
+            VerifyError::UnbalancedPopBlock { pc } => {
+                write!(f, "pc {pc}: PopBlock with no matching SetupExcept active")
             }
-            // 31..33: calls (named, tail, builtin)
-            31 => {
-                let s = read_string(data, &mut idx);
-                code.push(Instr::Call(s));
+            VerifyError::TruncatedCode { pc } => {
+                write!(f, "pc {pc}: falls through to the end of the code stream without a terminal instruction")
             }
-            32 => {
-                let s = read_string(data, &mut idx);
-                code.push(Instr::TailCall(s));
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+/// Verify that a decoded instruction stream is internally consistent before
+/// handing it to the VM: every branch target and `Function::address` is in
+/// bounds, every named `Call`/`TailCall` resolves in `funcs`, the operand
+/// stack never underflows, every control-flow predecessor of a given `pc`
+/// agrees on the stack depth entering it, and `SetupExcept`/`PopBlock`
+/// nesting is balanced along every path.
+///
+/// This is an abstract interpretation of stack depth, not a full type
+/// checker: it tracks *how many* values are on the stack at each `pc`, not
+/// their types, the same net-effect model [`analyze_function_shape`] uses
+/// for its capacity estimate — but here disagreement between paths is a
+/// hard error instead of being resolved by taking the max.
+pub fn verify(code: &[Instr], funcs: &HashMap<String, Function>) -> Result<(), VerifyError> {
+    for (name, func) in funcs {
+        if func.address >= code.len() {
+            return Err(VerifyError::FunctionAddressOutOfBounds {
+                name: name.clone(),
+                address: func.address,
+            });
+        }
+    }
+
+    if code.is_empty() {
+        return Ok(());
+    }
+    verify_region(code, 0, funcs)?;
+    for func in funcs.values() {
+        verify_region(code, func.address, funcs)?;
+    }
+    Ok(())
+}
+
+/// Check that `target` (a branch issued from `pc`) lies within `code`.
+fn check_target(code: &[Instr], pc: usize, target: usize) -> Result<(), VerifyError> {
+    if target >= code.len() {
+        Err(VerifyError::TargetOutOfBounds { pc, target })
+    } else {
+        Ok(())
+    }
+}
+
+/// Check that falling through from `pc` to `pc + 1` still lands inside
+/// `code` — i.e. `pc` isn't the last decoded instruction in a stream that
+/// doesn't end in a terminal op. Unlike `check_target`, there's no declared
+/// operand to report as the offending target, so this gets its own
+/// `VerifyError` variant.
+fn check_fallthrough(code: &[Instr], pc: usize) -> Result<(), VerifyError> {
+    if pc + 1 >= code.len() {
+        Err(VerifyError::TruncatedCode { pc })
+    } else {
+        Ok(())
+    }
+}
+
+/// Walk every instruction reachable from `entry` (a function's `address`, or
+/// `0` for the top-level program), requiring all predecessors of a given
+/// `pc` to agree on both the operand-stack depth and the number of active
+/// exception handlers entering it. See [`verify`].
+fn verify_region(
+    code: &[Instr],
+    entry: usize,
+    funcs: &HashMap<String, Function>,
+) -> Result<(), VerifyError> {
+    // `Function::address` bounds are checked by `verify` up front; `entry`
+    // is always either `0` (valid unless `code` is empty, which no real
+    // compiler output is) or one of those already-checked addresses.
+    let mut state_at: HashMap<usize, (usize, usize)> = HashMap::new();
+    let mut worklist: Vec<(usize, usize, usize)> = vec![(entry, 0, 0)];
+
+    while let Some((pc, depth, handlers)) = worklist.pop() {
+        if let Some(&(known_depth, known_handlers)) = state_at.get(&pc) {
+            if known_depth != depth {
+                return Err(VerifyError::StackDepthMismatch {
+                    pc,
+                    expected: known_depth,
+                    found: depth,
+                });
             }
-            33 => {
-                let name = read_string(data, &mut idx);
-                let argc = read_u32(data, &mut idx) as usize;
-                code.push(Instr::CallBuiltin(name, argc));
+            if known_handlers != handlers {
+                return Err(VerifyError::UnbalancedPopBlock { pc });
             }
-            // 34..38: misc control
-            34 => code.push(Instr::Pop),
-            35 => code.push(Instr::PushNone),
-            36 => code.push(Instr::Ret),
-            37 => code.push(Instr::Emit),
-            38 => code.push(Instr::Halt),
-            // 39..42: stores/attrs/assert
-            39 => code.push(Instr::StoreIndex),
-            40 => {
-                let s = read_string(data, &mut idx);
-                code.push(Instr::Attr(s));
+            continue;
+        }
+        state_at.insert(pc, (depth, handlers));
+
+        let instr = &code[pc];
+        if let Instr::Call(name) | Instr::TailCall(name) = instr {
+            if !funcs.contains_key(name) {
+                return Err(VerifyError::UnresolvedCall {
+                    pc,
+                    name: name.clone(),
+                });
             }
-            41 => {
-                let s = read_string(data, &mut idx);
-                code.push(Instr::StoreAttr(s));
+        }
+
+        let (pops, pushes) = instr_stack_effect(instr, funcs);
+        if depth < pops {
+            return Err(VerifyError::StackUnderflow { pc });
+        }
+        let after = depth - pops + pushes;
+
+        match instr {
+            Instr::Jump(target) => {
+                check_target(code, pc, *target)?;
+                worklist.push((*target, after, handlers));
             }
-            42 => code.push(Instr::Assert),
-            // 43: first-class callable invoke (argc inline)
-            43 => {
-                let n = read_u32(data, &mut idx) as usize;
-                code.push(Instr::CallValue(n));
+            Instr::JumpIfFalse(target) => {
+                check_target(code, pc, *target)?;
+                check_fallthrough(code, pc)?;
+                worklist.push((pc + 1, after, handlers));
+                worklist.push((*target, after, handlers));
             }
-            // 44..46: exception scaffolding and dynamic raise
-            44 => {
-                let t = read_u32(data, &mut idx) as usize;
-                code.push(Instr::SetupExcept(t));
+            Instr::SetupExcept(target, _) => {
+                check_target(code, pc, *target)?;
+                check_fallthrough(code, pc)?;
+                // The handler resumes with the stack truncated to the depth
+                // captured here (`after`, since `SetupExcept` itself has no
+                // stack effect) plus the one error value the unwind pushes
+                // (see `RuntimeError` unwinding in `vm.rs`); its own block is
+                // already gone from the active set by the time it runs.
+                worklist.push((pc + 1, after, handlers + 1));
+                worklist.push((*target, after + 1, handlers));
             }
-            45 => code.push(Instr::PopBlock),
-            46 => {
-                let kind_b = data[idx];
-                idx += 1;
-                let kind = ErrorKind::try_from(kind_b).unwrap();
-                code.push(Instr::Raise(kind));
+            Instr::PopBlock => {
+                if handlers == 0 {
+                    return Err(VerifyError::UnbalancedPopBlock { pc });
+                }
+                check_fallthrough(code, pc)?;
+                worklist.push((pc + 1, after, handlers - 1));
+            }
+            _ if instr_is_terminal(instr) => {}
+            _ => {
+                check_fallthrough(code, pc)?;
+                worklist.push((pc + 1, after, handlers));
             }
-            // 47..51: short opcodes for specific error kinds
-            47 => code.push(Instr::Raise(ErrorKind::Syntax)),
-            48 => code.push(Instr::Raise(ErrorKind::Type)),
-            49 => code.push(Instr::Raise(ErrorKind::UndefinedIdent)),
-            50 => code.push(Instr::Raise(ErrorKind::Value)),
-            51 => code.push(Instr::Raise(ErrorKind::ModuleImport)),
-            // Unknown opcode: no-op decode (advance already consumed 1 byte).
-            _ => {}
         }
     }
-    (code, funcs)
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A code stream whose last instruction isn't `Ret`/`TailCall`/`Halt`/
+    /// `Raise` used to schedule a fallthrough to `pc == code.len()`, which
+    /// the next worklist pop indexed into directly and panicked on — this
+    /// is reachable from `try_parse_bytecode`, which has no requirement
+    /// that a decoded stream end in a terminal instruction.
+    #[test]
+    fn verify_rejects_code_that_does_not_end_in_a_terminal_instruction() {
+        let code = vec![Instr::PushInt(1)];
+        let funcs = HashMap::new();
+        assert_eq!(
+            verify(&code, &funcs),
+            Err(VerifyError::TruncatedCode { pc: 0 })
+        );
+    }
+
+    #[test]
+    fn verify_accepts_code_ending_in_halt() {
+        let code = vec![Instr::PushInt(1), Instr::Halt];
+        let funcs = HashMap::new();
+        assert!(verify(&code, &funcs).is_ok());
+    }
+
+    #[test]
+    fn verify_rejects_jump_if_false_with_no_room_to_fall_through() {
+        // `JumpIfFalse`'s branch target is in bounds, but its fallthrough
+        // edge (`pc + 1`) would run off the end of `code`.
+        let code = vec![Instr::PushBool(true), Instr::JumpIfFalse(0)];
+        let funcs = HashMap::new();
+        assert_eq!(
+            verify(&code, &funcs),
+            Err(VerifyError::TruncatedCode { pc: 1 })
+        );
+    }
 }
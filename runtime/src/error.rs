@@ -24,6 +24,8 @@
 
 use std::fmt;
 
+use crate::value::Value;
+
 /// Compact enum of error categories used in bytecode and raise instructions.
 ///
 /// Each variant has a fixed numeric representation (`repr(u8)`), ensuring
@@ -43,6 +45,26 @@ pub enum ErrorKind {
     Value = 4,
     /// Failure to import a module.
     ModuleImport = 5,
+    /// Call depth exceeded the configured recursion limit.
+    Recursion = 6,
+    /// Execution was cancelled via the cooperative interrupt flag.
+    Interrupted = 7,
+    /// Indexing operation failed (list/str index out of bounds).
+    Index = 8,
+    /// Dictionary key was not found.
+    Key = 9,
+    /// Division or modulo by zero.
+    ZeroDivision = 10,
+    /// An `assert` instruction failed.
+    Assertion = 11,
+    /// Attempted to write to a frozen dictionary.
+    FrozenWrite = 12,
+    /// Execution ran out of fuel under an instruction-count budget.
+    FuelExhausted = 13,
+    /// Execution exceeded a wall-clock timeout budget.
+    Timeout = 14,
+    /// Integer arithmetic overflowed `i64`'s range.
+    IntegerOverflow = 15,
 }
 
 impl ErrorKind {
@@ -56,6 +78,42 @@ impl ErrorKind {
             ErrorKind::UndefinedIdent => RuntimeError::UndefinedIdentError(msg),
             ErrorKind::Value => RuntimeError::ValueError(msg),
             ErrorKind::ModuleImport => RuntimeError::ModuleImportError(msg),
+            ErrorKind::Recursion => RuntimeError::RecursionError(msg),
+            ErrorKind::Interrupted => RuntimeError::Interrupted,
+            ErrorKind::Index => RuntimeError::IndexError(msg),
+            ErrorKind::Key => RuntimeError::KeyError(msg),
+            ErrorKind::ZeroDivision => RuntimeError::ZeroDivisionError,
+            ErrorKind::Assertion => RuntimeError::AssertionError,
+            ErrorKind::FrozenWrite => RuntimeError::FrozenWriteError,
+            ErrorKind::FuelExhausted => RuntimeError::FuelExhausted,
+            ErrorKind::Timeout => RuntimeError::Timeout,
+            ErrorKind::IntegerOverflow => RuntimeError::IntegerOverflow(msg),
+        }
+    }
+}
+
+impl ErrorKind {
+    /// Short, stable name for this category, used as the `"kind"` field of
+    /// the error dict a `SetupExcept` handler receives (see `Raise` handling
+    /// in `vm.rs`) — mirrors `Instr::name()`'s role for opcodes.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ErrorKind::Generic => "Generic",
+            ErrorKind::Syntax => "SyntaxError",
+            ErrorKind::Type => "TypeError",
+            ErrorKind::UndefinedIdent => "UndefinedIdentError",
+            ErrorKind::Value => "ValueError",
+            ErrorKind::ModuleImport => "ModuleImportError",
+            ErrorKind::Recursion => "RecursionError",
+            ErrorKind::Interrupted => "Interrupted",
+            ErrorKind::Index => "IndexError",
+            ErrorKind::Key => "KeyError",
+            ErrorKind::ZeroDivision => "ZeroDivisionError",
+            ErrorKind::Assertion => "AssertionError",
+            ErrorKind::FrozenWrite => "FrozenWriteError",
+            ErrorKind::FuelExhausted => "FuelExhausted",
+            ErrorKind::Timeout => "Timeout",
+            ErrorKind::IntegerOverflow => "IntegerOverflow",
         }
     }
 }
@@ -72,6 +130,16 @@ impl TryFrom<u8> for ErrorKind {
             3 => UndefinedIdent,
             4 => Value,
             5 => ModuleImport,
+            6 => Recursion,
+            7 => Interrupted,
+            8 => Index,
+            9 => Key,
+            10 => ZeroDivision,
+            11 => Assertion,
+            12 => FrozenWrite,
+            13 => FuelExhausted,
+            14 => Timeout,
+            15 => IntegerOverflow,
             _ => return Err(()),
         })
     }
@@ -81,7 +149,7 @@ impl TryFrom<u8> for ErrorKind {
 ///
 /// Unlike [`ErrorKind`], this enum provides *structured* error information and
 /// detailed messages for debugging and user reporting.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone)]
 pub enum RuntimeError {
     /// An `assert` instruction failed.
     AssertionError,
@@ -93,6 +161,10 @@ pub enum RuntimeError {
     KeyError(String),
     /// Module import failed.
     ModuleImportError(String),
+    /// Call depth exceeded the configured recursion limit.
+    RecursionError(String),
+    /// Execution was cancelled via the cooperative interrupt flag (e.g. Ctrl-C).
+    Interrupted,
     /// Invalid or unexpected syntax was encountered.
     SyntaxError(String),
     /// Operation was applied to an inappropriate type.
@@ -105,8 +177,92 @@ pub enum RuntimeError {
     ZeroDivisionError,
     /// User-raised error (`raise` or `panic`).
     Raised(String),
+    /// User-raised *structured* exception: the `ErrorKind` named by the
+    /// `raise` instruction's own operand, paired with the original `Value`
+    /// it evaluated to (e.g. a `Dict` describing the error), carried
+    /// through unwinding so `except` blocks can pattern-match on it instead
+    /// of only seeing a stringified message. The kind travels alongside the
+    /// value rather than being inferred from it — see `kind()` below — so a
+    /// typed handler (`SetupExcept` with a non-empty kind list) can still
+    /// filter a structured raise by its declared kind instead of every
+    /// structured raise collapsing to `Generic`.
+    ///
+    /// `Value` has no `PartialEq`, so `RuntimeError`'s `PartialEq` below is
+    /// written by hand and compares this variant by kind and stringified form.
+    RaisedValue(ErrorKind, Value),
     /// Internal VM invariant violation (represents a bug or logic failure).
     VmInvariant(String),
+    /// Execution ran out of fuel under an instruction-count [`crate::vm::Budget`].
+    FuelExhausted,
+    /// Execution exceeded a wall-clock timeout [`crate::vm::Budget`].
+    Timeout,
+    /// Integer arithmetic (`add`/`sub`/`mul`/`neg`/`shl`) overflowed `i64`'s
+    /// range. The message embeds the operands so a handler or error log can
+    /// show what was actually computed, matching how `RecursionError`/
+    /// `KeyError` embed their own context.
+    IntegerOverflow(String),
+}
+
+impl PartialEq for RuntimeError {
+    fn eq(&self, other: &Self) -> bool {
+        use RuntimeError::*;
+        match (self, other) {
+            (AssertionError, AssertionError) => true,
+            (FrozenWriteError, FrozenWriteError) => true,
+            (IndexError(a), IndexError(b)) => a == b,
+            (KeyError(a), KeyError(b)) => a == b,
+            (ModuleImportError(a), ModuleImportError(b)) => a == b,
+            (RecursionError(a), RecursionError(b)) => a == b,
+            (Interrupted, Interrupted) => true,
+            (SyntaxError(a), SyntaxError(b)) => a == b,
+            (TypeError(a), TypeError(b)) => a == b,
+            (UndefinedIdentError(a), UndefinedIdentError(b)) => a == b,
+            (ValueError(a), ValueError(b)) => a == b,
+            (ZeroDivisionError, ZeroDivisionError) => true,
+            (Raised(a), Raised(b)) => a == b,
+            // No structural equality on `Value`; compare by rendered form.
+            (RaisedValue(ak, a), RaisedValue(bk, b)) => ak == bk && a.to_string() == b.to_string(),
+            (VmInvariant(a), VmInvariant(b)) => a == b,
+            (FuelExhausted, FuelExhausted) => true,
+            (Timeout, Timeout) => true,
+            (IntegerOverflow(a), IntegerOverflow(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl RuntimeError {
+    /// Classify this error into the compact [`ErrorKind`] category used by
+    /// typed exception handlers (`except Type, Value { ... }`-style filters).
+    ///
+    /// This is the inverse of [`ErrorKind::into_runtime`]; every variant maps
+    /// to exactly one category so `SetupExcept` filters can match on it
+    /// regardless of whether the error originated from a bytecode-level
+    /// `Raise` or from a VM-internal fault (e.g. `IndexError`).
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            RuntimeError::AssertionError => ErrorKind::Assertion,
+            RuntimeError::FrozenWriteError => ErrorKind::FrozenWrite,
+            RuntimeError::IndexError(_) => ErrorKind::Index,
+            RuntimeError::KeyError(_) => ErrorKind::Key,
+            RuntimeError::ModuleImportError(_) => ErrorKind::ModuleImport,
+            RuntimeError::RecursionError(_) => ErrorKind::Recursion,
+            RuntimeError::Interrupted => ErrorKind::Interrupted,
+            RuntimeError::SyntaxError(_) => ErrorKind::Syntax,
+            RuntimeError::TypeError(_) => ErrorKind::Type,
+            RuntimeError::UndefinedIdentError(_) => ErrorKind::UndefinedIdent,
+            RuntimeError::ValueError(_) => ErrorKind::Value,
+            RuntimeError::ZeroDivisionError => ErrorKind::ZeroDivision,
+            RuntimeError::Raised(_) => ErrorKind::Generic,
+            RuntimeError::RaisedValue(kind, _) => *kind,
+            // VM invariants are bugs, not user-catchable conditions; classify
+            // them as generic so a catch-all handler still sees them.
+            RuntimeError::VmInvariant(_) => ErrorKind::Generic,
+            RuntimeError::FuelExhausted => ErrorKind::FuelExhausted,
+            RuntimeError::Timeout => ErrorKind::Timeout,
+            RuntimeError::IntegerOverflow(_) => ErrorKind::IntegerOverflow,
+        }
+    }
 }
 
 impl fmt::Display for RuntimeError {
@@ -127,6 +283,12 @@ impl fmt::Display for RuntimeError {
             RuntimeError::ModuleImportError(msg) => {
                 write!(f, "ModuleImportError: {}", msg)
             }
+            RuntimeError::RecursionError(msg) => {
+                write!(f, "RecursionError: {}", msg)
+            }
+            RuntimeError::Interrupted => {
+                write!(f, "Interrupted: execution cancelled")
+            }
             RuntimeError::SyntaxError(msg) => {
                 write!(f, "SyntaxError: {}", msg)
             }
@@ -145,9 +307,21 @@ impl fmt::Display for RuntimeError {
             RuntimeError::Raised(msg) => {
                 write!(f, "RuntimeError: {}", msg)
             }
+            RuntimeError::RaisedValue(_, val) => {
+                write!(f, "RuntimeError: {}", val.to_string())
+            }
             RuntimeError::VmInvariant(msg) => {
                 write!(f, "VmInvariant: {}", msg)
             }
+            RuntimeError::FuelExhausted => {
+                write!(f, "FuelExhausted: execution budget ran out")
+            }
+            RuntimeError::Timeout => {
+                write!(f, "Timeout: execution exceeded its time budget")
+            }
+            RuntimeError::IntegerOverflow(msg) => {
+                write!(f, "IntegerOverflow: {}", msg)
+            }
         }
     }
 }
@@ -155,3 +329,46 @@ impl fmt::Display for RuntimeError {
 /// Integrates `RuntimeError` with the standard `Error` trait so it can be
 /// used in `Result<T, RuntimeError>` and interoperate with libraries like `anyhow`.
 impl std::error::Error for RuntimeError {}
+
+/// One entry in a captured call-stack traceback.
+///
+/// `function` is the callee's name and `call_site` is the program counter of
+/// the `Call`/`CallValue` instruction that entered it, recorded in
+/// caller-to-callee order (outermost frame first).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub function: String,
+    pub call_site: usize,
+}
+
+/// A [`RuntimeError`] paired with the call stack active when it was raised.
+///
+/// Mirrors Python/Java-style tracebacks: the frame list is snapshotted at
+/// raise time, before unwinding pops any of it, so it reflects exactly the
+/// calls that were active when the fault occurred. When no frames were
+/// recorded (a top-level error with an empty call stack), `Display` is a
+/// single line: the `RuntimeError` message followed by the faulting pc and
+/// instruction.
+///
+/// `fault_pc`/`fault_instr` pinpoint the instruction that actually raised the
+/// error, as opposed to `frames`, which records the *call sites* that led up
+/// to it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TracedError {
+    pub error: RuntimeError,
+    pub frames: Vec<Frame>,
+    pub fault_pc: usize,
+    pub fault_instr: String,
+}
+
+impl fmt::Display for TracedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (at pc {} in {})", self.error, self.fault_pc, self.fault_instr)?;
+        for frame in &self.frames {
+            write!(f, "\n  called from `{}` at pc {}", frame.function, frame.call_site)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for TracedError {}
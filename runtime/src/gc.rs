@@ -0,0 +1,134 @@
+//! # Tracing Garbage Collector for Cyclic List/Dict Values
+//!
+//! `Value::List`/`Value::Dict` are `Rc<RefCell<...>>`, so plain reference
+//! counting can't reclaim a list that contains itself (`x[0] = x`) or two
+//! dicts that reference each other — the cycle keeps their strong count
+//! above zero forever. This module adds a small mark-and-sweep pass that
+//! runs at a VM safe point (between instructions, via the allocation-count
+//! threshold in `vm.rs`, or on demand through the `gc.collect()` builtin):
+//!
+//! - Every list/dict allocation is registered here as a `Weak` handle (see
+//!   [`crate::value::Value::new_list`]/[`crate::value::Value::new_dict`]), so
+//!   the heap knows about every collection ever created without itself
+//!   holding a strong reference (which would defeat normal `Rc` reclamation
+//!   for the common acyclic case).
+//! - **Mark**: walk every root (the operand stack, globals, and all live
+//!   local environments) and record the pointer identity (`Rc::as_ptr`) of
+//!   every reachable list/dict — the same technique `Value::to_string`'s
+//!   cycle detector already uses for printing.
+//! - **Sweep**: for every still-alive but unmarked handle, clear its
+//!   contents. That drops the `Value`s it held — including whatever strong
+//!   references made up the cycle — so the allocation's count finally
+//!   reaches zero and it frees normally.
+//!
+//! No write barrier is needed: collection only ever runs between
+//! instructions, when the only live references into the heap are the roots
+//! passed to [`collect`].
+
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::{Rc, Weak};
+
+use crate::value::{DictKey, Value};
+
+thread_local! {
+    static LISTS: RefCell<Vec<Weak<RefCell<Vec<Value>>>>> = RefCell::new(Vec::new());
+    static DICTS: RefCell<Vec<Weak<RefCell<HashMap<DictKey, Value>>>>> = RefCell::new(Vec::new());
+    static ALLOCS: RefCell<u64> = RefCell::new(0);
+}
+
+/// Register a freshly allocated list so the collector can find it later.
+pub(crate) fn register_list(rc: &Rc<RefCell<Vec<Value>>>) {
+    LISTS.with(|l| l.borrow_mut().push(Rc::downgrade(rc)));
+    ALLOCS.with(|c| *c.borrow_mut() += 1);
+}
+
+/// Register a freshly allocated dict so the collector can find it later.
+pub(crate) fn register_dict(rc: &Rc<RefCell<HashMap<DictKey, Value>>>) {
+    DICTS.with(|d| d.borrow_mut().push(Rc::downgrade(rc)));
+    ALLOCS.with(|c| *c.borrow_mut() += 1);
+}
+
+/// Total number of lists/dicts ever allocated through `Value::new_list`/`new_dict`.
+pub fn alloc_count() -> u64 {
+    ALLOCS.with(|c| *c.borrow())
+}
+
+/// Number of list/dict handles still alive (not yet dropped).
+pub fn live_count() -> usize {
+    let lists = LISTS.with(|l| l.borrow().iter().filter(|w| w.strong_count() > 0).count());
+    let dicts = DICTS.with(|d| d.borrow().iter().filter(|w| w.strong_count() > 0).count());
+    lists + dicts
+}
+
+/// Mark `value` and everything reachable from it, recording pointer identity.
+fn mark(value: &Value, seen_lists: &mut HashSet<usize>, seen_dicts: &mut HashSet<usize>) {
+    match value {
+        Value::List(l) => {
+            let ptr = Rc::as_ptr(l) as usize;
+            if seen_lists.insert(ptr) {
+                for item in l.borrow().iter() {
+                    mark(item, seen_lists, seen_dicts);
+                }
+            }
+        }
+        Value::Dict(d) => {
+            let ptr = Rc::as_ptr(d) as usize;
+            if seen_dicts.insert(ptr) {
+                for item in d.borrow().values() {
+                    mark(item, seen_lists, seen_dicts);
+                }
+            }
+        }
+        Value::FrozenDict(d) => {
+            for item in d.values() {
+                mark(item, seen_lists, seen_dicts);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Run a mark-and-sweep collection over `roots`, clearing the contents of
+/// any tracked list/dict unreachable from them. Clearing (rather than
+/// dropping the handle directly, which we don't own) drops whatever
+/// `Value`s an unreachable cycle held, breaking the cycle so its `Rc`
+/// strong count can finally reach zero.
+///
+/// Returns the number of objects swept. Safe to call only at a point where
+/// no reference into the heap is live anywhere except in `roots` (the VM
+/// guarantees this by only collecting between instructions).
+pub fn collect(roots: &[&Value]) -> usize {
+    let mut seen_lists = HashSet::new();
+    let mut seen_dicts = HashSet::new();
+    for root in roots {
+        mark(root, &mut seen_lists, &mut seen_dicts);
+    }
+
+    let mut swept = 0;
+    LISTS.with(|lists| {
+        lists.borrow_mut().retain(|w| match w.upgrade() {
+            Some(rc) => {
+                if !seen_lists.contains(&(Rc::as_ptr(&rc) as usize)) {
+                    rc.borrow_mut().clear();
+                    swept += 1;
+                }
+                true
+            }
+            None => false,
+        });
+    });
+    DICTS.with(|dicts| {
+        dicts.borrow_mut().retain(|w| match w.upgrade() {
+            Some(rc) => {
+                if !seen_dicts.contains(&(Rc::as_ptr(&rc) as usize)) {
+                    rc.borrow_mut().clear();
+                    swept += 1;
+                }
+                true
+            }
+            None => false,
+        });
+    });
+    swept
+}
@@ -1,32 +1,64 @@
+//! # OMG runtime library surface (WASM-facing)
+//!
+//! This crate mirrors `main.rs`'s embedded-interpreter pipeline (decode
+//! `INTERP_OMGBC`, splice/run source through it) but exposes it as a plain
+//! library instead of a CLI, for two consumers:
+//! - [`wasm_bindgen`]-exported free functions (`run_file`, `run_source`,
+//!   `run_file_interactive`, `run_source_interactive`, `version`) —
+//!   one-shot, stateless execution. The `_interactive` variants bridge a
+//!   JS-supplied input callback into `read_line()`, since the plain
+//!   variants' `read_line()` always sees end-of-input (wasm has no real
+//!   stdin).
+//! - [`EvalSession`] — a persistent, incremental evaluator. This is the
+//!   plain-Rust type the `runtime-wasm` crate's `WasmSession` wraps to give
+//!   JS a session with memory across `eval` calls, the same way the
+//!   in-process source REPL (`repl::repl_interpret`) gives a terminal user
+//!   one.
+
 mod bytecode;
 mod error;
+mod gc;
 mod repl;
 mod value;
 mod vm;
 
+use std::collections::HashMap;
+use std::time::Duration;
+
 use bytecode::{parse_bytecode, Function, Instr};
+use error::TracedError;
+use vm::{run_traced, run_with_input, Budget};
 use wasm_bindgen::prelude::*;
-use vm::run;
 
 /// Embedded `interpreter.omgb` generated at build time.
 const INTERP_OMGBC: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/interpreter.omgb"));
 
-/// Run bytecode with the given arguments and collect any text emitted by the program.
+/// Run bytecode with the given arguments and collect any text emitted by the
+/// program. `input` backs `read_line()`: [`run_file`]/[`run_source`] pass a
+/// provider that always yields `None` (wasm has no real stdin to fall back
+/// to), while [`run_file_interactive`]/[`run_source_interactive`] pass one
+/// bridging a JS callback.
 fn exec(
     code: Vec<Instr>,
-    funcs: std::collections::HashMap<String, Function>,
+    funcs: HashMap<String, Function>,
     args: &[String],
+    input: &mut dyn FnMut() -> Option<String>,
 ) -> Result<String, JsValue> {
-    let mut output = String::new();
-    {
-        let mut emit = |s: String| {
-            output.push_str(&s);
-            output.push('\n');
-        };
-        run(&code, &funcs, args, &mut emit)
-            .map_err(|e| JsValue::from_str(&format!("{}", e)))?;
-    }
-    Ok(output)
+    let mut buf: Vec<u8> = Vec::new();
+    run_with_input(&code, &funcs, args, &mut buf, Some(input))
+        .map_err(|e| JsValue::from_str(&format!("{}", e)))?;
+    Ok(String::from_utf8_lossy(&buf).into_owned())
+}
+
+/// Call a JS-supplied input provider for one `read_line()`. A thrown
+/// exception or a non-string return both degrade to `None` (end of input) —
+/// the `FnMut() -> Option<String>` shape has no channel to propagate a JS
+/// exception back into the VM as a distinct error.
+fn call_input_fn(input_fn: &js_sys::Function) -> Option<String> {
+    input_fn
+        .call0(&JsValue::NULL)
+        .ok()
+        .and_then(|v| v.as_string())
 }
 
 /// Execute an OMG source file by path using the embedded interpreter.
@@ -34,15 +66,30 @@ fn exec(
 pub fn run_file(prog_path: &str) -> Result<String, JsValue> {
     let args = vec![prog_path.to_string()];
     let (code, funcs) = parse_bytecode(INTERP_OMGBC);
-    exec(code, funcs, &args)
+    exec(code, funcs, &args, &mut || None)
 }
 
-/// Execute an OMG source string using the embedded interpreter.
-///
-/// This splices the interpreter's initialization code in front of a small
-/// program that pushes the provided source and calls its `run` procedure.
+/// Same as [`run_file`], but `read_line()` calls pull from `input_fn` (a
+/// zero-argument JS function returning a string, or anything else to signal
+/// end of input) instead of always seeing end-of-input immediately.
 #[wasm_bindgen]
-pub fn run_source(source: &str) -> Result<String, JsValue> {
+pub fn run_file_interactive(prog_path: &str, input_fn: &js_sys::Function) -> Result<String, JsValue> {
+    let args = vec![prog_path.to_string()];
+    let (code, funcs) = parse_bytecode(INTERP_OMGBC);
+    exec(code, funcs, &args, &mut || call_input_fn(input_fn))
+}
+
+/// Splice `source` in front of a call to the embedded interpreter's
+/// `run(source)` procedure, dropping its trailing `Halt` so the call can be
+/// appended.
+///
+/// Shared by [`run_source`] (one-shot) and [`EvalSession::eval`]
+/// (incremental, history-replayed): the interpreter only exposes a
+/// whole-program `run(source)`, not an incremental-eval entry point (see
+/// `repl` module docs for the same constraint on the terminal REPL), so
+/// every call re-feeds the full source text rather than evaluating just the
+/// new part.
+fn splice_run_source(source: &str) -> (Vec<Instr>, HashMap<String, Function>) {
     let (mut program, funcs) = parse_bytecode(INTERP_OMGBC);
 
     // Drop the interpreter's final HALT so we can append our own instructions.
@@ -55,6 +102,138 @@ pub fn run_source(source: &str) -> Result<String, JsValue> {
     program.push(Instr::Call("run".to_string()));
     program.push(Instr::Halt);
 
-    exec(program, funcs, &[])
+    (program, funcs)
 }
 
+/// Execute an OMG source string using the embedded interpreter.
+///
+/// This splices the interpreter's initialization code in front of a small
+/// program that pushes the provided source and calls its `run` procedure.
+#[wasm_bindgen]
+pub fn run_source(source: &str) -> Result<String, JsValue> {
+    let (program, funcs) = splice_run_source(source);
+    exec(program, funcs, &[], &mut || None)
+}
+
+/// Same as [`run_source`], but `read_line()` calls pull from `input_fn`
+/// instead of always seeing end-of-input immediately (see
+/// [`run_file_interactive`]).
+#[wasm_bindgen]
+pub fn run_source_interactive(source: &str, input_fn: &js_sys::Function) -> Result<String, JsValue> {
+    let (program, funcs) = splice_run_source(source);
+    exec(program, funcs, &[], &mut || call_input_fn(input_fn))
+}
+
+/// Version string of the runtime.
+#[wasm_bindgen]
+pub fn version() -> String {
+    env!("CARGO_PKG_VERSION").to_string()
+}
+
+/// Outcome of one [`EvalSession::eval`] call.
+pub struct EvalOutcome {
+    /// Text the snippet printed, with the session's prior output diffed out
+    /// (see [`EvalSession`] docs) — only what's new this call.
+    pub stdout: String,
+    /// Set if the run faulted. Carries the full call-stack traceback; the
+    /// `runtime-wasm` crate maps this down to its `Diagnostic.message`/`kind`.
+    pub error: Option<TracedError>,
+    /// Dispatched-instruction count this call consumed, if a `fuel` or
+    /// `timeout_ms` budget was supplied (`None` means no budget was set, not
+    /// that nothing ran).
+    pub fuel_used: Option<u64>,
+}
+
+/// A persistent, embeddable evaluation session.
+///
+/// Each [`eval`](EvalSession::eval) call re-feeds the session's accumulated
+/// source history plus the new snippet through the embedded interpreter (via
+/// [`splice_run_source`]) — the same strategy `repl::repl_interpret` uses to
+/// give the terminal REPL memory across blocks, since the interpreter itself
+/// only exposes whole-program execution. Variables/functions a snippet
+/// defines become visible to the next `eval` call because they're baked into
+/// the next run's source text, not because this crate threads a shared VM
+/// `globals` table through — the interpreter keeps the user script's own
+/// bindings inside *its own* OMG-level environment, invisible to Rust.
+///
+/// Only successful snippets are folded into history, so a faulting snippet
+/// doesn't poison subsequent evals (matching the terminal REPL: a block is
+/// only committed to history once it actually completes).
+///
+/// This is the plain-Rust type the `runtime-wasm` crate's `WasmSession`
+/// wraps for its `#[wasm_bindgen]`-exported `eval`/`reset`; keeping it here
+/// (rather than in `runtime-wasm` directly) means it's usable — and
+/// unit-testable — without a wasm target.
+pub struct EvalSession {
+    history: String,
+    last_output: String,
+}
+
+impl Default for EvalSession {
+    fn default() -> Self {
+        EvalSession::new()
+    }
+}
+
+impl EvalSession {
+    /// Start a fresh session with no accumulated history.
+    pub fn new() -> Self {
+        EvalSession {
+            history: String::new(),
+            last_output: String::new(),
+        }
+    }
+
+    /// Reset the session back to empty, discarding all history.
+    pub fn reset(&mut self) {
+        self.history.clear();
+        self.last_output.clear();
+    }
+
+    /// Evaluate one snippet against this session's history, capped by an
+    /// optional instruction-count `fuel` and/or `timeout_ms` wall-clock
+    /// budget (see [`vm::Budget`]).
+    pub fn eval(&mut self, code: &str, fuel: Option<u64>, timeout_ms: Option<u32>) -> EvalOutcome {
+        let combined = format!("{}{}", self.history, code);
+        let (program, funcs) = splice_run_source(&combined);
+
+        let mut budget = Budget::new();
+        budget.fuel = fuel;
+        budget.timeout = timeout_ms.map(|ms| Duration::from_millis(ms as u64));
+        let has_budget = fuel.is_some() || timeout_ms.is_some();
+
+        let mut buf: Vec<u8> = Vec::new();
+        let result = run_traced(
+            &program,
+            &funcs,
+            &[],
+            None,
+            if has_budget { Some(&mut budget) } else { None },
+            &mut buf,
+        );
+        let fuel_used = has_budget.then(|| budget.fuel_used());
+
+        let full_output = String::from_utf8_lossy(&buf).into_owned();
+        let new_output = match full_output.strip_prefix(&self.last_output) {
+            Some(rest) => rest.to_string(),
+            None => full_output.clone(),
+        };
+
+        match result {
+            Ok(()) => {
+                self.last_output = full_output;
+                self.history = combined;
+                EvalOutcome {
+                    stdout: new_output,
+                    error: None,
+                    fuel_used,
+                }
+            }
+            Err(traced) => EvalOutcome {
+                stdout: new_output,
+                error: Some(traced),
+                fuel_used,
+            },
+        }
+    }
+}
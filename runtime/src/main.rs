@@ -6,7 +6,14 @@
 //!    interpreter** that’s compiled into this binary at build time.
 //!
 //! Behavior summary:
-//! - With **no args**, start an interactive REPL.
+//! - With **no args**, start an interactive source-level REPL.
+//! - With `--bytecode-repl`, start the bytecode-mnemonic REPL instead (see
+//!   `repl::repl_bytecode`).
+//! - With `--test <fixtures.txt>`, run a golden-test fixture file instead of
+//!   a program (see `test_runner`).
+//! - With `--emit-native <script.omgb> -o <out>`, ahead-of-time compile a
+//!   straight-line numeric bytecode program to a native executable instead
+//!   of running it (see `native_codegen`).
 //! - With `-h/--help`, print usage.
 //! - With `-v/--version`, print build-target + version.
 //! - With a **`.omgb`** path, load bytecode from disk and execute it.
@@ -20,16 +27,20 @@
 
 use std::env;
 use std::fs;
+use std::time::Duration;
 
 mod bytecode;
 mod error;
+mod gc;
+mod native_codegen;
 mod repl;
+mod test_runner;
 mod value;
 mod vm;
 
-use bytecode::parse_bytecode;
-use repl::repl_interpret;
-use vm::run;
+use bytecode::{disassemble, parse_bytecode, try_parse_bytecode, verify};
+use repl::{repl_bytecode, repl_interpret};
+use vm::{run_traced, Budget};
 
 /// Embedded `interpreter.omgb` generated at build time.
 ///
@@ -67,7 +78,28 @@ Options:
     -h, --help
         Show this help message and exit.
     -v, --version
-        Show runtime version."#,
+        Show runtime version.
+    --bytecode-repl
+        Start an interactive REPL over raw bytecode mnemonics instead of the
+        default OMG source REPL.
+    --test <fixtures.txt>
+        Run a section-based golden-test fixture file (see `test_runner`
+        module docs for the format) and report pass/fail per section.
+    --disasm <script.omgb>
+        Print a labeled textual listing of a compiled bytecode file instead
+        of running it.
+    --emit-native <script.omgb> -o <out>
+        Ahead-of-time compile a straight-line numeric bytecode program to a
+        native executable via nasm + ld (see `native_codegen` module docs
+        for the supported instruction subset). Unlike the interpreter, the
+        emitted arithmetic does not check for overflow or out-of-range
+        shift counts — it silently wraps/masks instead of raising
+        IntegerOverflow, so results can diverge from running the same
+        bytecode under the VM.
+    --fuel <n> / --timeout-ms <n>
+        (`.omgb` mode only, placed right after the script path) Cap execution
+        to at most <n> dispatched instructions and/or <n> milliseconds of
+        wall-clock time; exceeding either aborts with FuelExhausted/Timeout."#,
         VERSION
     )
 }
@@ -95,9 +127,123 @@ fn main() {
 
     // --- Mode selection & meta commands ------------------------------------
 
-    // No arguments → interactive REPL (dev-friendly quick start).
+    // No arguments → interactive source-level REPL (dev-friendly quick start).
+    // Hand it the decoded embedded interpreter so it can run blocks in-process
+    // instead of re-spawning this binary per turn (see `repl::repl_interpret`).
     if args.len() == 1 {
-        repl_interpret();
+        let (interp_code, interp_funcs) = parse_bytecode(INTERP_OMGBC);
+        repl_interpret(&interp_code, &interp_funcs);
+        return;
+    }
+
+    // Bytecode-mnemonic REPL, against a persistent in-process VM state.
+    if args[1] == "--bytecode-repl" {
+        repl_bytecode();
+        return;
+    }
+
+    // Print a labeled textual listing of a compiled bytecode file, for
+    // inspecting or diffing compiled output instead of hand-reading a raw
+    // `.omgb` dump.
+    if args[1] == "--disasm" {
+        let Some(bc_path) = args.get(2) else {
+            eprintln!("--disasm requires a bytecode file path");
+            std::process::exit(1);
+        };
+        let src = fs::read(bc_path).expect("failed to read bytecode file");
+        let (code, funcs) = match try_parse_bytecode(&src) {
+            Ok(result) => result,
+            Err(e) => {
+                eprintln!("corrupt bytecode: {}", e);
+                std::process::exit(1);
+            }
+        };
+        print!("{}", disassemble(&code, &funcs));
+        return;
+    }
+
+    // AOT native compile: lower bytecode to NASM assembly and invoke the
+    // system assembler/linker to produce a standalone executable.
+    if args[1] == "--emit-native" {
+        let Some(bc_path) = args.get(2) else {
+            eprintln!("--emit-native requires a bytecode file path");
+            std::process::exit(1);
+        };
+        let mut out_path = "a.out".to_string();
+        let mut i = 3;
+        while i < args.len() {
+            if args[i] == "-o" {
+                if let Some(v) = args.get(i + 1) {
+                    out_path = v.clone();
+                    i += 1;
+                }
+            }
+            i += 1;
+        }
+
+        let src = fs::read(bc_path).expect("failed to read bytecode file");
+        let (code, _funcs) = match try_parse_bytecode(&src) {
+            Ok(result) => result,
+            Err(e) => {
+                eprintln!("corrupt bytecode: {}", e);
+                std::process::exit(1);
+            }
+        };
+
+        let asm = match native_codegen::emit_native(&code) {
+            Ok(asm) => asm,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        };
+
+        let asm_path = format!("{}.asm", out_path);
+        let obj_path = format!("{}.o", out_path);
+        fs::write(&asm_path, asm).expect("failed to write generated assembly");
+
+        let assemble = std::process::Command::new("nasm")
+            .args(["-f", "elf64", &asm_path, "-o", &obj_path])
+            .status();
+        match assemble {
+            Ok(status) if status.success() => {}
+            Ok(status) => {
+                eprintln!("nasm exited with status {}", status);
+                std::process::exit(1);
+            }
+            Err(e) => {
+                eprintln!("failed to run nasm: {}", e);
+                std::process::exit(1);
+            }
+        }
+
+        let link = std::process::Command::new("ld").args([&obj_path, "-o", &out_path]).status();
+        match link {
+            Ok(status) if status.success() => {}
+            Ok(status) => {
+                eprintln!("ld exited with status {}", status);
+                std::process::exit(1);
+            }
+            Err(e) => {
+                eprintln!("failed to run ld: {}", e);
+                std::process::exit(1);
+            }
+        }
+
+        println!("wrote {}", out_path);
+        return;
+    }
+
+    // Golden-test runner: execute every record in a fixture file and compare
+    // captured `emit` output against its `expected-output` block.
+    if args[1] == "--test" {
+        let Some(fixture_path) = args.get(2) else {
+            eprintln!("--test requires a fixture file path");
+            std::process::exit(1);
+        };
+        if !test_runner::run_fixture_file(fixture_path) {
+            std::process::exit(1);
+        }
         return;
     }
 
@@ -123,20 +269,35 @@ fn main() {
     if args[1].ends_with(".omgb") {
         // === Bytecode mode: execute a precompiled .omgb binary ===
         //
-        // Layout: omg <file.omgb> [--] [program args...]
+        // Layout: omg <file.omgb> [--fuel <n>] [--timeout-ms <n>] [--] [program args...]
         // We slice the original `args` to obtain "program args" exposed to the VM.
         let bc_path = &args[1];
 
-        // Extract program arguments after the `.omgb` path.
-        // If `--` is present immediately after the path, skip it.
-        let program_args: &[String] = if args.len() > 2 {
-            if args[2] == "--" {
-                &args[3..]
-            } else {
-                &args[2..]
+        // Optional execution budget, recognized right after the script path
+        // and before the `--`/program-args tail.
+        let mut rest = &args[2..];
+        let mut fuel: Option<u64> = None;
+        let mut timeout_ms: Option<u64> = None;
+        loop {
+            match rest.first().map(String::as_str) {
+                Some("--fuel") => {
+                    fuel = rest.get(1).and_then(|v| v.parse().ok());
+                    rest = &rest[2.min(rest.len())..];
+                }
+                Some("--timeout-ms") => {
+                    timeout_ms = rest.get(1).and_then(|v| v.parse().ok());
+                    rest = &rest[2.min(rest.len())..];
+                }
+                _ => break,
             }
+        }
+
+        // Extract program arguments after the budget flags.
+        // If `--` is present immediately after, skip it.
+        let program_args: &[String] = if rest.first().map(String::as_str) == Some("--") {
+            &rest[1..]
         } else {
-            &[]
+            rest
         };
 
         // Read bytecode from disk; any I/O error is a hard failure (panic)
@@ -144,11 +305,42 @@ fn main() {
         let src = fs::read(bc_path).expect("failed to read bytecode file");
 
         // Decode the bytecode image into instruction stream + function table.
-        let (code, funcs) = parse_bytecode(&src);
+        // This is untrusted input (a user-supplied file), so use the
+        // non-panicking parser and report a decode failure the same way a
+        // `verify()` failure below is reported.
+        let (code, funcs) = match try_parse_bytecode(&src) {
+            Ok(result) => result,
+            Err(e) => {
+                eprintln!("corrupt bytecode: {}", e);
+                std::process::exit(1);
+            }
+        };
+
+        // Catch malformed bytecode (out-of-bounds jump targets, unresolved
+        // calls, stack-depth inconsistencies) up front with a localized
+        // diagnostic, instead of letting it surface mid-run as an opaque
+        // `VmInvariant`.
+        if let Err(e) = verify(&code, &funcs) {
+            eprintln!("bytecode verification failed: {}", e);
+            std::process::exit(1);
+        }
+
+        let mut budget = Budget::new();
+        budget.fuel = fuel;
+        budget.timeout = timeout_ms.map(Duration::from_millis);
+        let has_budget = fuel.is_some() || timeout_ms.is_some();
 
         // Hand off to the VM. On runtime error we print to stderr and exit 1
         // (so that shells/scripts can detect failure).
-        if let Err(e) = run(&code, &funcs, program_args) {
+        let result = run_traced(
+            &code,
+            &funcs,
+            program_args,
+            None,
+            if has_budget { Some(&mut budget) } else { None },
+            &mut std::io::stdout(),
+        );
+        if let Err(e) = result {
             eprintln!("{}", e);
             std::process::exit(1);
         }
@@ -185,7 +377,7 @@ fn main() {
 
         // Execute the interpreter, providing it with the constructed arguments.
         // On error, forward message to stderr and exit 1.
-        if let Err(e) = run(&code, &funcs, &full_args) {
+        if let Err(e) = run_traced(&code, &funcs, &full_args, None, None, &mut std::io::stdout()) {
             eprintln!("{}", e);
             std::process::exit(1);
         }
@@ -0,0 +1,287 @@
+//! # AOT native code generation (`--emit-native`)
+//!
+//! Lowers a **straight-line, numeric-only** subset of the bytecode instruction
+//! stream to NASM-style x86-64 assembly, for programs that don't need the
+//! full VM (no variables, control flow, calls, or string/list/dict values).
+//!
+//! ## Scope
+//! Supported: `PushInt`, `PushBool`, arithmetic (`Add`/`Sub`/`Mul`/`Div`/`Mod`),
+//! bitwise (`BAnd`/`BOr`/`BXor`/`Shl`/`Shr`), comparisons (`Eq`/`Ne`/`Lt`/`Le`/
+//! `Gt`/`Ge`), boolean ops (`And`/`Or`/`Not`), `Neg`, `Pop`, `Emit`, and `Halt`.
+//!
+//! Everything else — variables, jumps, function calls, exceptions, imports,
+//! indexing/slicing/attributes, and any string/list/dict-producing op — is
+//! rejected with a diagnostic naming the unsupported instruction and its
+//! position, rather than silently mis-compiled. Running those programs
+//! through the VM (`omg script.omgb`) is still the general-purpose path;
+//! this backend is an optimization for a bounded numeric subset, not a
+//! replacement for it.
+//!
+//! ## Model
+//! The operand stack is the real machine stack (`rsp`): `PushInt` is
+//! `mov rax, imm` + `push rax`; every binary op is `pop`/`pop`/compute/`push`,
+//! matching the VM's own `pop(b); pop(a); ...; push(result)` order so
+//! non-commutative ops (`Sub`, `Div`, `Shl`, ...) come out in the same operand
+//! order as `ops_arith.rs`. `Emit` pops one value into `rdi` and calls a small
+//! `print_int` routine (decimal ASCII conversion + a `write(2)` syscall);
+//! integer division/modulo by zero branches to a guard that prints a message
+//! and exits(1), mirroring `RuntimeError::ZeroDivisionError` without having a
+//! VM around to raise it properly.
+//!
+//! ## Known divergence from the VM: no overflow/shift-range checks
+//! Unlike the interpreter, which uses `checked_add`/`checked_sub`/
+//! `checked_mul`/`checked_shl` and raises `RuntimeError::IntegerOverflow`
+//! (see `vm/ops_arith.rs`), the arithmetic emitted here is raw `add`/`sub`/
+//! `imul`/`shl`/`shr`: it silently wraps on overflow and masks the shift
+//! count to 6 bits on `Shl`/`Shr` (x86's `shl r64, cl` semantics) instead of
+//! erroring. The same bytecode can therefore produce different results
+//! under `--emit-native` than under the normal VM path for inputs near
+//! `i64::MIN`/`i64::MAX` or with shift counts outside `0..64`. There is no
+//! check for this yet; callers who need VM-identical overflow behavior
+//! should not rely on `--emit-native` for such inputs.
+
+use crate::bytecode::Instr;
+
+/// Lower `code` to a complete NASM source file (as text), or `Err` naming the
+/// first unsupported instruction encountered and its index in `code`.
+pub fn emit_native(code: &[Instr]) -> Result<String, String> {
+    let mut body = String::new();
+    for (pc, instr) in code.iter().enumerate() {
+        match instr {
+            Instr::PushInt(v) => {
+                body.push_str(&format!("    mov rax, {}\n    push rax\n", v));
+            }
+            Instr::PushBool(b) => {
+                body.push_str(&format!("    mov rax, {}\n    push rax\n", if *b { 1 } else { 0 }));
+            }
+            Instr::Add => body.push_str("    pop rbx\n    pop rax\n    add rax, rbx\n    push rax\n"),
+            Instr::Sub => body.push_str("    pop rbx\n    pop rax\n    sub rax, rbx\n    push rax\n"),
+            Instr::Mul => body.push_str("    pop rbx\n    pop rax\n    imul rax, rbx\n    push rax\n"),
+            Instr::Div => body.push_str(
+                "    pop rbx\n    pop rax\n    cmp rbx, 0\n    je zero_division_error\n    cqo\n    idiv rbx\n    push rax\n",
+            ),
+            Instr::Mod => body.push_str(
+                "    pop rbx\n    pop rax\n    cmp rbx, 0\n    je zero_division_error\n    cqo\n    idiv rbx\n    push rdx\n",
+            ),
+            Instr::BAnd => body.push_str("    pop rbx\n    pop rax\n    and rax, rbx\n    push rax\n"),
+            Instr::BOr => body.push_str("    pop rbx\n    pop rax\n    or rax, rbx\n    push rax\n"),
+            Instr::BXor => body.push_str("    pop rbx\n    pop rax\n    xor rax, rbx\n    push rax\n"),
+            Instr::Shl => body.push_str("    pop rcx\n    pop rax\n    shl rax, cl\n    push rax\n"),
+            Instr::Shr => body.push_str("    pop rcx\n    pop rax\n    shr rax, cl\n    push rax\n"),
+            Instr::Eq => body.push_str(&compare_snippet("sete")),
+            Instr::Ne => body.push_str(&compare_snippet("setne")),
+            Instr::Lt => body.push_str(&compare_snippet("setl")),
+            Instr::Le => body.push_str(&compare_snippet("setle")),
+            Instr::Gt => body.push_str(&compare_snippet("setg")),
+            Instr::Ge => body.push_str(&compare_snippet("setge")),
+            Instr::And => body.push_str("    pop rbx\n    pop rax\n    and rax, rbx\n    push rax\n"),
+            Instr::Or => body.push_str("    pop rbx\n    pop rax\n    or rax, rbx\n    push rax\n"),
+            Instr::Not => {
+                body.push_str("    pop rax\n    test rax, rax\n    sete al\n    movzx rax, al\n    push rax\n")
+            }
+            Instr::Neg => body.push_str("    pop rax\n    neg rax\n    push rax\n"),
+            Instr::Pop => body.push_str("    add rsp, 8\n"),
+            Instr::Emit => body.push_str("    pop rdi\n    call print_int\n"),
+            Instr::Halt => body.push_str("    jmp exit_ok\n"),
+            other => {
+                return Err(format!(
+                    "unsupported in native mode: `{}` at instruction {} (only straight-line numeric ops are supported; see native_codegen module docs)",
+                    other.name(),
+                    pc
+                ));
+            }
+        }
+    }
+
+    Ok(format!(
+        r#"; Generated by `omg --emit-native` — see runtime/src/native_codegen.rs.
+; Numeric-only bytecode lowered to x86-64; assemble with NASM, link with ld.
+BITS 64
+
+section .bss
+itoa_buf: resb 32
+
+section .data
+zero_div_msg: db "ZeroDivisionError: integer division or modulo by zero", 10
+zero_div_msg_len: equ $ - zero_div_msg
+
+section .text
+global _start
+
+_start:
+{body}
+exit_ok:
+    mov rax, 60
+    xor rdi, rdi
+    syscall
+
+; print_int: write the decimal (signed) representation of rdi, plus a
+; trailing newline, to stdout. Converts by repeated division, building
+; digits back-to-front into itoa_buf. i64::MIN negates to itself, which the
+; unsigned `div` below still converts to the right digits.
+print_int:
+    mov rax, rdi
+    mov rsi, itoa_buf + 31
+    mov byte [rsi], 10
+    dec rsi
+    xor rcx, rcx
+    cmp rax, 0
+    jge .convert
+    neg rax
+    mov rcx, 1
+.convert:
+    mov rbx, 10
+.divloop:
+    xor rdx, rdx
+    div rbx
+    add rdx, '0'
+    mov [rsi], dl
+    dec rsi
+    test rax, rax
+    jnz .divloop
+    cmp rcx, 1
+    jne .done
+    mov byte [rsi], '-'
+    dec rsi
+.done:
+    inc rsi
+    lea rdx, [itoa_buf + 32]
+    sub rdx, rsi
+    mov rax, 1
+    mov rdi, 1
+    syscall
+    ret
+
+zero_division_error:
+    mov rax, 1
+    mov rdi, 2
+    lea rsi, [rel zero_div_msg]
+    mov rdx, zero_div_msg_len
+    syscall
+    mov rax, 60
+    mov rdi, 1
+    syscall
+"#,
+        body = body
+    ))
+}
+
+/// Shared snippet for `Eq`/`Ne`/`Lt`/`Le`/`Gt`/`Ge`: `cmp rax, rbx`, set the
+/// low byte per `setcc`, zero-extend, push. `setcc` is the mnemonic for
+/// whichever `setcc` variant (`sete`, `setl`, ...) matches the comparison.
+fn compare_snippet(setcc: &str) -> String {
+    format!(
+        "    pop rbx\n    pop rax\n    cmp rax, rbx\n    {} al\n    movzx rax, al\n    push rax\n",
+        setcc
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_int_and_push_bool_emit_mov_then_push() {
+        let code = vec![Instr::PushInt(42), Instr::PushBool(true), Instr::Halt];
+        let asm = emit_native(&code).expect("supported instructions should lower");
+        assert!(asm.contains("mov rax, 42\n    push rax"));
+        assert!(asm.contains("mov rax, 1\n    push rax"));
+    }
+
+    #[test]
+    fn arithmetic_ops_pop_rhs_then_lhs_to_match_vm_operand_order() {
+        // pop rbx (b) before pop rax (a), so `a - b` lowers to `rax - rbx`
+        // with rax holding a — matching ops_arith.rs's `pop(b); pop(a)` order.
+        for (instr, op) in [
+            (Instr::Add, "add rax, rbx"),
+            (Instr::Sub, "sub rax, rbx"),
+            (Instr::Mul, "imul rax, rbx"),
+            (Instr::BAnd, "and rax, rbx"),
+            (Instr::BOr, "or rax, rbx"),
+            (Instr::BXor, "xor rax, rbx"),
+        ] {
+            let asm = emit_native(&[instr]).expect("supported instructions should lower");
+            assert!(
+                asm.contains(&format!("pop rbx\n    pop rax\n    {}\n    push rax", op)),
+                "expected pop rbx/pop rax/{op}/push rax, got:\n{asm}"
+            );
+        }
+    }
+
+    #[test]
+    fn shifts_pop_count_into_rcx() {
+        for (instr, op) in [(Instr::Shl, "shl rax, cl"), (Instr::Shr, "shr rax, cl")] {
+            let asm = emit_native(&[instr]).expect("supported instructions should lower");
+            assert!(
+                asm.contains(&format!("pop rcx\n    pop rax\n    {}\n    push rax", op)),
+                "expected the shift count in rcx, got:\n{asm}"
+            );
+        }
+    }
+
+    #[test]
+    fn comparisons_emit_cmp_then_the_matching_setcc() {
+        for (instr, setcc) in [
+            (Instr::Eq, "sete"),
+            (Instr::Ne, "setne"),
+            (Instr::Lt, "setl"),
+            (Instr::Le, "setle"),
+            (Instr::Gt, "setg"),
+            (Instr::Ge, "setge"),
+        ] {
+            let asm = emit_native(&[instr]).expect("supported instructions should lower");
+            assert!(
+                asm.contains(&format!("cmp rax, rbx\n    {} al\n    movzx rax, al", setcc)),
+                "expected cmp rax, rbx followed by {setcc} al, got:\n{asm}"
+            );
+        }
+    }
+
+    #[test]
+    fn div_and_mod_both_guard_on_zero_and_pick_quotient_or_remainder() {
+        let div = emit_native(&[Instr::Div]).expect("Div should lower");
+        assert!(div.contains("je zero_division_error"));
+        assert!(div.contains("idiv rbx\n    push rax"));
+
+        let rem = emit_native(&[Instr::Mod]).expect("Mod should lower");
+        assert!(rem.contains("je zero_division_error"));
+        assert!(rem.contains("idiv rbx\n    push rdx"));
+    }
+
+    #[test]
+    fn boolean_ops_and_unary_ops_lower_as_expected() {
+        let and_or = emit_native(&[Instr::And, Instr::Or]).expect("And/Or should lower");
+        assert_eq!(and_or.matches("and rax, rbx\n    push rax").count(), 1);
+        assert_eq!(and_or.matches("or rax, rbx\n    push rax").count(), 1);
+
+        let not = emit_native(&[Instr::Not]).expect("Not should lower");
+        assert!(not.contains("test rax, rax\n    sete al\n    movzx rax, al"));
+
+        let neg = emit_native(&[Instr::Neg]).expect("Neg should lower");
+        assert!(neg.contains("neg rax\n    push rax"));
+
+        let pop = emit_native(&[Instr::Pop]).expect("Pop should lower");
+        assert!(pop.contains("add rsp, 8"));
+    }
+
+    #[test]
+    fn emit_calls_print_int_with_the_popped_value_in_rdi() {
+        let asm = emit_native(&[Instr::Emit]).expect("Emit should lower");
+        assert!(asm.contains("pop rdi\n    call print_int"));
+    }
+
+    #[test]
+    fn halt_jumps_to_exit_ok_and_the_label_is_defined() {
+        let asm = emit_native(&[Instr::Halt]).expect("Halt should lower");
+        assert!(asm.contains("jmp exit_ok"));
+        assert!(asm.contains("exit_ok:"));
+    }
+
+    #[test]
+    fn unsupported_instruction_is_rejected_with_its_name_and_position() {
+        let code = vec![Instr::PushInt(1), Instr::Load("x".to_string())];
+        let err = emit_native(&code).expect_err("Load should be rejected");
+        assert!(err.contains("LOAD") || err.contains("Load"), "got: {err}");
+        assert!(err.contains('1'), "expected the offending index 1 in: {err}");
+    }
+}
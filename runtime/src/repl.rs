@@ -1,45 +1,215 @@
-//! # OMG Language REPL
+//! # OMG Language REPL(s)
 //!
-//! This module implements an **interactive Read–Eval–Print Loop (REPL)** for
-//! the OMG language. It allows users to type OMG code line by line, evaluate
-//! it immediately, and see results.
+//! This module implements two interactive Read–Eval–Print Loops:
 //!
-//! ## Design
+//! - [`repl_interpret`]: types OMG **source** line by line, against the
+//!   embedded interpreter, run in-process — see its own docs below.
+//! - [`repl_bytecode`]: types raw **bytecode mnemonics** line by line,
+//!   executed directly, in-process, against a persistent [`crate::vm::VmState`].
+//!
+//! ## `repl_interpret` design
+//! - Real line editing (cursor movement, history recall, tab completion) via
+//!   `rustyline`, same as [`repl_bytecode`] below.
 //! - Provides prompts (`>>>` for fresh input, `...` for continuation).
 //! - Tracks **brace depth** so users can enter multi-line blocks (e.g., function
-//!   definitions, conditionals) before execution.
-//! - Preserves **command history** so new input can build upon previously
-//!   defined variables and functions.
-//! - Executes code by writing it to a temporary `.omg` file and re-invoking the
-//!   current binary with that file. This ensures consistency between REPL and
-//!   script execution.
+//!   definitions, conditionals) before execution — this scan is unchanged from
+//!   the original blocking-`stdin` version, just driven per-line through the
+//!   editor instead of `io::stdin().read_line`.
+//! - Preserves **command history** (in the interpreted-program sense: prior
+//!   blocks are re-fed alongside new input) so new input can build upon
+//!   previously defined variables and functions, *and* (in the line-editor
+//!   sense) a persisted `~/.omg_history` file of accepted blocks, recalled
+//!   with the up/down arrows across sessions.
+//! - Executes code by writing it to a temporary `.omg` file and running the
+//!   embedded interpreter bytecode against it in-process via [`crate::vm::run_traced`]
+//!   (no subprocess spawn) — see "Limitations" below for why the temp file
+//!   and the history-replay-plus-diff model both remain.
 //! - Supports graceful exit with `exit` or `quit`.
 //!
-//! ## Limitations
-//! - Because execution is performed by spawning a new process, performance is
-//!   lower than a native in-process interpreter loop.
-//! - Output diffing (`last_output`) is used to only print new results between
-//!   iterations, preventing repeated display of old output.
+//! ### Limitations
+//! - The embedded interpreter is itself compiled OMG bytecode; it has no
+//!   incremental-eval entry point, only a `run(source)` that parses and
+//!   executes a whole program from scratch. So each block is still executed
+//!   by re-feeding `history + block` as one source text (not a new Rust-level
+//!   "hack" — a consequence of the interpreter's own design, out of scope
+//!   here), and `last_output` diffing still suppresses the re-printed history
+//!   each turn. What this revision removes is the *process*-level hack: no
+//!   more spawning a child process of the current binary and shelling out to
+//!   re-invoke it once per block.
+//! - The interpreter still expects a script *path* (it reads the program off
+//!   disk by filename, the same contract `main.rs`'s source mode uses), so a
+//!   temp file is still written/removed per turn.
+//!
+//! ## `repl_bytecode` design
+//! See its own doc comment.
 
+use std::collections::HashMap;
 use std::fs;
 use std::io::{self, Write};
-use std::process::Command;
+use std::path::PathBuf;
+
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::{ValidationContext, ValidationResult, Validator};
+use rustyline::{Context, Editor, Helper};
+
+use crate::bytecode::{Function, Instr};
+use crate::vm::{run_traced, Builtins, VmState};
+
+/// Best-effort keyword set for source-REPL completion.
+///
+/// The OMG language frontend (lexer/parser) lives in `bootstrap/interpreter.omg`,
+/// not in this Rust crate, so there's no authoritative keyword table to import
+/// here — this list is inferred from what the bytecode layer exposes (`Raise`,
+/// `Import`, `Assert`, boolean ops) plus the usual suspects for a C-brace-style
+/// language. Good enough for completion; not a source of truth for validity.
+const SOURCE_KEYWORDS: &[&str] = &[
+    "function", "return", "if", "else", "elif", "while", "for", "let", "true", "false", "none",
+    "and", "or", "not", "import", "try", "except", "raise", "assert", "break", "continue",
+];
+
+/// Pull out plausible identifiers from a block of OMG source for completion
+/// purposes: the name after `function` and the name before a top-level `=`.
+/// Heuristic, not a real tokenizer — false negatives just mean a name isn't
+/// offered for completion yet, never a correctness issue.
+fn extract_identifiers(source: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut words = source.split(|c: char| !c.is_alphanumeric() && c != '_').peekable();
+    let mut prev: Option<&str> = None;
+    while let Some(word) = words.next() {
+        if word.is_empty() {
+            continue;
+        }
+        if prev == Some("function") {
+            names.push(word.to_string());
+        }
+        prev = Some(word);
+    }
+    for line in source.lines() {
+        if let Some((lhs, _)) = line.split_once('=') {
+            let lhs = lhs.trim();
+            if !lhs.is_empty()
+                && !lhs.ends_with(['=', '!', '<', '>'])
+                && lhs.chars().all(|c| c.is_alphanumeric() || c == '_')
+            {
+                names.push(lhs.to_string());
+            }
+        }
+    }
+    names
+}
+
+/// `rustyline` helper for [`repl_interpret`]: completes OMG keywords plus
+/// identifiers seen in previously accepted blocks (see [`extract_identifiers`]).
+struct SourceReplHelper {
+    identifiers: Vec<String>,
+}
+
+impl Completer for SourceReplHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let start = line[..pos]
+            .rfind(|c: char| !c.is_alphanumeric() && c != '_')
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        let word = &line[start..pos];
+
+        let mut candidates: Vec<Pair> = SOURCE_KEYWORDS
+            .iter()
+            .filter(|kw| kw.starts_with(word))
+            .map(|kw| Pair {
+                display: kw.to_string(),
+                replacement: kw.to_string(),
+            })
+            .collect();
+        candidates.extend(self.identifiers.iter().filter(|id| id.starts_with(word)).map(|id| Pair {
+            display: id.clone(),
+            replacement: id.clone(),
+        }));
+
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for SourceReplHelper {
+    type Hint = String;
+
+    fn hint(&self, _line: &str, _pos: usize, _ctx: &Context<'_>) -> Option<String> {
+        None
+    }
+}
+
+impl Highlighter for SourceReplHelper {
+    /// Color recognized keywords at the start of the line; everything else is
+    /// left unstyled (a full tokenizing highlighter isn't worth the
+    /// complexity without a real lexer to drive it from).
+    fn highlight<'l>(&self, line: &'l str, _pos: usize) -> std::borrow::Cow<'l, str> {
+        match line.split_whitespace().next() {
+            Some(word) if SOURCE_KEYWORDS.contains(&word) => {
+                std::borrow::Cow::Owned(format!("\x1b[35m{}\x1b[0m{}", word, &line[word.len()..]))
+            }
+            _ => std::borrow::Cow::Borrowed(line),
+        }
+    }
+}
+
+impl Validator for SourceReplHelper {
+    /// Brace-depth/continuation is handled by [`repl_interpret`] itself (it
+    /// needs to track state across lines within one block), so every line
+    /// handed to the editor is considered complete on its own.
+    fn validate(&self, ctx: &mut ValidationContext) -> rustyline::Result<ValidationResult> {
+        if ctx.input().is_empty() {
+            Ok(ValidationResult::Incomplete)
+        } else {
+            Ok(ValidationResult::Valid(None))
+        }
+    }
+}
+
+impl Helper for SourceReplHelper {}
+
+/// Resolve the dotfile path used to persist source-REPL history across
+/// sessions. `None` if `$HOME` isn't set, in which case history just isn't
+/// persisted.
+fn source_history_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".omg_history"))
+}
 
 /// Run an interactive REPL for the OMG language.
 ///
+/// `interp_code`/`interp_funcs` are the embedded interpreter's decoded
+/// bytecode (see `main.rs`'s `INTERP_OMGBC`) — the same image `main.rs` would
+/// run for `.omg` source files, reused here so the REPL executes in-process
+/// instead of re-spawning the current binary.
+///
 /// The loop:
-/// 1. Prints a prompt.
-/// 2. Reads a line of user input.
-/// 3. If braces are balanced and the user isn’t inside a string, executes the
+/// 1. Prints a prompt (`editor.readline`, so arrow keys/history/completion
+///    all work as in any shell).
+/// 2. If braces are balanced and the user isn’t inside a string, executes the
 ///    accumulated block.
-/// 4. Displays new output while suppressing repeated history.
-/// 5. Resets buffers for the next iteration.
+/// 3. Displays new output while suppressing repeated history.
+/// 4. Resets buffers for the next iteration.
 ///
 /// Exits cleanly on EOF (Ctrl+D) or if the user types `exit`/`quit`.
-pub fn repl_interpret() {
+pub fn repl_interpret(interp_code: &[Instr], interp_funcs: &HashMap<String, Function>) {
     println!("OMG Language Interpreter - REPL");
     println!("Type `exit` or `quit` to leave.");
 
+    let mut editor = Editor::<SourceReplHelper>::new().expect("failed to start line editor");
+    editor.set_helper(Some(SourceReplHelper { identifiers: Vec::new() }));
+    let history_path = source_history_path();
+    if let Some(path) = &history_path {
+        let _ = editor.load_history(path);
+    }
+
     // Running history of successfully executed code (preserved across turns).
     let mut history = String::new();
     // Tracks the full stdout of the last run so we can diff and only print new lines.
@@ -52,15 +222,19 @@ pub fn repl_interpret() {
     loop {
         // Choose primary (>>> ) or continuation (... ) prompt.
         let prompt = if buffer.is_empty() { ">>> " } else { "... " };
-        print!("{}", prompt);
-        io::stdout().flush().unwrap();
-
-        let mut line = String::new();
-        // EOF (Ctrl+D) → exit gracefully.
-        if io::stdin().read_line(&mut line).unwrap() == 0 {
-            println!();
-            break;
-        }
+        let line = match editor.readline(prompt) {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => {
+                buffer.clear();
+                brace_depth = 0;
+                continue;
+            }
+            Err(ReadlineError::Eof) => break,
+            Err(e) => {
+                println!("readline error: {}", e);
+                break;
+            }
+        };
 
         let trimmed = line.trim();
         // Allow "exit" or "quit" as explicit exit commands (only at fresh prompt).
@@ -92,7 +266,7 @@ pub fn repl_interpret() {
             }
         }
 
-        buffer.push(line);
+        buffer.push(format!("{}\n", line));
 
         // If braces are still open, wait for more input before executing.
         if brace_depth > 0 {
@@ -104,7 +278,8 @@ pub fn repl_interpret() {
         // Combine prior history with the current block into one program.
         let source = format!(";;;omg\n{}{}", history, block);
 
-        // Write to a temporary `.omg` file.
+        // The interpreter reads its program off disk by path, so it still
+        // needs a temp file even though execution itself is now in-process.
         let temp_path = std::env::temp_dir().join("omg_repl.omg");
         if fs::write(&temp_path, &source).is_err() {
             println!("failed to write temp file");
@@ -113,49 +288,297 @@ pub fn repl_interpret() {
             continue;
         }
 
-        // Spawn a child process of the current binary, running the temp script.
-        let output = Command::new(std::env::current_exe().unwrap())
-            .arg(temp_path.to_string_lossy().to_string())
-            .output();
+        let temp_path_str = temp_path.to_string_lossy().to_string();
+        let mut out_buf: Vec<u8> = Vec::new();
+        let result = run_traced(interp_code, interp_funcs, &[temp_path_str], None, None, &mut out_buf);
 
-        // Clean up the temp file after execution.
         let _ = fs::remove_file(&temp_path);
 
-        match output {
-            Ok(out) => {
-                let stderr = String::from_utf8_lossy(&out.stderr).to_string();
-                if !stderr.is_empty() {
-                    // If parse error complains about unexpected EOF, allow more input.
-                    if stderr.contains("EOF") {
-                        continue;
-                    } else {
-                        // Otherwise print error and reset buffer.
-                        print!("{}", stderr);
-                        buffer.clear();
-                        brace_depth = 0;
-                        continue;
-                    }
+        match result {
+            Err(traced) => {
+                // If parse error complains about unexpected EOF, allow more input.
+                if traced.to_string().contains("EOF") {
+                    continue;
                 }
-
-                let stdout = String::from_utf8_lossy(&out.stdout).to_string();
+                println!("{}", traced);
+                buffer.clear();
+                brace_depth = 0;
+            }
+            Ok(()) => {
+                let stdout = String::from_utf8_lossy(&out_buf).to_string();
                 // Diff new stdout against the previous run, only print new content.
                 if stdout.starts_with(&last_output) {
                     print!("{}", &stdout[last_output.len()..]);
                 } else {
                     print!("{}", stdout);
                 }
+                let _ = io::stdout().flush();
 
                 last_output = stdout;
                 // Accumulate successful block into history so state persists.
+                let _ = editor.add_history_entry(block.trim_end());
+                if let Some(helper) = editor.helper_mut() {
+                    for name in extract_identifiers(&block) {
+                        if !helper.identifiers.contains(&name) {
+                            helper.identifiers.push(name);
+                        }
+                    }
+                }
                 history.push_str(&block);
                 buffer.clear();
                 brace_depth = 0;
             }
-            Err(_) => {
-                println!("failed to run script");
-                buffer.clear();
-                brace_depth = 0;
+        }
+    }
+
+    if let Some(path) = &history_path {
+        let _ = editor.save_history(path);
+    }
+}
+
+// === Bytecode REPL ==========================================================
+
+/// Opcode mnemonics accepted by [`parse_instr`], used both to parse input and
+/// to drive completion/highlighting in [`ReplHelper`].
+const OPCODES: &[&str] = &[
+    "PUSH_INT", "PUSH_FLOAT", "PUSH_STR", "PUSH_BOOL", "PUSH_NONE", "LOAD", "STORE", "ADD", "SUB",
+    "MUL", "DIV", "MOD", "EQ", "NE", "LT", "LE", "GT", "GE", "BAND", "BOR", "BXOR", "SHL", "SHR",
+    "AND", "OR", "NOT", "NEG", "INDEX", "SLICE", "POP", "RET", "EMIT", "ASSERT", "CALL_BUILTIN",
+];
+
+/// Parse one line of input into a single [`Instr`].
+///
+/// Only opcodes that are meaningful standalone are supported: pushes,
+/// arithmetic/comparison/bitwise/boolean ops, variable load/store, builtin
+/// calls, and a handful of misc ops (`POP`, `RET`, `EMIT`, `ASSERT`).
+/// Control-flow (`JUMP`, `JUMP_IF_FALSE`, `CALL`, `TAIL_CALL`, `CALL_VALUE`),
+/// exception scaffolding (`SETUP_EXCEPT`, `POP_BLOCK`, `RAISE`), and `IMPORT`
+/// all take instruction-index or cross-frame operands that don't make sense
+/// for a REPL where every line is its own one-instruction program; they are
+/// rejected here with an explanatory error instead of silently mis-parsed.
+pub(crate) fn parse_instr(line: &str) -> Result<Instr, String> {
+    let mut parts = line.splitn(2, char::is_whitespace);
+    let op = parts.next().unwrap_or("").to_uppercase();
+    let rest = parts.next().unwrap_or("").trim();
+
+    match op.as_str() {
+        "PUSH_INT" => rest
+            .parse::<i64>()
+            .map(Instr::PushInt)
+            .map_err(|_| format!("PUSH_INT expects an integer, got '{}'", rest)),
+        "PUSH_FLOAT" => rest
+            .parse::<f64>()
+            .map(Instr::PushFloat)
+            .map_err(|_| format!("PUSH_FLOAT expects a float, got '{}'", rest)),
+        "PUSH_STR" => Ok(Instr::PushStr(rest.trim_matches('"').to_string())),
+        "PUSH_BOOL" => match rest {
+            "true" => Ok(Instr::PushBool(true)),
+            "false" => Ok(Instr::PushBool(false)),
+            _ => Err(format!("PUSH_BOOL expects true/false, got '{}'", rest)),
+        },
+        "PUSH_NONE" => Ok(Instr::PushNone),
+        "LOAD" if !rest.is_empty() => Ok(Instr::Load(rest.to_string())),
+        "STORE" if !rest.is_empty() => Ok(Instr::Store(rest.to_string())),
+        "LOAD" | "STORE" => Err(format!("{} requires a variable name", op)),
+        "ADD" => Ok(Instr::Add),
+        "SUB" => Ok(Instr::Sub),
+        "MUL" => Ok(Instr::Mul),
+        "DIV" => Ok(Instr::Div),
+        "MOD" => Ok(Instr::Mod),
+        "EQ" => Ok(Instr::Eq),
+        "NE" => Ok(Instr::Ne),
+        "LT" => Ok(Instr::Lt),
+        "LE" => Ok(Instr::Le),
+        "GT" => Ok(Instr::Gt),
+        "GE" => Ok(Instr::Ge),
+        "BAND" => Ok(Instr::BAnd),
+        "BOR" => Ok(Instr::BOr),
+        "BXOR" => Ok(Instr::BXor),
+        "SHL" => Ok(Instr::Shl),
+        "SHR" => Ok(Instr::Shr),
+        "AND" => Ok(Instr::And),
+        "OR" => Ok(Instr::Or),
+        "NOT" => Ok(Instr::Not),
+        "NEG" => Ok(Instr::Neg),
+        "INDEX" => Ok(Instr::Index),
+        "SLICE" => Ok(Instr::Slice),
+        "POP" => Ok(Instr::Pop),
+        "RET" => Ok(Instr::Ret),
+        "EMIT" => Ok(Instr::Emit),
+        "ASSERT" => Ok(Instr::Assert),
+        "CALL_BUILTIN" => {
+            let mut args = rest.splitn(2, char::is_whitespace);
+            let name = args.next().unwrap_or("").to_string();
+            if name.is_empty() {
+                return Err("CALL_BUILTIN requires '<name> <argc>'".to_string());
+            }
+            let argc: usize = args
+                .next()
+                .unwrap_or("0")
+                .trim()
+                .parse()
+                .map_err(|_| "CALL_BUILTIN expects '<name> <argc>'".to_string())?;
+            Ok(Instr::CallBuiltin(name, argc))
+        }
+        "" => Err("empty input".to_string()),
+        other => Err(format!(
+            "unknown or unsupported opcode '{}' (control-flow/call/import opcodes aren't supported in the line REPL)",
+            other
+        )),
+    }
+}
+
+/// `rustyline` helper providing completion/highlighting/validation for the
+/// bytecode REPL. Rebuilt with the latest global names after each executed
+/// line, whether or not it raised (see [`repl_bytecode`]), so newly defined
+/// variables/functions are immediately completable.
+struct ReplHelper {
+    globals: Vec<String>,
+}
+
+impl Completer for ReplHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let start = line[..pos].rfind(char::is_whitespace).map(|i| i + 1).unwrap_or(0);
+        let word = &line[start..pos];
+        let word_upper = word.to_uppercase();
+
+        let mut candidates: Vec<Pair> = OPCODES
+            .iter()
+            .filter(|op| op.starts_with(&word_upper))
+            .map(|op| Pair {
+                display: op.to_string(),
+                replacement: op.to_string(),
+            })
+            .collect();
+        candidates.extend(self.globals.iter().filter(|g| g.starts_with(word)).map(|g| Pair {
+            display: g.clone(),
+            replacement: g.clone(),
+        }));
+
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+
+    fn hint(&self, _line: &str, _pos: usize, _ctx: &Context<'_>) -> Option<String> {
+        None
+    }
+}
+
+impl Highlighter for ReplHelper {
+    /// Color the leading opcode mnemonic; operands are left unstyled. A full
+    /// tokenizing highlighter (recoloring string-literal operands, etc.)
+    /// isn't worth the complexity for a single-instruction-per-line input
+    /// model.
+    fn highlight<'l>(&self, line: &'l str, _pos: usize) -> std::borrow::Cow<'l, str> {
+        match line.split_whitespace().next() {
+            Some(op) if OPCODES.contains(&op.to_uppercase().as_str()) => {
+                std::borrow::Cow::Owned(format!("\x1b[36m{}\x1b[0m{}", op, &line[op.len()..]))
             }
+            _ => std::borrow::Cow::Borrowed(line),
         }
     }
 }
+
+impl Validator for ReplHelper {
+    /// Every line is exactly one instruction (no multi-line blocks here), so
+    /// input is complete the moment it's non-empty.
+    fn validate(&self, ctx: &mut ValidationContext) -> rustyline::Result<ValidationResult> {
+        if ctx.input().trim().is_empty() {
+            Ok(ValidationResult::Incomplete)
+        } else {
+            Ok(ValidationResult::Valid(None))
+        }
+    }
+}
+
+impl Helper for ReplHelper {}
+
+/// Resolve the dotfile path used to persist bytecode-REPL history across
+/// sessions. Returns `None` if `$HOME` isn't set; history then simply isn't
+/// persisted (this REPL is a debugging/exploration tool, not something that
+/// needs to fail hard over a missing home directory).
+fn bytecode_history_path() -> Option<std::path::PathBuf> {
+    std::env::var_os("HOME").map(|home| std::path::PathBuf::from(home).join(".omg_bytecode_history"))
+}
+
+/// Run an interactive REPL over raw bytecode mnemonics (`PUSH_INT 2`, `ADD`,
+/// `EMIT`, ...), executed directly against a persistent, in-process
+/// [`VmState`] — unlike [`repl_interpret`], which re-runs the embedded
+/// interpreter over the accumulated OMG *source* (via a temp file) per
+/// block, this mode reads no source text at all and keeps one machine state
+/// across lines.
+///
+/// The key property that makes this work is that `VmState::run_segment` is
+/// re-entrant over carried-forward `globals`/`module_cache` rather than
+/// building a fresh machine state per call: a `STORE x` typed on one line is
+/// visible to a `LOAD x` typed on the next, because both run against the
+/// same `VmState`.
+///
+/// Line editing, opcode/variable completion, mnemonic highlighting, and
+/// history persisted to `~/.omg_bytecode_history` are all provided by
+/// `rustyline` via [`ReplHelper`].
+pub fn repl_bytecode() {
+    println!("OMG Bytecode REPL - type opcode mnemonics, e.g. `PUSH_INT 2`, `EMIT`.");
+    println!("Type `exit` or `quit` to leave.");
+
+    let mut editor = Editor::<ReplHelper>::new().expect("failed to start line editor");
+    editor.set_helper(Some(ReplHelper { globals: Vec::new() }));
+    let history_path = bytecode_history_path();
+    if let Some(path) = &history_path {
+        let _ = editor.load_history(path);
+    }
+
+    let mut state = VmState::new(&[]);
+    let builtins = Builtins::standard();
+    let empty_funcs = HashMap::new();
+
+    loop {
+        match editor.readline(">>> ") {
+            Ok(line) => {
+                let trimmed = line.trim();
+                if trimmed.is_empty() {
+                    continue;
+                }
+                let _ = editor.add_history_entry(line.as_str());
+                if trimmed == "exit" || trimmed == "quit" {
+                    break;
+                }
+
+                match parse_instr(trimmed) {
+                    Ok(instr) => {
+                        let code = vec![instr, Instr::Halt];
+                        if let Err(traced) =
+                            state.run_segment(&code, &empty_funcs, &builtins, &mut std::io::stdout())
+                        {
+                            println!("{}", traced.error);
+                        }
+                        if let Some(helper) = editor.helper_mut() {
+                            helper.globals = state.global_names().cloned().collect();
+                        }
+                    }
+                    Err(msg) => println!("{}", msg),
+                }
+            }
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => {
+                println!("readline error: {}", e);
+                break;
+            }
+        }
+    }
+
+    if let Some(path) = &history_path {
+        let _ = editor.save_history(path);
+    }
+}
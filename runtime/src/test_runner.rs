@@ -0,0 +1,196 @@
+//! Section-based golden-test runner for bytecode programs.
+//!
+//! Invoked via `omg --test <fixtures.txt>` (see `main.rs`). A fixture file is
+//! a sequence of named records:
+//!
+//! ```text
+//! # [addition]
+//! ## program
+//! PUSH_INT 2
+//! PUSH_INT 3
+//! ADD
+//! EMIT
+//! ## args
+//! foo
+//! bar
+//! ## expected-output
+//! 5
+//! ```
+//!
+//! `## program` is required; `## args` is optional (one program argument per
+//! line, exposed to the record's program the same way CLI args are, via the
+//! `args` global). `## expected-output` is required and compared, with
+//! trailing whitespace dropped from each of its lines and trailing newlines
+//! dropped from both sides, against whatever `emit` would have printed,
+//! captured by running the
+//! record through [`crate::vm::run_with_sink`] with an in-memory buffer
+//! instead of real stdout.
+//!
+//! Each line inside `## program` is parsed with [`crate::repl::parse_instr`],
+//! the same one-instruction-per-line mnemonic parser the bytecode REPL uses —
+//! so, as there, control-flow/call/import opcodes aren't available and a
+//! record is necessarily a straight-line instruction sequence. That covers
+//! the arithmetic/builtin-call regression cases this format exists for;
+//! fixtures needing jumps or function calls should instead be precompiled
+//! `.omgb` files run by hand, as before.
+
+use std::collections::HashMap;
+use std::fs;
+
+use crate::repl::parse_instr;
+use crate::vm::run_with_sink;
+
+/// One `# [name]` record parsed out of a fixture file.
+struct Record {
+    name: String,
+    program: Vec<String>,
+    args: Vec<String>,
+    expected: String,
+}
+
+/// Split a fixture file into records at `# [name]` headers, then each
+/// record's body at `## program` / `## args` / `## expected-output`
+/// sub-headers.
+fn parse_fixture(text: &str) -> Result<Vec<Record>, String> {
+    let mut records = Vec::new();
+    let mut name: Option<String> = None;
+    let mut program = Vec::new();
+    let mut args = Vec::new();
+    let mut expected = Vec::new();
+    let mut section = "";
+
+    let flush = |name: &Option<String>,
+                 program: &mut Vec<String>,
+                 args: &mut Vec<String>,
+                 expected: &mut Vec<String>,
+                 records: &mut Vec<Record>| {
+        if let Some(name) = name {
+            records.push(Record {
+                name: name.clone(),
+                program: std::mem::take(program),
+                args: std::mem::take(args),
+                expected: expected.join("\n"),
+            });
+        }
+        expected.clear();
+    };
+
+    for raw_line in text.lines() {
+        let line = raw_line.trim_end();
+        if let Some(rest) = line.trim_start().strip_prefix("# [") {
+            let rest = rest.trim_end();
+            let header = rest
+                .strip_suffix(']')
+                .ok_or_else(|| format!("malformed section header: '{}'", line))?;
+            flush(&name, &mut program, &mut args, &mut expected, &mut records);
+            name = Some(header.to_string());
+            section = "";
+            continue;
+        }
+        match line.trim_start() {
+            "## program" => section = "program",
+            "## args" => section = "args",
+            "## expected-output" => section = "expected-output",
+            _ => match section {
+                "program" if !line.trim().is_empty() => program.push(line.trim().to_string()),
+                "args" if !line.trim().is_empty() => args.push(line.trim().to_string()),
+                "expected-output" => expected.push(line.to_string()),
+                _ => {}
+            },
+        }
+    }
+    flush(&name, &mut program, &mut args, &mut expected, &mut records);
+
+    Ok(records)
+}
+
+/// Run every record in `fixture_path`, printing a pass/fail line per record
+/// and a line-by-line diff (see [`diff_lines`]) for failures. Returns `false` if any record failed (so
+/// `main.rs` can set a non-zero exit code).
+pub fn run_fixture_file(fixture_path: &str) -> bool {
+    let text = match fs::read_to_string(fixture_path) {
+        Ok(t) => t,
+        Err(e) => {
+            eprintln!("failed to read fixture file '{}': {}", fixture_path, e);
+            return false;
+        }
+    };
+
+    let records = match parse_fixture(&text) {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("failed to parse fixture file '{}': {}", fixture_path, e);
+            return false;
+        }
+    };
+
+    let mut all_passed = true;
+    for record in &records {
+        let mut code = Vec::with_capacity(record.program.len());
+        let mut parse_err = None;
+        for line in &record.program {
+            match parse_instr(line) {
+                Ok(instr) => code.push(instr),
+                Err(e) => {
+                    parse_err = Some(e);
+                    break;
+                }
+            }
+        }
+
+        if let Some(e) = parse_err {
+            all_passed = false;
+            println!("FAIL {}: {}", record.name, e);
+            continue;
+        }
+
+        let funcs = HashMap::new();
+        let args = record.args.clone();
+        let mut buf: Vec<u8> = Vec::new();
+        if let Err(e) = run_with_sink(&code, &funcs, &args, &mut buf) {
+            all_passed = false;
+            println!("FAIL {}: program raised {}", record.name, e);
+            continue;
+        }
+        let actual = String::from_utf8_lossy(&buf);
+
+        let actual = actual.trim_end_matches('\n');
+        let expected = record.expected.trim_end_matches('\n');
+        if actual == expected {
+            println!("PASS {}", record.name);
+        } else {
+            all_passed = false;
+            println!("FAIL {}:", record.name);
+            for diff_line in diff_lines(expected, actual) {
+                println!("  {}", diff_line);
+            }
+        }
+    }
+
+    all_passed
+}
+
+/// Minimal line-oriented diff: prefix expected-only lines with `-`,
+/// actual-only lines with `+`, matching lines with a space. Good enough for
+/// short golden-test outputs; not a general LCS diff.
+fn diff_lines(expected: &str, actual: &str) -> Vec<String> {
+    let expected_lines: Vec<&str> = expected.lines().collect();
+    let actual_lines: Vec<&str> = actual.lines().collect();
+    let max = expected_lines.len().max(actual_lines.len());
+    let mut out = Vec::with_capacity(max);
+    for i in 0..max {
+        let e = expected_lines.get(i).copied();
+        let a = actual_lines.get(i).copied();
+        match (e, a) {
+            (Some(e), Some(a)) if e == a => out.push(format!("  {}", e)),
+            (Some(e), Some(a)) => {
+                out.push(format!("- {}", e));
+                out.push(format!("+ {}", a));
+            }
+            (Some(e), None) => out.push(format!("- {}", e)),
+            (None, Some(a)) => out.push(format!("+ {}", a)),
+            (None, None) => {}
+        }
+    }
+    out
+}
@@ -6,45 +6,128 @@
 //!
 //! ## Supported types
 //! - `Int(i64)` – 64-bit signed integer
+//! - `Float(f64)` – 64-bit floating-point number
 //! - `Str(String)` – UTF-8 string
 //! - `Bool(bool)` – boolean truth values
 //! - `List(Rc<RefCell<Vec<Value>>>)` – mutable, reference-counted lists
-//! - `Dict(Rc<RefCell<HashMap<String, Value>>>)` – mutable, reference-counted dictionaries
-//! - `FrozenDict(Rc<HashMap<String, Value>>)` – immutable dictionaries (used for imports)
+//! - `Dict(Rc<RefCell<HashMap<DictKey, Value>>>)` – mutable, reference-counted dictionaries
+//! - `FrozenDict(Rc<HashMap<DictKey, Value>>)` – immutable dictionaries (used for imports)
 //! - `None` – sentinel for “no value” (similar to Python’s `None` / JS’s `undefined`)
 //!
 //! ## Design
 //! - `Rc<RefCell<...>>` enables multiple references to a collection while allowing
 //!   safe mutation when borrowed mutably at runtime.
 //! - `FrozenDict` ensures that imported namespaces and constants remain immutable.
-//! - Convenience methods (`as_int`, `as_bool`, `to_string`) provide coercion rules
-//!   consistent with OMG’s dynamic typing.
+//! - `List`/`Dict` should be constructed via [`Value::new_list`]/[`Value::new_dict`]
+//!   rather than building the `Rc` by hand, so the [`crate::gc`] collector knows
+//!   about the allocation and can reclaim it if it ends up in a reference cycle.
+//! - Convenience methods (`as_int`, `as_float`, `as_bool`, `to_string`) provide
+//!   coercion rules consistent with OMG’s dynamic typing.
+//!
+//! ## Dict keys
+//! Dict keys are [`DictKey`], not `Value`: only `Int`, `Str`, and `Bool` are
+//! hashable, so `dict[1]` and `dict["1"]` land in distinct slots instead of
+//! both stringifying to `"1"`. [`DictKey::from_value`] converts an index/key
+//! operand, rejecting anything else (`Float`, `List`, `Dict`, `FrozenDict`,
+//! `None`) with a `RuntimeError::TypeError`. Attribute access (`obj.key`)
+//! always maps to `DictKey::Str(key)`.
 //!
 //! ## Coercion rules
 //! - **Integer conversion (`as_int`)**:
 //!   - `Int` → itself
+//!   - `Float` → truncated toward zero; NaN/∞ is a `ValueError` (there is no
+//!     finite `i64` to truncate to)
 //!   - `Str` → parse as integer or error
 //!   - `Bool` → true → 1, false → 0
 //!   - `List`/`Dict`/`FrozenDict` → length
 //!   - `None` → 0
+//! - **Float conversion (`as_float`)**:
+//!   - `Float` → itself
+//!   - `Int` → widened losslessly
+//!   - everything else → `as_int()`, then widened
 //! - **Boolean conversion (`as_bool`)**:
-//!   - Falsy: `false`, `0`, `""`, `[]`, `{}`, `None`
+//!   - Falsy: `false`, `0`, `0.0`/`-0.0`, NaN, `""`, `[]`, `{}`, `None`
 //!   - Truthy: everything else
 //! - **String conversion (`to_string`)**:
 //!   - Provides human-readable representations, with recursion detection
 //!     (`[...]`, `{...}`) to prevent infinite loops on cyclic structures.
+//!   - `Float` always keeps a decimal point (e.g. `1.0`, not `1`) so floats
+//!     remain visually distinct from integers.
+//!
+//! ## Equality
+//! `Value` has no derived `PartialEq` (lists/dicts are `Rc<RefCell<_>>`, and
+//! there's no single sensible notion of dict-key equality across variants).
+//! Instead, [`Value::eq`] defines the comparison the VM needs: `Int`/`Float`
+//! compare numerically (mixed `Int`/`Float` promotes the `Int` side), and all
+//! other pairings compare by variant and contents.
 
 use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 
 use crate::error::RuntimeError;
+use crate::gc;
+
+/// A hashable dict key: `Value` itself can't derive `Hash`/`Eq` (lists/dicts
+/// are interior-mutable `Rc<RefCell<_>>`, and floats aren't `Eq`), so dicts
+/// key on this narrower type instead. Only `Int`, `Str`, and `Bool` values
+/// are convertible via [`DictKey::from_value`] — everything else is rejected
+/// as unhashable.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum DictKey {
+    /// 64-bit signed integer key.
+    Int(i64),
+    /// String key.
+    Str(String),
+    /// Boolean key.
+    Bool(bool),
+}
+
+impl DictKey {
+    /// Convert a `Value` used as a dict key or index operand into a
+    /// `DictKey`, or a `RuntimeError::TypeError` if `value` isn't hashable
+    /// (`Float`, `List`, `Dict`, `FrozenDict`, and `None` all fall here).
+    pub fn from_value(value: &Value) -> Result<DictKey, RuntimeError> {
+        match value {
+            Value::Int(i) => Ok(DictKey::Int(*i)),
+            Value::Str(s) => Ok(DictKey::Str(s.clone())),
+            Value::Bool(b) => Ok(DictKey::Bool(*b)),
+            other => Err(RuntimeError::TypeError(format!(
+                "{} is not a valid dict key",
+                other.to_string()
+            ))),
+        }
+    }
+
+    /// Convert the key back into the `Value` it was built from (used by
+    /// `keys()` to return each key in its natural type rather than always
+    /// stringifying it).
+    pub fn to_value(&self) -> Value {
+        match self {
+            DictKey::Int(i) => Value::Int(*i),
+            DictKey::Str(s) => Value::Str(s.clone()),
+            DictKey::Bool(b) => Value::Bool(*b),
+        }
+    }
+
+    /// Human-readable form, used when printing a dict and in `KeyError`
+    /// messages. Mirrors `Value::to_string`'s non-trait convention.
+    pub fn to_string(&self) -> String {
+        match self {
+            DictKey::Int(i) => i.to_string(),
+            DictKey::Str(s) => s.clone(),
+            DictKey::Bool(b) => b.to_string(),
+        }
+    }
+}
 
 /// Value type for the VM stack and environments.
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub enum Value {
     /// 64-bit signed integer.
     Int(i64),
+    /// 64-bit floating-point number.
+    Float(f64),
     /// UTF-8 string.
     Str(String),
     /// Boolean truth value.
@@ -52,20 +135,52 @@ pub enum Value {
     /// Mutable list (reference-counted, interior-mutable).
     List(Rc<RefCell<Vec<Value>>>),
     /// Mutable dictionary (reference-counted, interior-mutable).
-    Dict(Rc<RefCell<HashMap<String, Value>>>),
+    Dict(Rc<RefCell<HashMap<DictKey, Value>>>),
     /// Immutable dictionary (reference-counted).
-    FrozenDict(Rc<HashMap<String, Value>>),
+    FrozenDict(Rc<HashMap<DictKey, Value>>),
     /// Sentinel for “no value”.
     None,
 }
 
 impl Value {
+    /// Allocate a new mutable list, registering it with the [`gc`] heap so
+    /// `gc.collect()` can find and reclaim it if it ends up in a cycle.
+    ///
+    /// Prefer this over constructing `Value::List(Rc::new(RefCell::new(..)))`
+    /// directly — untracked allocations are invisible to the collector.
+    pub fn new_list(items: Vec<Value>) -> Value {
+        let rc = Rc::new(RefCell::new(items));
+        gc::register_list(&rc);
+        Value::List(rc)
+    }
+
+    /// Allocate a new mutable dict, registering it with the [`gc`] heap so
+    /// `gc.collect()` can find and reclaim it if it ends up in a cycle.
+    ///
+    /// Prefer this over constructing `Value::Dict(Rc::new(RefCell::new(..)))`
+    /// directly — untracked allocations are invisible to the collector.
+    pub fn new_dict(map: HashMap<DictKey, Value>) -> Value {
+        let rc = Rc::new(RefCell::new(map));
+        gc::register_dict(&rc);
+        Value::Dict(rc)
+    }
+
     /// Convert the value into an integer, applying OMG coercion rules.
     ///
     /// Returns `Ok(i64)` on success, or a [`RuntimeError::TypeError`] if conversion fails.
     pub fn as_int(&self) -> Result<i64, RuntimeError> {
         match self {
             Value::Int(i) => Ok(*i),
+            Value::Float(f) => {
+                if f.is_nan() || f.is_infinite() {
+                    Err(RuntimeError::ValueError(format!(
+                        "cannot convert {} to int",
+                        f
+                    )))
+                } else {
+                    Ok(*f as i64)
+                }
+            }
             Value::Str(s) => s.parse::<i64>().map_err(|_| {
                 RuntimeError::TypeError(format!("Invalid literal for int(): '{}'", s))
             }),
@@ -77,6 +192,19 @@ impl Value {
         }
     }
 
+    /// Convert the value into a 64-bit float, applying OMG coercion rules.
+    ///
+    /// `Int` promotes losslessly (within `f64`'s 53-bit mantissa); everything
+    /// else defers to [`Value::as_int`] and then widens the result. This is
+    /// the coercion arithmetic handlers use once either operand is a `Float`.
+    pub fn as_float(&self) -> Result<f64, RuntimeError> {
+        match self {
+            Value::Float(f) => Ok(*f),
+            Value::Int(i) => Ok(*i as f64),
+            other => Ok(other.as_int()? as f64),
+        }
+    }
+
     /// Convert the value into a boolean (truthiness semantics).
     ///
     /// - Falsy: `false`, `0`, `""`, `[]`, `{}`, `None`
@@ -85,6 +213,7 @@ impl Value {
         match self {
             Value::Bool(b) => *b,
             Value::Int(i) => *i != 0,
+            Value::Float(f) => *f != 0.0 && !f.is_nan(),
             Value::Str(s) => !s.is_empty(),
             Value::List(l) => !l.borrow().is_empty(),
             Value::Dict(d) => !d.borrow().is_empty(),
@@ -105,6 +234,15 @@ impl Value {
         fn helper(val: &Value, seen: &mut HashSet<usize>) -> String {
             match val {
                 Value::Int(i) => i.to_string(),
+                // Always keep a decimal point so floats stay visually
+                // distinct from ints (`1.0`, not `1`).
+                Value::Float(f) => {
+                    if f.fract() == 0.0 && f.is_finite() {
+                        format!("{:.1}", f)
+                    } else {
+                        f.to_string()
+                    }
+                }
                 Value::Str(s) => s.clone(),
                 Value::Bool(b) => b.to_string(),
 
@@ -128,7 +266,7 @@ impl Value {
                     let inner: Vec<String> = map
                         .borrow()
                         .iter()
-                        .map(|(k, v)| format!("{}: {}", k, helper(v, seen)))
+                        .map(|(k, v)| format!("{}: {}", k.to_string(), helper(v, seen)))
                         .collect();
                     format!("{{{}}}", inner.join(", "))
                 }
@@ -141,7 +279,7 @@ impl Value {
                     }
                     let inner: Vec<String> = map
                         .iter()
-                        .map(|(k, v)| format!("{}: {}", k, helper(v, seen)))
+                        .map(|(k, v)| format!("{}: {}", k.to_string(), helper(v, seen)))
                         .collect();
                     format!("{{{}}}", inner.join(", "))
                 }
@@ -155,3 +293,24 @@ impl Value {
         helper(self, &mut seen)
     }
 }
+
+impl PartialEq for Value {
+    /// Structural equality, with numeric promotion for mixed `Int`/`Float`
+    /// comparisons so `1 == 1.0` holds as it does in most dynamic languages.
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Int(a), Value::Int(b)) => a == b,
+            (Value::Float(a), Value::Float(b)) => a == b,
+            (Value::Int(a), Value::Float(b)) | (Value::Float(b), Value::Int(a)) => {
+                *a as f64 == *b
+            }
+            (Value::Str(a), Value::Str(b)) => a == b,
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::List(a), Value::List(b)) => *a.borrow() == *b.borrow(),
+            (Value::Dict(a), Value::Dict(b)) => *a.borrow() == *b.borrow(),
+            (Value::FrozenDict(a), Value::FrozenDict(b)) => *a == *b,
+            (Value::None, Value::None) => true,
+            _ => false,
+        }
+    }
+}
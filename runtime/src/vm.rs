@@ -11,6 +11,8 @@
 //! - **Return stack (`ret_stack`)**: return program counters for calls.
 //! - **Block stack (`block_stack`)**: exception-handling frames capturing
 //!   handler location and stack/env depths for unwinding.
+//! - **Call frames (`call_frames`)**: parallel to `env_stack`/`ret_stack`,
+//!   records `(callee name, call-site pc)` for traceback reporting.
 //! - **Program counter (`pc`)**: index into `code` (the instruction stream).
 //! - **Advance flag (`advance_pc`)**: lets control-flow ops manage the PC.
 //!
@@ -22,21 +24,124 @@
 //!
 //! The machine is deterministic and “fails fast”: any instruction error sets
 //! `error_flag`, triggers block unwinding if a handler is present, or terminates
-//! with a `RuntimeError` if unhandled.
+//! with a `RuntimeError` if unhandled. [`run_traced`] additionally reports the
+//! call-stack traceback active when the error was raised; [`run`] and
+//! [`run_with_interrupt`] discard it for callers that only need the error.
+//!
+//! [`run`] and friends below are all-or-nothing entry points. For a host
+//! that needs to pause and resume a program (cooperative coroutines, an
+//! `Instr::Yield` pause point), see [`step::Vm`] instead, which holds the
+//! same machine state behind a struct rather than on the Rust call stack.
 
-use std::cell::RefCell;
 use std::collections::HashMap;
+use std::io::Write;
 use std::path::PathBuf;
 use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 use crate::bytecode::{Function, Instr};
-use crate::error::RuntimeError;
-use crate::value::Value;
+use crate::error::{ErrorKind, Frame, RuntimeError, TracedError};
+use crate::gc;
+use crate::value::{DictKey, Value};
 
 mod builtins;
 mod ops_arith;
 mod ops_control;
 mod ops_struct;
+mod step;
+mod trace;
+
+pub use builtins::Builtins;
+pub use step::{StepResult, Vm};
+pub use trace::{TraceEvent, Tracer};
+
+/// Default maximum number of nested call frames (`env_stack` depth) before
+/// the VM reports a [`RuntimeError::RecursionError`] instead of growing the
+/// frame stacks without bound.
+pub(super) const MAX_CALL_DEPTH: usize = 10_000;
+
+/// Number of instructions executed between checks of the interrupt flag.
+///
+/// Checking every instruction would add overhead to the hot loop for no
+/// practical benefit; checking periodically keeps cancellation latency low
+/// while staying cheap.
+const INTERRUPT_CHECK_INTERVAL: u64 = 1024;
+
+/// Number of instructions executed between checks of a [`Budget`]'s wall-clock
+/// timeout. `Instant::now()` is cheap but not free; like
+/// [`INTERRUPT_CHECK_INTERVAL`], this trades a little cancellation latency for
+/// a cheaper hot loop.
+const TIMEOUT_CHECK_INTERVAL: u64 = 4096;
+
+/// Optional execution limits enforced by the VM loop: a deterministic
+/// instruction-count budget ("fuel") and/or a wall-clock timeout.
+///
+/// Fuel is decremented once per dispatched instruction; reaching zero raises
+/// [`RuntimeError::FuelExhausted`]. The timeout is checked every
+/// [`TIMEOUT_CHECK_INTERVAL`] instructions (not every instruction, to keep the
+/// check cheap); exceeding it raises [`RuntimeError::Timeout`]. `max_call_depth`
+/// overrides the default [`MAX_CALL_DEPTH`] ceiling on nested call frames,
+/// raising [`RuntimeError::RecursionError`] once exceeded. Any of the three
+/// limits may be set independently; `None` falls back to that limit's default
+/// (no limit, for fuel/timeout; [`MAX_CALL_DEPTH`], for call depth).
+///
+/// Passed as `&mut Budget` (rather than by value) so the caller can read back
+/// [`Budget::fuel_used`] once the run returns — both limit inputs and the
+/// fuel-used output live on the same struct, mirroring how `EvalResult`
+/// reports `fuel_used` to a WASM caller.
+#[derive(Debug, Default, Clone)]
+pub struct Budget {
+    pub fuel: Option<u64>,
+    pub timeout: Option<std::time::Duration>,
+    /// Overrides [`MAX_CALL_DEPTH`] for this run; `None` keeps the default.
+    pub max_call_depth: Option<usize>,
+    fuel_used: u64,
+}
+
+impl Budget {
+    /// A budget with no limits set (equivalent to passing `None`, but usable
+    /// when the caller wants `fuel_used` reported back regardless).
+    pub fn new() -> Self {
+        Budget::default()
+    }
+
+    /// A budget with only an instruction-count limit.
+    pub fn with_fuel(fuel: u64) -> Self {
+        Budget { fuel: Some(fuel), ..Budget::default() }
+    }
+
+    /// A budget with only a wall-clock timeout.
+    pub fn with_timeout(timeout: std::time::Duration) -> Self {
+        Budget { timeout: Some(timeout), ..Budget::default() }
+    }
+
+    /// A budget with only a call-depth limit, overriding [`MAX_CALL_DEPTH`].
+    pub fn with_max_call_depth(max_call_depth: usize) -> Self {
+        Budget { max_call_depth: Some(max_call_depth), ..Budget::default() }
+    }
+
+    /// Instructions actually dispatched by the most recent run this budget
+    /// was passed to (zero until then).
+    pub fn fuel_used(&self) -> u64 {
+        self.fuel_used
+    }
+
+    /// Fuel left over after the most recent run, for a caller time-slicing a
+    /// program across successive [`run_with_budget`]/[`run_traced`] calls
+    /// (e.g. "run until out of fuel, hand the remainder to the next turn").
+    /// `None` if no `fuel` limit was set — there's nothing to meter.
+    pub fn remaining_fuel(&self) -> Option<u64> {
+        self.fuel.map(|f| f.saturating_sub(self.fuel_used))
+    }
+}
+
+/// Number of list/dict allocations between automatic [`gc::collect`] passes.
+///
+/// Collection is `O(reachable heap)`, so triggering it by allocation count
+/// (rather than every instruction) keeps the amortized per-allocation cost
+/// low while still bounding how much cyclic garbage can pile up.
+const GC_ALLOC_THRESHOLD: u64 = 10_000;
 
 /// # Exception-handling metadata for a protected region.
 ///
@@ -50,11 +155,22 @@ mod ops_struct;
 /// - `stack_size`: operand stack height to restore on unwind
 /// - `env_depth`: number of local env frames to keep (truncate above this)
 /// - `ret_depth`: number of return addresses to keep (truncate above this)
+/// - `filter`: `ErrorKind`s this handler catches; an empty list catches any
+///   kind (the original catch-all behavior)
 pub(super) struct Block {
     handler: usize,
     stack_size: usize,
     env_depth: usize,
     ret_depth: usize,
+    filter: Vec<ErrorKind>,
+}
+
+impl Block {
+    /// Whether this block's filter matches the given error kind.
+    /// An empty filter catches everything.
+    pub(super) fn catches(&self, kind: ErrorKind) -> bool {
+        self.filter.is_empty() || self.filter.contains(&kind)
+    }
 }
 
 /// Pop a single [`Value`] from the operand stack.
@@ -69,7 +185,8 @@ pub(super) fn pop(stack: &mut Vec<Value>) -> Result<Value, RuntimeError> {
         .ok_or_else(|| RuntimeError::VmInvariant("stack underflow".to_string()))
 }
 
-/// Execute bytecode on a stack-based virtual machine.
+/// Execute bytecode on a stack-based virtual machine, writing `emit` output
+/// to real stdout.
 ///
 /// # Parameters
 /// - `code`: the linear bytecode stream to execute
@@ -90,26 +207,204 @@ pub(super) fn pop(stack: &mut Vec<Value>) -> Result<Value, RuntimeError> {
 /// pushes the error message string to the operand stack for the handler to
 /// consume, and resumes at the handler `pc`. Without a handler, the error ends
 /// execution immediately.
+///
+/// Use [`run_with_sink`] to redirect `emit` output elsewhere (a pipeline, an
+/// in-memory buffer, an embedder's own writer).
 pub fn run(
     code: &[Instr],
     funcs: &HashMap<String, Function>,
     program_args: &[String],
 ) -> Result<(), RuntimeError> {
-    // Operand/value stack. All computation flows through here.
-    let mut stack: Vec<Value> = Vec::new();
+    run_with_sink(code, funcs, program_args, &mut std::io::stdout())
+}
+
+/// Same as [`run`], but writes `emit` output to the caller-supplied `sink`
+/// instead of stdout — the extension point that lets an omglang program be
+/// driven as a filter (piped stdin/stdout) or embedded where stdout isn't
+/// the right destination.
+///
+/// `read_line()` still reads real stdin directly, exactly as it always has.
+/// Use [`run_with_input`] for the extension point that lets an embedder
+/// (e.g. the wasm bindings) supply input from somewhere other than stdin.
+pub fn run_with_sink(
+    code: &[Instr],
+    funcs: &HashMap<String, Function>,
+    program_args: &[String],
+    sink: &mut dyn Write,
+) -> Result<(), RuntimeError> {
+    run_with_interrupt(code, funcs, program_args, None, sink).map_err(|traced| traced.error)
+}
+
+/// Same as [`run_with_sink`], but additionally accepts an `input` provider:
+/// a closure called once per `read_line()`, returning `Some(line)` for the
+/// next line or `None` when exhausted (mapped to `read_line()`'s existing
+/// EOF value, `Value::None`). `None` for `input` itself (no provider at
+/// all, as every other `run_*` entry point passes) falls back to the
+/// ordinary `read_line` builtin, which reads real stdin — the extension
+/// point the wasm bindings use to bridge a JS-supplied input callback,
+/// since wasm has no real stdin to read.
+pub fn run_with_input(
+    code: &[Instr],
+    funcs: &HashMap<String, Function>,
+    program_args: &[String],
+    sink: &mut dyn Write,
+    input: Option<&mut dyn FnMut() -> Option<String>>,
+) -> Result<(), RuntimeError> {
+    let mut globals = bootstrap_globals(program_args);
+    let mut module_cache = HashMap::new();
+    run_inner(
+        code,
+        funcs,
+        &mut globals,
+        &mut module_cache,
+        None,
+        None,
+        &Builtins::standard(),
+        sink,
+        input,
+        None,
+    )
+    .map_err(|traced| traced.error)
+}
+
+/// Same as [`run_with_sink`], but accepts a cooperative interrupt flag.
+///
+/// `interrupt`, if provided, is polled every [`INTERRUPT_CHECK_INTERVAL`]
+/// instructions. The embedder (a CLI installing a Ctrl-C handler, a host
+/// application with its own cancellation button, etc.) sets the flag from
+/// any thread; the VM observes it between instructions and unwinds via
+/// `RuntimeError::Interrupted`, routed through the normal `SetupExcept`
+/// machinery so user code can catch it like any other exception.
+pub fn run_with_interrupt(
+    code: &[Instr],
+    funcs: &HashMap<String, Function>,
+    program_args: &[String],
+    interrupt: Option<Arc<AtomicBool>>,
+    sink: &mut dyn Write,
+) -> Result<(), RuntimeError> {
+    run_traced(code, funcs, program_args, interrupt, None, sink).map_err(|traced| traced.error)
+}
+
+/// Same as [`run_with_interrupt`], but additionally accepts a [`Budget`]
+/// (instruction-count fuel and/or a wall-clock timeout). `budget` is updated
+/// in place with `fuel_used` once the run returns (whether it finished,
+/// errored, or ran out of budget).
+pub fn run_with_budget(
+    code: &[Instr],
+    funcs: &HashMap<String, Function>,
+    program_args: &[String],
+    interrupt: Option<Arc<AtomicBool>>,
+    budget: Option<&mut Budget>,
+    sink: &mut dyn Write,
+) -> Result<(), RuntimeError> {
+    run_traced(code, funcs, program_args, interrupt, budget, sink).map_err(|traced| traced.error)
+}
+
+/// Same as [`run_with_interrupt`], but on an unhandled error returns a
+/// [`TracedError`] carrying the call-stack traceback active at raise time,
+/// for Python/Java-style top-level error reporting (e.g. the CLI and REPL).
+///
+/// Uses the standard builtin registry ([`Builtins::standard`]); to add or
+/// override builtins, use [`run_with_builtins`] instead.
+pub fn run_traced(
+    code: &[Instr],
+    funcs: &HashMap<String, Function>,
+    program_args: &[String],
+    interrupt: Option<Arc<AtomicBool>>,
+    budget: Option<&mut Budget>,
+    sink: &mut dyn Write,
+) -> Result<(), TracedError> {
+    let mut globals = bootstrap_globals(program_args);
+    let mut module_cache = HashMap::new();
+    run_inner(
+        code,
+        funcs,
+        &mut globals,
+        &mut module_cache,
+        interrupt,
+        budget,
+        &Builtins::standard(),
+        sink,
+        None,
+        None,
+    )
+}
+
+/// Same as [`run_traced`], but dispatches `CallBuiltin` through a
+/// caller-supplied [`Builtins`] registry instead of the standard one.
+///
+/// This is the extension point for embedders: build a `Builtins`, register
+/// host functions on it (or override standard ones), and drive the VM with
+/// it instead of the standard library.
+pub fn run_with_builtins(
+    code: &[Instr],
+    funcs: &HashMap<String, Function>,
+    program_args: &[String],
+    interrupt: Option<Arc<AtomicBool>>,
+    budget: Option<&mut Budget>,
+    builtins: &Builtins,
+    sink: &mut dyn Write,
+) -> Result<(), TracedError> {
+    let mut globals = bootstrap_globals(program_args);
+    let mut module_cache = HashMap::new();
+    run_inner(
+        code,
+        funcs,
+        &mut globals,
+        &mut module_cache,
+        interrupt,
+        budget,
+        builtins,
+        sink,
+        None,
+        None,
+    )
+}
+
+/// Same as [`run_with_sink`], but drives `tracer` with a [`TraceEvent`] once
+/// per dispatched instruction, before it executes — the extension point for
+/// debugging the exact sequence leading to a `VmInvariant`/`Raised` without
+/// recompiling. Every other `run_*` entry point passes `None` for the
+/// tracer, so the hot loop's only added cost there is a single
+/// `Option::as_deref_mut()` check per instruction.
+pub fn run_with_tracer(
+    code: &[Instr],
+    funcs: &HashMap<String, Function>,
+    program_args: &[String],
+    sink: &mut dyn Write,
+    tracer: &mut dyn Tracer,
+) -> Result<(), RuntimeError> {
+    let mut globals = bootstrap_globals(program_args);
+    let mut module_cache = HashMap::new();
+    run_inner(
+        code,
+        funcs,
+        &mut globals,
+        &mut module_cache,
+        None,
+        None,
+        &Builtins::standard(),
+        sink,
+        None,
+        Some(tracer),
+    )
+    .map_err(|traced| traced.error)
+}
 
-    // Global variables are visible to all frames. Locals live in `env`.
+/// Build the initial `globals` for a fresh top-level run: `args` (program
+/// arguments as a list), and `module_file`/`current_dir` derived from the
+/// first argument (or `<stdin>`/`.` for REPL-like execution with none).
+///
+/// Shared by every entry point that starts a *new* top-level program
+/// ([`run_traced`], [`run_with_builtins`], [`import_module`]); [`VmState`]
+/// calls it once at construction and then threads the same `globals` through
+/// every subsequent [`VmState::run_segment`] call instead of rebuilding it.
+fn bootstrap_globals(program_args: &[String]) -> HashMap<String, Value> {
     let mut globals: HashMap<String, Value> = HashMap::new();
 
-    // Expose command line arguments to programs as a list in `globals["args"]`.
     let arg_values: Vec<Value> = program_args.iter().map(|s| Value::Str(s.clone())).collect();
-    globals.insert(
-        "args".to_string(),
-        Value::List(Rc::new(RefCell::new(arg_values))),
-    );
+    globals.insert("args".to_string(), Value::new_list(arg_values));
 
-    // Derive `module_file` and `current_dir` from the first argument if present,
-    // else assume REPL-like execution.
     if let Some(first) = program_args.first() {
         let path = PathBuf::from(first.replace("\\", "/"));
         globals.insert(
@@ -129,6 +424,165 @@ pub fn run(
         globals.insert("current_dir".to_string(), Value::Str(".".to_string()));
     }
 
+    globals
+}
+
+/// Persistent VM state carried across REPL evaluations.
+///
+/// A one-shot [`run_traced`]/[`run_with_builtins`] call builds a fresh
+/// `globals`/`module_cache` for a single top-level program and discards them
+/// on return. A REPL instead wants one snippet's `STORE x` to be visible to
+/// the next snippet's `LOAD x` — i.e. `run` needs to be re-entrant over a
+/// carried-forward machine state rather than starting from scratch each
+/// call. `VmState` holds exactly the two pieces of state that *should*
+/// persist across snippets (globals and the module import cache); the
+/// operand stack, local env, and call frames are intentionally rebuilt fresh
+/// per [`run_segment`](VmState::run_segment) call, since each snippet is its
+/// own self-contained top-level program, not a continuation of the previous
+/// one's control flow.
+pub struct VmState {
+    globals: HashMap<String, Value>,
+    module_cache: HashMap<String, Value>,
+}
+
+impl VmState {
+    /// Start a new persistent session, bootstrapping `globals` exactly as a
+    /// one-shot run would (see [`bootstrap_globals`]).
+    pub fn new(program_args: &[String]) -> Self {
+        VmState {
+            globals: bootstrap_globals(program_args),
+            module_cache: HashMap::new(),
+        }
+    }
+
+    /// Run one snippet of bytecode against this session's carried-forward
+    /// `globals`/`module_cache`. Takes `builtins` and `sink` per call (rather
+    /// than fixing them at construction) so a host can swap in custom
+    /// builtins or redirect `emit` output between snippets if needed.
+    pub fn run_segment(
+        &mut self,
+        code: &[Instr],
+        funcs: &HashMap<String, Function>,
+        builtins: &Builtins,
+        sink: &mut dyn Write,
+    ) -> Result<(), TracedError> {
+        run_inner(
+            code,
+            funcs,
+            &mut self.globals,
+            &mut self.module_cache,
+            None,
+            None,
+            builtins,
+            sink,
+            None,
+            None,
+        )
+    }
+
+    /// Names currently bound in this session's globals (functions and plain
+    /// variables alike), for REPL completion/introspection.
+    pub fn global_names(&self) -> impl Iterator<Item = &String> {
+        self.globals.keys()
+    }
+}
+
+/// Resolve a module path referenced by `Instr::Import` against the
+/// importing file's directory, matching `current_dir`'s own `/`-normalized
+/// convention. Absolute paths are returned unchanged.
+fn resolve_module_path(path: &str, current_dir: &str) -> String {
+    let path = path.replace('\\', "/");
+    if PathBuf::from(&path).is_absolute() {
+        path
+    } else {
+        format!("{}/{}", current_dir, path)
+    }
+}
+
+/// Load and run the module at `path`, returning a `Value::FrozenDict`
+/// namespace of its exported bindings (cached by resolved path so repeated
+/// imports of the same file only execute it once).
+///
+/// Top-level globals become namespace entries directly; top-level functions
+/// are exposed as `Value::Str` names qualified with the module's resolved
+/// path (`"<path>::<func>"`) for introspection (`type`, printing, dict
+/// iteration). Invoking an imported function through `CALL_VALUE` is not
+/// yet supported: the module runs in its own isolated code/func address
+/// space, and bridging that into the importer's requires unifying the two
+/// instruction streams (a linking pass), which is out of scope here.
+fn import_module(
+    path: &str,
+    current_dir: &str,
+    module_cache: &mut HashMap<String, Value>,
+    builtins: &Builtins,
+    sink: &mut dyn Write,
+) -> Result<Value, RuntimeError> {
+    let resolved = resolve_module_path(path, current_dir);
+    if let Some(cached) = module_cache.get(&resolved) {
+        return Ok(cached.clone());
+    }
+
+    let bytes = std::fs::read(&resolved).map_err(|e| {
+        RuntimeError::ModuleImportError(format!("cannot read module '{}': {}", resolved, e))
+    })?;
+    let (mod_code, mod_funcs) = crate::bytecode::parse_bytecode(&bytes);
+    let mod_args = vec![resolved.clone()];
+    let mut mod_globals = bootstrap_globals(&mod_args);
+    let mut mod_module_cache = HashMap::new();
+    run_inner(
+        &mod_code,
+        &mod_funcs,
+        &mut mod_globals,
+        &mut mod_module_cache,
+        None,
+        None,
+        builtins,
+        sink,
+        None,
+        None,
+    )
+    .map_err(|traced| traced.error)?;
+
+    let mut exports: HashMap<DictKey, Value> = HashMap::new();
+    for (name, value) in mod_globals {
+        if matches!(name.as_str(), "args" | "module_file" | "current_dir") {
+            continue;
+        }
+        exports.insert(DictKey::Str(name), value);
+    }
+    for name in mod_funcs.keys() {
+        exports.insert(
+            DictKey::Str(name.clone()),
+            Value::Str(format!("{}::{}", resolved, name)),
+        );
+    }
+
+    let namespace = Value::FrozenDict(Rc::new(exports));
+    module_cache.insert(resolved, namespace.clone());
+    Ok(namespace)
+}
+
+fn run_inner(
+    code: &[Instr],
+    funcs: &HashMap<String, Function>,
+    globals: &mut HashMap<String, Value>,
+    module_cache: &mut HashMap<String, Value>,
+    interrupt: Option<Arc<AtomicBool>>,
+    mut budget: Option<&mut Budget>,
+    builtins: &Builtins,
+    sink: &mut dyn Write,
+    mut input: Option<&mut dyn FnMut() -> Option<String>>,
+    mut tracer: Option<&mut dyn Tracer>,
+) -> Result<(), TracedError> {
+    // Operand/value stack. All computation flows through here. Preallocated
+    // to the statically analyzed peak depth of the top-level code (see
+    // `bytecode::main_max_stack`) so a hot loop doesn't repeatedly reallocate
+    // as it grows; the analysis saturates/conservatively bounds the estimate
+    // rather than panicking, so a `reserve` call here is always safe even if
+    // the estimate turns out too low (it just reallocates once more, as it
+    // would have without this at all).
+    let mut stack: Vec<Value> = Vec::with_capacity(crate::bytecode::main_max_stack(code, funcs));
+
     // Current local environment (top frame) and the stack of saved locals.
     // Function calls push a new local env; returns restore the previous.
     let mut env: HashMap<String, Value> = HashMap::new();
@@ -137,6 +591,11 @@ pub fn run(
     // Return address stack for user-defined function calls (stores PCs).
     let mut ret_stack: Vec<usize> = Vec::new();
 
+    // Parallel call-stack traceback: (callee name, call-site pc), pushed and
+    // popped in lockstep with `env_stack`/`ret_stack` so its length always
+    // matches theirs.
+    let mut call_frames: Vec<Frame> = Vec::new();
+
     // Program counter: index of the current instruction.
     let mut pc: usize = 0;
 
@@ -146,17 +605,93 @@ pub fn run(
     // Pending error from an instruction, to be handled by a block or returned.
     let mut error_flag: Option<RuntimeError> = None;
 
+    // Instructions executed so far, used to throttle interrupt/timeout
+    // polling and as the fuel counter for a `Budget`.
+    let mut steps: u64 = 0;
+
+    // Next `gc::alloc_count()` at which to run an automatic collection pass.
+    let mut next_gc_at = gc::alloc_count() + GC_ALLOC_THRESHOLD;
+
+    // Wall-clock start, used by `Budget::timeout` (cheap to record even when
+    // no budget/timeout is set).
+    let start = std::time::Instant::now();
+
+    // Effective call-depth ceiling for this run: the caller's override, if
+    // any, else the default.
+    let max_call_depth = budget
+        .as_deref()
+        .and_then(|b| b.max_call_depth)
+        .unwrap_or(MAX_CALL_DEPTH);
+
     // === Fetch–Decode–Execute loop ===
     while pc < code.len() {
         // By default we advance to the next instruction after executing.
         // Control-flow ops (jumps, calls) will set this to false.
         let mut advance_pc = true;
 
+        // Safe point: between instructions, the only live references into
+        // the list/dict heap are these roots, so it's sound to sweep.
+        if gc::alloc_count() >= next_gc_at {
+            let roots: Vec<&Value> = stack
+                .iter()
+                .chain(globals.values())
+                .chain(env.values())
+                .chain(env_stack.iter().flat_map(|frame| frame.values()))
+                .collect();
+            gc::collect(&roots);
+            next_gc_at = gc::alloc_count() + GC_ALLOC_THRESHOLD;
+        }
+
+        // Poll the cooperative interrupt flag periodically rather than every
+        // instruction, keeping the hot loop cheap.
+        steps += 1;
+
+        // Fuel is charged once per dispatched instruction (no throttling —
+        // it has to be exact to be deterministic).
+        if let Some(b) = budget.as_deref_mut() {
+            b.fuel_used = steps;
+            if let Some(fuel) = b.fuel {
+                if steps > fuel {
+                    error_flag = Some(RuntimeError::FuelExhausted);
+                }
+            }
+            if steps % TIMEOUT_CHECK_INTERVAL == 0 {
+                if let Some(timeout) = b.timeout {
+                    if start.elapsed() >= timeout {
+                        error_flag = Some(RuntimeError::Timeout);
+                    }
+                }
+            }
+        }
+
+        if steps % INTERRUPT_CHECK_INTERVAL == 0 {
+            if let Some(flag) = &interrupt {
+                if flag.load(Ordering::Relaxed) {
+                    error_flag = Some(RuntimeError::Interrupted);
+                }
+            }
+        }
+
         // Execute the current instruction, capturing any runtime error.
-        let instr_res: Result<(), RuntimeError> = loop {
+        let instr_res: Result<(), RuntimeError> = if error_flag.is_some() {
+            Ok(())
+        } else {
+            if let Some(t) = tracer.as_deref_mut() {
+                let snap_start = stack.len().saturating_sub(trace::TRACE_STACK_SNAPSHOT);
+                let event = TraceEvent {
+                    pc,
+                    instr: &code[pc],
+                    stack_depth: stack.len(),
+                    stack_top: &stack[snap_start..],
+                    block_depth: block_stack.len(),
+                };
+                t.on_instr(&event);
+            }
+            loop {
             match &code[pc] {
                 // ----- Literals / Basic pushes -----
                 Instr::PushInt(v) => stack.push(Value::Int(*v)),
+                Instr::PushFloat(v) => stack.push(Value::Float(*v)),
                 Instr::PushStr(s) => stack.push(Value::Str(s.clone())),
                 Instr::PushBool(b) => stack.push(Value::Bool(*b)),
                 // ----- Aggregate construction -----
@@ -245,6 +780,16 @@ pub fn run(
                         break Err(e);
                     }
                 }
+                Instr::Concat => {
+                    if let Err(e) = ops_struct::handle_concat(&mut stack) {
+                        break Err(e);
+                    }
+                }
+                Instr::Repeat => {
+                    if let Err(e) = ops_struct::handle_repeat(&mut stack) {
+                        break Err(e);
+                    }
+                }
                 // ----- Assertions / exceptions -----
                 Instr::Assert => {
                     if let Err(e) = ops_control::handle_assert(&mut stack) {
@@ -262,8 +807,10 @@ pub fn run(
                         &mut env,
                         &mut env_stack,
                         &mut ret_stack,
+                        &mut call_frames,
                         &mut pc,
                         &mut advance_pc,
+                        max_call_depth,
                     ) {
                         break Err(e);
                     }
@@ -295,8 +842,10 @@ pub fn run(
                         &mut env,
                         &mut env_stack,
                         &mut ret_stack,
+                        &mut call_frames,
                         &mut pc,
                         &mut advance_pc,
+                        max_call_depth,
                     ) {
                         break Err(e);
                     }
@@ -315,13 +864,48 @@ pub fn run(
                     }
                 }
                 Instr::CallBuiltin(name, argc) => {
-                    // Invoke a builtin by name with `argc` args sourced from stack.
-                    if let Err(e) = ops_control::handle_call_builtin(
+                    // `gc.collect` is handled here rather than through the
+                    // `Builtins` table: it needs the full root set (operand
+                    // stack + every live env frame), which a plain builtin
+                    // (env/globals only) can't see.
+                    if name == "gc.collect" {
+                        for _ in 0..*argc {
+                            stack.pop();
+                        }
+                        let roots: Vec<&Value> = stack
+                            .iter()
+                            .chain(globals.values())
+                            .chain(env.values())
+                            .chain(env_stack.iter().flat_map(|frame| frame.values()))
+                            .collect();
+                        let swept = gc::collect(&roots);
+                        stack.push(Value::Int(swept as i64));
+                    } else if name == "flush" {
+                        // Needs the sink, which a plain builtin can't see.
+                        for _ in 0..*argc {
+                            stack.pop();
+                        }
+                        if let Err(e) = ops_control::handle_flush(sink) {
+                            break Err(e);
+                        }
+                        stack.push(Value::None);
+                    } else if name == "read_line" && *argc == 0 && input.is_some() {
+                        // An input provider was supplied (an embedder bridging
+                        // its own source of input, e.g. the wasm bindings'
+                        // JS callback) — needs `input`, which a plain builtin
+                        // can't see, so it's special-cased here the same way
+                        // `flush` needs `sink`. Without a provider, `read_line`
+                        // falls through to the ordinary builtin dispatch below
+                        // and reads real stdin as it always has.
+                        let line = input.as_mut().unwrap()();
+                        stack.push(line.map(Value::Str).unwrap_or(Value::None));
+                    } else if let Err(e) = ops_control::handle_call_builtin(
                         name,
                         *argc,
                         &mut stack,
                         &env,
-                        &globals,
+                        globals,
+                        builtins,
                     ) {
                         break Err(e);
                     }
@@ -333,28 +917,35 @@ pub fn run(
                 }
                 Instr::Ret => {
                      // Return from current function frame. Restores env and PC.
-                    ops_control::handle_ret(
+                    if let Err(e) = ops_control::handle_ret(
                         &mut stack,
                         &mut pc,
                         &mut env,
                         &mut env_stack,
                         &mut ret_stack,
+                        &mut call_frames,
                         &mut advance_pc,
-                    );
+                    ) {
+                        break Err(e);
+                    }
                 }
                 Instr::Emit => {
-                    // Print top-of-stack (implementation-defined output).
-                    ops_control::handle_emit(&mut stack);
+                    // Write top-of-stack to the caller-supplied sink.
+                    if let Err(e) = ops_control::handle_emit(&mut stack, sink) {
+                        break Err(e);
+                    }
                 }
                 Instr::Halt => {
                     // Force termination by jumping to end of code.
                     ops_control::handle_halt(code.len(), &mut pc, &mut advance_pc);
                 }
                 // ----- Structured exception handling blocks -----
-                Instr::SetupExcept(target) => {
-                    // Push a handler frame capturing current depths and a handler PC.
+                Instr::SetupExcept(target, kinds) => {
+                    // Push a handler frame capturing current depths, a handler
+                    // PC, and the `ErrorKind`s it filters on (empty = catch-all).
                     ops_control::handle_setup_except(
                         *target,
+                        kinds.clone(),
                         &stack,
                         &env_stack,
                         &ret_stack,
@@ -369,9 +960,30 @@ pub fn run(
                     // Raise an exception; converts to `RuntimeError`, bubbled to VM.
                     break ops_control::handle_raise(kind, &mut stack);
                 }
+                // ----- Modules -----
+                Instr::Import(path) => {
+                    let current_dir = match globals.get("current_dir") {
+                        Some(Value::Str(s)) => s.clone(),
+                        _ => ".".to_string(),
+                    };
+                    match import_module(path, &current_dir, module_cache, builtins, sink) {
+                        Ok(ns) => stack.push(ns),
+                        Err(e) => break Err(e),
+                    }
+                }
+                Instr::Yield => {
+                    // No host stepping loop here to actually suspend for
+                    // (see `Vm::step` for that) — resume immediately with
+                    // no injected value, discarding the yielded one.
+                    if let Err(e) = pop(&mut stack) {
+                        break Err(e);
+                    }
+                    stack.push(Value::None);
+                }
             }
             // If we got here, the instruction completed without error.
             break Ok(());
+            }
         };
 
         // Capture any fault from the just-executed instruction.
@@ -379,33 +991,69 @@ pub fn run(
             error_flag = Some(e);
         }
 
-        // If an error occurred, attempt to unwind to the nearest handler.
+        // If an error occurred, attempt to unwind to the nearest matching handler.
         if let Some(err) = error_flag.take() {
+            // Snapshot the traceback now, before unwinding pops any frames,
+            // so an unhandled error reports exactly the calls that were
+            // active when it was raised.
+            let traceback = call_frames.clone();
+            let fault_pc = pc;
+            let fault_instr = code[pc].name().to_string();
             let mut handled = false;
-            // Pop blocks until one handles the error. For the first viable block:
-            // - restore env/ret/stack depths
-            // - jump to its handler
-            // - push the error message string as the handler’s input
+            let err_kind = err.kind();
+            // Pop blocks until one whose filter catches this error's kind.
+            // Non-matching blocks are discarded (the handler was only ever
+            // scoped to kinds it declared) and the search continues outward.
             while let Some(block) = block_stack.pop() {
+                if !block.catches(err_kind) {
+                    continue;
+                }
                 // Restore local frames to the captured depth.
                 while env_stack.len() > block.env_depth {
                     env = env_stack.pop().unwrap();
                     ret_stack.pop();
+                    call_frames.pop();
                 }
                 // Ensure return addresses match the captured depth.
                 ret_stack.truncate(block.ret_depth);
                 // Restore operand stack height.
                 stack.truncate(block.stack_size);
-                // Transfer control to handler and provide error info.
+                // Transfer control to handler and provide error info: a
+                // structured raise (`raise value`) hands back its original
+                // Value unchanged; any other `RuntimeError` is described as a
+                // dict ({"kind": ..., "message": ...}) so handler code can
+                // branch on `err["kind"]` instead of pattern-matching a
+                // formatted string.
                 pc = block.handler;
-                stack.push(Value::Str(err.to_string()));
+                let handler_input = match err {
+                    RuntimeError::RaisedValue(_, v) => v,
+                    other => {
+                        let mut fields = HashMap::new();
+                        fields.insert(
+                            DictKey::Str("kind".to_string()),
+                            Value::Str(other.kind().name().to_string()),
+                        );
+                        fields.insert(
+                            DictKey::Str("message".to_string()),
+                            Value::Str(other.to_string()),
+                        );
+                        Value::new_dict(fields)
+                    }
+                };
+                stack.push(handler_input);
                 handled = true;
                 break;
             }
 
-            // No handler: abort with the original error.
+            // No matching handler: abort with the original error, reporting
+            // the call stack that was active at raise time.
             if !handled {
-                return Err(err);
+                return Err(TracedError {
+                    error: err,
+                    frames: traceback,
+                    fault_pc,
+                    fault_instr,
+                });
             } else {
                 // We transferred control to a handler; do not auto-advance PC.
                 continue;
@@ -9,52 +9,303 @@
 //!   returns a `Value` or a `RuntimeError`.
 //! - **No direct VM coupling:** Builtins don’t read VM registers; any state
 //!   needed is passed in explicitly (e.g., `env`, `globals`) or stored in this
-//!   module (like file handles).
+//!   module (like file handles). One consequence: `map`/`filter`/`reduce`
+//!   can only invoke *other builtins* by name as their callback, not
+//!   user-defined OMG functions — those live in the VM's `funcs` table, which
+//!   this module has no access to (see their doc comments below).
 //! - **Filesystem helpers:** Relative paths are resolved against `current_dir`
 //!   (from the current env or globals) to keep script behavior predictable.
 //! - **File I/O table:** Simple integer file descriptors (`i32`) map to
 //!   open files. Access is synchronized for thread-safety.
+//! - **Pluggable registry:** [`Builtins`] is a name → function table. The
+//!   crate-wide default ([`Builtins::standard`]) backs the free-standing
+//!   [`call_builtin`] used by `ops_control`; an embedder that wants to add or
+//!   override host functions builds its own `Builtins` and drives the VM via
+//!   `vm::run_with_builtins` instead. Plain functions go through
+//!   [`Builtins::register`]; a host function that needs to capture state
+//!   (an FFI handle, a channel, ...) uses [`Builtins::register_fn`], which
+//!   `call` falls back to whenever a name isn't a core builtin.
 //!
 //! ## Provided builtins (summary)
-//! - **Data / conversion:** `chr`, `ascii`, `hex`, `binary`, `length`, `freeze`
+//! - **Data / conversion:** `chr`, `ord`/`ascii`, `hex`, `binary`, `bits`,
+//!   `length`/`len`, `str`, `int`, `type`, `upper`, `lower`, `freeze`
+//! - **Math:** `pow`, `sqrt`, `floor`, `ceil`, `round`, `abs`, `min`, `max`
+//! - **Randomness:** `random`, `randint`, `one_in`, `seed`
+//! - **Collections:** `keys`, `values`, `range`, `map`, `filter`, `reduce`
 //! - **Errors:** `panic`, `raise`
-//! - **Filesystem:** `read_file`, `file_exists`
-//! - **File descriptors:** `file_open`, `file_read`, `file_write`, `file_close`
+//! - **Filesystem:** `read_file`, `write_file`, `read_bytes`, `write_bytes`, `file_exists`
+//! - **File descriptors:** `file_open`, `file_read`, `file_write`, `file_close`,
+//!   `file_readline`, `file_readlines`, `file_read_until`, `file_copy`,
+//!   `file_seek`, `file_tell`, `last_io_error`, `set_max_open_files`,
+//!   `open_file_count`
+//! - **Standard input:** `read_line`, `read_all_stdin`
+//! - **System:** `argv`, `env`, `exit`
 //! - **Meta:** `call_builtin` (dispatch another builtin dynamically)
+//! - **GC:** `gc.alloc_count`, `gc.live_count` (see `crate::gc`; `gc.collect`
+//!   itself is dispatched specially by `vm.rs`, not registered here)
+//! - **Output sink:** `flush()` is likewise dispatched specially by `vm.rs`
+//!   (not registered here), since it needs the same `emit` sink a plain
+//!   builtin can't see
 //!
 //! ## Error conventions
 //! - Arity/type mismatches → `RuntimeError::TypeError`
 //! - Value problems (e.g., bad width, invalid file mode) → `RuntimeError::ValueError`
 //! - IO failures → mapped to `ValueError` or `ModuleImportError` (for `read_file`,
-//!   since it’s commonly used by import loaders)
+//!   since it’s commonly used by import loaders); `file_open`/`file_read`/
+//!   `file_write`/`read_file` additionally stash the failing `std::io::Error`'s
+//!   errno and kind for `last_io_error()` to report (see `record_io_error`)
 //! - `raise()` manufactures a `RuntimeError` via the VM’s raise handler
 //!
 //! ## Notes on text vs binary I/O
 //! - `file_open(path, "r"|"w"|"a")` → **text** (UTF‑8 strings)
 //! - `file_open(path, "rb"|"wb"|"ab")` → **binary** (list of byte ints 0–255)
 //! - `file_write` enforces the correct data type for the handle kind.
+//! - `file_readline`/`file_readlines` (text) and `file_read_until` (binary)
+//!   lazily wrap a handle's `fs::File` in a `BufReader` on first use (see
+//!   `FileHandle`). Once that's happened, `file_read`/`file_write` on the
+//!   same handle is a `ValueError` — the `BufReader` may already hold bytes
+//!   past the file's actual cursor, so a raw whole-file op could silently
+//!   skip or duplicate data.
 
 use std::collections::HashMap;
+use std::env as std_env;
 use std::fs::{self, OpenOptions};
-use std::io::{Read, Write};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
 use std::path::PathBuf;
 use std::rc::Rc;
-use std::cell::RefCell;
 use std::sync::{atomic::{AtomicI32, Ordering}, Mutex};
 
 use once_cell::sync::Lazy;
 
 use super::ops_control;
 use crate::error::{ErrorKind, RuntimeError};
-use crate::value::Value;
+use crate::value::{DictKey, Value};
+
+/// Signature every builtin function implements: positional args, the current
+/// local environment, and the globals table (for things like `current_dir`).
+pub type BuiltinFn =
+    fn(&[Value], &HashMap<String, Value>, &HashMap<String, Value>) -> Result<Value, RuntimeError>;
+
+/// Signature for a host-registered closure (see [`Builtins::register_fn`]).
+///
+/// `BuiltinFn` is a plain function pointer, so it can't capture any state —
+/// fine for the standard library, which is all pure functions, but not for
+/// an embedder wiring in a host channel, an FFI handle, or anything else
+/// that needs to close over context. A boxed `Fn` can.
+pub type HostFn = Box<
+    dyn Fn(&[Value], &HashMap<String, Value>, &HashMap<String, Value>) -> Result<Value, RuntimeError>
+        + Send
+        + Sync,
+>;
+
+/// A name → function registry for `CallBuiltin`.
+///
+/// [`Builtins::standard`] gives you the full built-in standard library;
+/// [`Builtins::register`] lets an embedder add or override entries (e.g. a
+/// host-provided `http_get`) before driving the VM with `vm::run_with_builtins`.
+/// [`Builtins::register_fn`] is the same idea for a closure that needs to
+/// capture host state rather than a plain function pointer — `call` checks
+/// `table` first and falls back to `host_fns` so a host function can never
+/// silently shadow a core builtin of the same name.
+pub struct Builtins {
+    table: HashMap<String, BuiltinFn>,
+    host_fns: HashMap<String, HostFn>,
+}
+
+impl Builtins {
+    /// Build the registry backing the standard library (every builtin listed
+    /// in this module's doc comment).
+    pub fn standard() -> Self {
+        let mut table: HashMap<String, BuiltinFn> = HashMap::new();
+        table.insert("chr".to_string(), builtin_chr as BuiltinFn);
+        table.insert("ascii".to_string(), builtin_ascii as BuiltinFn);
+        table.insert("ord".to_string(), builtin_ascii as BuiltinFn);
+        table.insert("hex".to_string(), builtin_hex as BuiltinFn);
+        table.insert("binary".to_string(), builtin_binary as BuiltinFn);
+        table.insert("bits".to_string(), builtin_bits as BuiltinFn);
+        table.insert("length".to_string(), builtin_length as BuiltinFn);
+        table.insert("len".to_string(), builtin_length as BuiltinFn);
+        table.insert("str".to_string(), builtin_str as BuiltinFn);
+        table.insert("int".to_string(), builtin_int as BuiltinFn);
+        table.insert("type".to_string(), builtin_type as BuiltinFn);
+        table.insert("upper".to_string(), builtin_upper as BuiltinFn);
+        table.insert("lower".to_string(), builtin_lower as BuiltinFn);
+        table.insert("freeze".to_string(), builtin_freeze as BuiltinFn);
+        table.insert("pow".to_string(), builtin_pow as BuiltinFn);
+        table.insert("sqrt".to_string(), builtin_sqrt as BuiltinFn);
+        table.insert("floor".to_string(), builtin_floor as BuiltinFn);
+        table.insert("ceil".to_string(), builtin_ceil as BuiltinFn);
+        table.insert("round".to_string(), builtin_round as BuiltinFn);
+        table.insert("abs".to_string(), builtin_abs as BuiltinFn);
+        table.insert("min".to_string(), builtin_min as BuiltinFn);
+        table.insert("max".to_string(), builtin_max as BuiltinFn);
+        table.insert("random".to_string(), builtin_random as BuiltinFn);
+        table.insert("randint".to_string(), builtin_randint as BuiltinFn);
+        table.insert("one_in".to_string(), builtin_one_in as BuiltinFn);
+        table.insert("seed".to_string(), builtin_seed as BuiltinFn);
+        table.insert("keys".to_string(), builtin_keys as BuiltinFn);
+        table.insert("values".to_string(), builtin_values as BuiltinFn);
+        table.insert("range".to_string(), builtin_range as BuiltinFn);
+        table.insert("map".to_string(), builtin_map as BuiltinFn);
+        table.insert("filter".to_string(), builtin_filter as BuiltinFn);
+        table.insert("reduce".to_string(), builtin_reduce as BuiltinFn);
+        table.insert("panic".to_string(), builtin_panic as BuiltinFn);
+        table.insert("raise".to_string(), builtin_raise as BuiltinFn);
+        table.insert("read_file".to_string(), builtin_read_file as BuiltinFn);
+        table.insert("write_file".to_string(), builtin_write_file as BuiltinFn);
+        table.insert("read_bytes".to_string(), builtin_read_bytes as BuiltinFn);
+        table.insert("write_bytes".to_string(), builtin_write_bytes as BuiltinFn);
+        table.insert("file_open".to_string(), builtin_file_open as BuiltinFn);
+        table.insert("file_read".to_string(), builtin_file_read as BuiltinFn);
+        table.insert("file_write".to_string(), builtin_file_write as BuiltinFn);
+        table.insert("file_close".to_string(), builtin_file_close as BuiltinFn);
+        table.insert("file_readline".to_string(), builtin_file_readline as BuiltinFn);
+        table.insert("file_readlines".to_string(), builtin_file_readlines as BuiltinFn);
+        table.insert("file_read_until".to_string(), builtin_file_read_until as BuiltinFn);
+        table.insert("file_copy".to_string(), builtin_file_copy as BuiltinFn);
+        table.insert("file_seek".to_string(), builtin_file_seek as BuiltinFn);
+        table.insert("file_tell".to_string(), builtin_file_tell as BuiltinFn);
+        table.insert("last_io_error".to_string(), builtin_last_io_error as BuiltinFn);
+        table.insert("set_max_open_files".to_string(), builtin_set_max_open_files as BuiltinFn);
+        table.insert("open_file_count".to_string(), builtin_open_file_count as BuiltinFn);
+        table.insert("file_exists".to_string(), builtin_file_exists as BuiltinFn);
+        table.insert("read_line".to_string(), builtin_read_line as BuiltinFn);
+        table.insert("read_all_stdin".to_string(), builtin_read_all_stdin as BuiltinFn);
+        table.insert("call_builtin".to_string(), builtin_call_builtin as BuiltinFn);
+        table.insert("argv".to_string(), builtin_argv as BuiltinFn);
+        table.insert("env".to_string(), builtin_env as BuiltinFn);
+        table.insert("exit".to_string(), builtin_exit as BuiltinFn);
+        table.insert("gc.alloc_count".to_string(), builtin_gc_alloc_count as BuiltinFn);
+        table.insert("gc.live_count".to_string(), builtin_gc_live_count as BuiltinFn);
+        Builtins {
+            table,
+            host_fns: HashMap::new(),
+        }
+    }
+
+    /// Register (or override) a single builtin by name.
+    pub fn register(&mut self, name: &str, f: BuiltinFn) {
+        self.table.insert(name.to_string(), f);
+    }
+
+    /// Register (or replace an earlier closure of the same name) a single
+    /// builtin with a closure that may
+    /// capture host state (an FFI handle, an I/O channel, ...) — the
+    /// extension point for embedders whose host function isn't expressible
+    /// as a plain `BuiltinFn` pointer. Dispatched through the same
+    /// `CallBuiltin(name, argc)` path as every other builtin. A name that is
+    /// already a core builtin keeps resolving to the core function, since
+    /// `call` checks `table` first.
+    pub fn register_fn<F>(&mut self, name: &str, f: F)
+    where
+        F: Fn(&[Value], &HashMap<String, Value>, &HashMap<String, Value>) -> Result<Value, RuntimeError>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.host_fns.insert(name.to_string(), Box::new(f));
+    }
+
+    /// Dispatch `name` against this registry: core/overridden builtins in
+    /// `table` first, falling back to host closures in `host_fns` when the
+    /// name isn't a core builtin.
+    pub fn call(
+        &self,
+        name: &str,
+        args: &[Value],
+        env: &HashMap<String, Value>,
+        globals: &HashMap<String, Value>,
+    ) -> Result<Value, RuntimeError> {
+        if let Some(f) = self.table.get(name) {
+            return f(args, env, globals);
+        }
+        if let Some(f) = self.host_fns.get(name) {
+            return f(args, env, globals);
+        }
+        Err(RuntimeError::TypeError(format!("unknown builtin: {}", name)))
+    }
+}
+
+impl Default for Builtins {
+    fn default() -> Self {
+        Builtins::standard()
+    }
+}
+
+/// The registry backing the free-standing [`call_builtin`] (used wherever
+/// code doesn't thread a caller-supplied [`Builtins`] through, e.g. the
+/// existing `ops_control::handle_call_builtin` call sites and tests).
+static DEFAULT_BUILTINS: Lazy<Builtins> = Lazy::new(Builtins::standard);
+
+/// Underlying OS file a [`FileEntry`] wraps: either the raw `fs::File` (the
+/// state every handle starts in), or a `BufReader` around it once a
+/// line-oriented read (`file_readline`/`file_readlines`/`file_read_until`)
+/// has requested one. Once buffered, the handle can't go back to whole-file
+/// `file_read`/`file_write` — see [`FileEntry::raw_file`] — since the
+/// `BufReader` may be holding bytes past the file's actual cursor position
+/// that a raw read would silently skip.
+enum FileHandle {
+    Raw(fs::File),
+    Buffered(BufReader<fs::File>),
+}
 
 /// Entry in the in-process file descriptor table.
 struct FileEntry {
-    file: fs::File,
+    /// `None` only transiently, inside [`FileEntry::buffered`], while
+    /// ownership is being moved from `Raw` into a new `Buffered` wrapper.
+    file: Option<FileHandle>,
     /// Whether this handle is opened in **binary** mode (`rb`, `wb`, `ab`).
     binary: bool,
 }
 
+impl FileEntry {
+    fn new(file: fs::File, binary: bool) -> Self {
+        FileEntry {
+            file: Some(FileHandle::Raw(file)),
+            binary,
+        }
+    }
+
+    /// Borrow the underlying `fs::File` for whole-file I/O (`file_read`,
+    /// `file_write`). Errors if the handle has already been switched into
+    /// buffered line-read mode.
+    fn raw_file(&mut self) -> Result<&mut fs::File, RuntimeError> {
+        match self.file.as_mut().expect("FileEntry.file is only None mid-call") {
+            FileHandle::Raw(f) => Ok(f),
+            FileHandle::Buffered(_) => Err(RuntimeError::ValueError(
+                "cannot use a handle for whole-file I/O after it has been read line-by-line"
+                    .to_string(),
+            )),
+        }
+    }
+
+    /// Borrow the handle's `BufReader`, lazily wrapping the raw `fs::File`
+    /// the first call. Subsequent calls reuse the same `BufReader` so its
+    /// internal buffer (and thus read position) carries over correctly.
+    fn buffered(&mut self) -> &mut BufReader<fs::File> {
+        let current = self.file.take().expect("FileEntry.file is only None mid-call");
+        let buffered = match current {
+            FileHandle::Buffered(r) => r,
+            FileHandle::Raw(f) => BufReader::new(f),
+        };
+        self.file = Some(FileHandle::Buffered(buffered));
+        match self.file.as_mut().unwrap() {
+            FileHandle::Buffered(r) => r,
+            FileHandle::Raw(_) => unreachable!("just replaced with Buffered above"),
+        }
+    }
+
+    /// Reposition the handle, going through whichever variant (`Raw` or
+    /// `Buffered`) is currently active so a `BufReader`'s internal buffer is
+    /// discarded correctly rather than serving stale bytes after the seek.
+    fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+        match self.file.as_mut().expect("FileEntry.file is only None mid-call") {
+            FileHandle::Raw(f) => f.seek(pos),
+            FileHandle::Buffered(r) => r.seek(pos),
+        }
+    }
+}
+
 /// Global FD table. A simple, process-local registry mapping `i32` handles to open files.
 /// Wrapped in a `Mutex` to be usable from multiple threads safely.
 static FILE_HANDLES: Lazy<Mutex<HashMap<i32, FileEntry>>> =
@@ -63,6 +314,196 @@ static FILE_HANDLES: Lazy<Mutex<HashMap<i32, FileEntry>>> =
 /// Monotonic counter to allocate new integer file descriptors.
 static NEXT_FD: AtomicI32 = AtomicI32::new(0);
 
+/// Soft cap on live entries in `FILE_HANDLES`, enforced by `file_open`.
+/// Defaults to `i32::MAX` (effectively unbounded) until a script calls
+/// `set_max_open_files`.
+static MAX_OPEN_FILES: AtomicI32 = AtomicI32::new(i32::MAX);
+
+/// Raw `RLIMIT_NOFILE` plumbing for `set_max_open_files`'s Unix-only soft
+/// limit raise. Hand-rolled rather than pulled in via the `libc` crate,
+/// since this tree has no `Cargo.toml` to declare that dependency in.
+///
+/// Gated to the specific Unix flavors whose `RLIMIT_NOFILE` resource number
+/// is known below, rather than `cfg(unix)` generally — that cfg also
+/// matches targets (Android, Solaris, ...) this module would otherwise get
+/// wrong silently.
+#[cfg(any(
+    target_os = "linux",
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "dragonfly"
+))]
+mod rlimit {
+    #[repr(C)]
+    pub struct RLimit {
+        pub cur: u64,
+        pub max: u64,
+    }
+
+    // The `RLIMIT_NOFILE` resource number isn't portable across Unix
+    // flavors even though the syscalls are; the other OMG build targets
+    // this repo touches (native_codegen's x86-64 output, the wasm crate)
+    // don't go through this path at all.
+    #[cfg(target_os = "linux")]
+    pub const RLIMIT_NOFILE: i32 = 7;
+    #[cfg(any(target_os = "macos", target_os = "ios"))]
+    pub const RLIMIT_NOFILE: i32 = 8;
+    #[cfg(any(
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd",
+        target_os = "dragonfly"
+    ))]
+    pub const RLIMIT_NOFILE: i32 = 8;
+
+    extern "C" {
+        #[link_name = "getrlimit"]
+        pub fn getrlimit(resource: i32, rlp: *mut RLimit) -> i32;
+        #[link_name = "setrlimit"]
+        pub fn setrlimit(resource: i32, rlp: *const RLimit) -> i32;
+    }
+}
+
+/// Attempt to raise the process's soft `RLIMIT_NOFILE` toward its hard
+/// limit, returning the resulting effective soft limit. On non-Unix targets,
+/// on a Unix flavor `mod rlimit` doesn't have a resource number for, or if
+/// the syscalls fail, this is a no-op that just reports the limit already
+/// in effect (or `requested` as a best guess if it can't be read).
+#[cfg(any(
+    target_os = "linux",
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "dragonfly"
+))]
+fn raise_nofile_limit(requested: u64) -> u64 {
+    unsafe {
+        let mut lim = rlimit::RLimit { cur: 0, max: 0 };
+        if rlimit::getrlimit(rlimit::RLIMIT_NOFILE, &mut lim) != 0 {
+            return requested;
+        }
+        let target = requested.min(lim.max);
+        if target > lim.cur {
+            let raised = rlimit::RLimit {
+                cur: target,
+                max: lim.max,
+            };
+            if rlimit::setrlimit(rlimit::RLIMIT_NOFILE, &raised) == 0 {
+                return target;
+            }
+        }
+        lim.cur
+    }
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "dragonfly"
+)))]
+fn raise_nofile_limit(requested: u64) -> u64 {
+    requested
+}
+
+/// set_max_open_files(n) -> Int effective limit; enforces a soft cap of `n`
+/// live handles in `file_open` (past this, opens fail with a dedicated
+/// `ValueError` instead of letting the OS fail deep inside `opts.open`), and
+/// on Unix also attempts to raise the process's actual `RLIMIT_NOFILE` soft
+/// limit toward its hard limit so the OS itself doesn't become the bottleneck
+/// first. Returns the resulting effective OS limit (which may be lower than
+/// `n` if the hard limit doesn't allow it).
+fn builtin_set_max_open_files(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [Value::Int(n)] if *n > 0 => {
+            MAX_OPEN_FILES.store(*n as i32, Ordering::SeqCst);
+            let effective = raise_nofile_limit(*n as u64);
+            Ok(Value::Int(effective as i64))
+        }
+        [Value::Int(_)] => Err(RuntimeError::ValueError(
+            "set_max_open_files() expects a positive count".to_string(),
+        )),
+        _ => Err(RuntimeError::TypeError(
+            "set_max_open_files() expects a count".to_string(),
+        )),
+    }
+}
+
+/// open_file_count() -> Int number of currently live file handles.
+fn builtin_open_file_count(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    if !args.is_empty() {
+        return Err(RuntimeError::TypeError(
+            "open_file_count() takes no arguments".to_string(),
+        ));
+    }
+    Ok(Value::Int(FILE_HANDLES.lock().unwrap().len() as i64))
+}
+
+/// The most recent I/O failure captured by [`record_io_error`], surfaced to
+/// OMG scripts via the `last_io_error()` builtin. `None` until the first
+/// failure; never cleared on success, so it always reflects "the last I/O
+/// call that failed", not "the last I/O call".
+static LAST_IO_ERROR: Lazy<Mutex<Option<IoErrorInfo>>> = Lazy::new(|| Mutex::new(None));
+
+/// Snapshot of a `std::io::Error` worth keeping around after the original
+/// error has been turned into a `RuntimeError` and its `io::Error` dropped.
+struct IoErrorInfo {
+    /// Platform errno (e.g. `ENOENT`/`EACCES`), or `-1` if the error didn't
+    /// originate from the OS (`raw_os_error()` returned `None`).
+    code: i32,
+    /// `std::io::ErrorKind`'s `Debug` name, e.g. `"NotFound"`, `"PermissionDenied"`.
+    kind: String,
+    message: String,
+}
+
+/// Record `err` as the most recent I/O failure for `last_io_error()` to
+/// report, then return it unchanged so call sites can chain this into their
+/// existing `.map_err(...)` pipelines without restructuring them.
+fn record_io_error(err: std::io::Error) -> std::io::Error {
+    let info = IoErrorInfo {
+        code: err.raw_os_error().unwrap_or(-1),
+        kind: format!("{:?}", err.kind()),
+        message: err.to_string(),
+    };
+    *LAST_IO_ERROR.lock().unwrap() = Some(info);
+    err
+}
+
+/// last_io_error() -> Dict {code: Int, kind: Str, message: Str}; the most
+/// recent I/O failure recorded by `file_open`/`file_read`/`file_write`/
+/// `read_file`, or an empty-message, `code: -1`, `kind: ""` entry if no I/O
+/// call has failed yet this run.
+fn builtin_last_io_error(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    if !args.is_empty() {
+        return Err(RuntimeError::TypeError(
+            "last_io_error() takes no arguments".to_string(),
+        ));
+    }
+    let guard = LAST_IO_ERROR.lock().unwrap();
+    let mut map = HashMap::new();
+    match guard.as_ref() {
+        Some(info) => {
+            map.insert(DictKey::Str("code".to_string()), Value::Int(info.code as i64));
+            map.insert(DictKey::Str("kind".to_string()), Value::Str(info.kind.clone()));
+            map.insert(DictKey::Str("message".to_string()), Value::Str(info.message.clone()));
+        }
+        None => {
+            map.insert(DictKey::Str("code".to_string()), Value::Int(-1));
+            map.insert(DictKey::Str("kind".to_string()), Value::Str(String::new()));
+            map.insert(DictKey::Str("message".to_string()), Value::Str(String::new()));
+        }
+    }
+    Ok(Value::new_dict(map))
+}
+
 /// Resolve a user-supplied path relative to `current_dir` (env or globals).
 ///
 /// The VM injects `current_dir` and `module_file` globals/locals on program start.
@@ -82,320 +523,1220 @@ fn resolve_path(path: &str, env: &HashMap<String, Value>, globals: &HashMap<Stri
     path_buf
 }
 
-/// Dispatch a built-in function by name.
+/// Dispatch a built-in function by name against the default registry.
 ///
-/// * `name`  – builtin identifier (e.g. `"length"`, `"file_open"`)  
-/// * `args`  – positional arguments as already-evaluated `Value`s  
-/// * `env`   – current local environment (for `current_dir`)  
+/// * `name`  – builtin identifier (e.g. `"length"`, `"file_open"`)
+/// * `args`  – positional arguments as already-evaluated `Value`s
+/// * `env`   – current local environment (for `current_dir`)
 /// * `globals` – global environment (fallback for `current_dir`)
 ///
-/// Returns a `Value` on success or a `RuntimeError` on failure.
+/// Returns a `Value` on success or a `RuntimeError` on failure. Embedders
+/// that need to add or override builtins should build a [`Builtins`]
+/// instead and drive the VM with `vm::run_with_builtins`.
 pub fn call_builtin(
     name: &str,
     args: &[Value],
     env: &HashMap<String, Value>,
     globals: &HashMap<String, Value>,
 ) -> Result<Value, RuntimeError> {
-    match name {
-        // --- Data / conversion ------------------------------------------------
+    DEFAULT_BUILTINS.call(name, args, env, globals)
+}
 
-        // chr(i64) -> single-character string (low 8 bits)
-        "chr" => match args {
-            [Value::Int(i)] => Ok(Value::Str((*i as u8 as char).to_string())),
-            _ => Err(RuntimeError::TypeError(
-                "chr() expects one integer".to_string(),
-            )),
-        },
+// --- Data / conversion ------------------------------------------------
+
+/// chr(i64) -> single-character string (low 8 bits)
+fn builtin_chr(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [Value::Int(i)] => Ok(Value::Str((*i as u8 as char).to_string())),
+        _ => Err(RuntimeError::TypeError(
+            "chr() expects one integer".to_string(),
+        )),
+    }
+}
+
+/// ascii("c")/ord("c") -> integer code point (requires exactly one character)
+fn builtin_ascii(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [Value::Str(s)] if s.chars().count() == 1 => {
+            Ok(Value::Int(s.chars().next().unwrap() as i64))
+        }
+        _ => Err(RuntimeError::TypeError(
+            "ascii() expects a single character (arity mismatch)".to_string(),
+        )),
+    }
+}
+
+/// hex(i64) -> lowercase hex string
+fn builtin_hex(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [Value::Int(i)] => Ok(Value::Str(format!("{:x}", i))),
+        _ => Err(RuntimeError::TypeError(
+            "hex() expects one integer (arity mismatch)".to_string(),
+        )),
+    }
+}
 
-        // ascii("c") -> integer code point (requires exactly one character)
-        "ascii" => match args {
-            [Value::Str(s)] if s.chars().count() == 1 => {
-                Ok(Value::Int(s.chars().next().unwrap() as i64))
+/// binary(n[, width]) -> binary string; with width, mask & zero-pad
+fn builtin_binary(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [Value::Int(n)] => Ok(Value::Str(format!("{:b}", n))),
+        [Value::Int(n), Value::Int(width)] => {
+            if *width <= 0 {
+                Err(RuntimeError::ValueError(
+                    "binary() width must be positive".to_string(),
+                ))
+            } else {
+                // Mask to width, then print padded.
+                let mask = (1_i64 << width) - 1;
+                Ok(Value::Str(format!(
+                    "{:0width$b}",
+                    n & mask,
+                    width = *width as usize
+                )))
             }
-            _ => Err(RuntimeError::TypeError(
-                "ascii() expects a single character (arity mismatch)".to_string(),
-            )),
-        },
+        }
+        _ => Err(RuntimeError::TypeError(
+            "binary() expects one or two integers (arity mismatch)".to_string(),
+        )),
+    }
+}
+
+/// bits(buf, offset, width) -> unsigned integer formed by `width` bits of
+/// `buf` (a byte buffer: a string's UTF-8 bytes, or a list of byte integers
+/// 0-255) starting at absolute bit `offset`, read MSB-first within each byte.
+/// Complements `hex`/`binary` (which produce bit/byte representations) by
+/// letting packed binary formats/protocols be read back out.
+fn builtin_bits(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    let (buf, offset, width) = match args {
+        [buf, Value::Int(offset), Value::Int(width)] => (buf, *offset, *width),
+        _ => {
+            return Err(RuntimeError::TypeError(
+                "bits() expects (buf, offset, width)".to_string(),
+            ))
+        }
+    };
+
+    if !(1..=64).contains(&width) {
+        return Err(RuntimeError::ValueError(
+            "bits() width must be between 1 and 64".to_string(),
+        ));
+    }
+    if offset < 0 {
+        return Err(RuntimeError::ValueError(
+            "bits() offset must be non-negative".to_string(),
+        ));
+    }
 
-        // hex(i64) -> lowercase hex string
-        "hex" => match args {
-            [Value::Int(i)] => Ok(Value::Str(format!("{:x}", i))),
+    let bytes: Vec<u8> = match buf {
+        Value::Str(s) => s.bytes().collect(),
+        Value::List(list) => list
+            .borrow()
+            .iter()
+            .map(|v| match v {
+                Value::Int(b) if (0..=255).contains(b) => Ok(*b as u8),
+                _ => Err(RuntimeError::TypeError(
+                    "bits() list buffer must contain byte integers (0-255)".to_string(),
+                )),
+            })
+            .collect::<Result<Vec<u8>, RuntimeError>>()?,
+        _ => {
+            return Err(RuntimeError::TypeError(
+                "bits() buf must be a string or list of byte integers".to_string(),
+            ))
+        }
+    };
+
+    let offset = offset as u64;
+    let width = width as u64;
+    if offset + width > bytes.len() as u64 * 8 {
+        return Err(RuntimeError::ValueError(
+            "bits() offset + width exceeds buffer length".to_string(),
+        ));
+    }
+
+    let mut byte = (offset / 8) as usize;
+    let mut bit = (offset % 8) as u32;
+    let mut acc: u64 = 0;
+    for _ in 0..width {
+        let bit_val = (bytes[byte] >> (7 - bit)) & 1;
+        acc = (acc << 1) | bit_val as u64;
+        bit += 1;
+        if bit == 8 {
+            bit = 0;
+            byte += 1;
+        }
+    }
+
+    Ok(Value::Int(acc as i64))
+}
+
+/// length(x)/len(x) for list, dict, or string
+fn builtin_length(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    if args.len() != 1 {
+        Err(RuntimeError::TypeError(
+            "length() expects one positional argument (arity mismatch)".to_string(),
+        ))
+    } else {
+        match &args[0] {
+            Value::List(list) => Ok(Value::Int(list.borrow().len() as i64)),
+            Value::Dict(map) => Ok(Value::Int(map.borrow().len() as i64)),
+            Value::FrozenDict(map) => Ok(Value::Int(map.len() as i64)),
+            Value::Str(s) => Ok(Value::Int(s.chars().count() as i64)),
             _ => Err(RuntimeError::TypeError(
-                "hex() expects one integer (arity mismatch)".to_string(),
+                "length() expects list, dict, or string (type mismatch)".to_string(),
             )),
-        },
-
-        // binary(n[, width]) -> binary string; with width, mask & zero-pad
-        "binary" => match args {
-            [Value::Int(n)] => Ok(Value::Str(format!("{:b}", n))),
-            [Value::Int(n), Value::Int(width)] => {
-                if *width <= 0 {
-                    Err(RuntimeError::ValueError(
-                        "binary() width must be positive".to_string(),
-                    ))
-                } else {
-                    // Mask to width, then print padded.
-                    let mask = (1_i64 << width) - 1;
-                    Ok(Value::Str(format!(
-                        "{:0width$b}",
-                        n & mask,
-                        width = *width as usize
-                    )))
-                }
+        }
+    }
+}
+
+/// str(x) -> human-readable string, via `Value::to_string`
+fn builtin_str(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [v] => Ok(Value::Str(v.to_string())),
+        _ => Err(RuntimeError::TypeError(
+            "str() expects one argument (arity mismatch)".to_string(),
+        )),
+    }
+}
+
+/// int(x) -> integer, via `Value::as_int` coercion rules
+fn builtin_int(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [v] => Ok(Value::Int(v.as_int()?)),
+        _ => Err(RuntimeError::TypeError(
+            "int() expects one argument (arity mismatch)".to_string(),
+        )),
+    }
+}
+
+/// type(x) -> short type-name string (`"int"`, `"float"`, `"str"`, `"bool"`,
+/// `"list"`, `"dict"`, `"none"`)
+fn builtin_type(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [v] => Ok(Value::Str(
+            match v {
+                Value::Int(_) => "int",
+                Value::Float(_) => "float",
+                Value::Str(_) => "str",
+                Value::Bool(_) => "bool",
+                Value::List(_) => "list",
+                Value::Dict(_) | Value::FrozenDict(_) => "dict",
+                Value::None => "none",
             }
-            _ => Err(RuntimeError::TypeError(
-                "binary() expects one or two integers (arity mismatch)".to_string(),
-            )),
-        },
+            .to_string(),
+        )),
+        _ => Err(RuntimeError::TypeError(
+            "type() expects one argument (arity mismatch)".to_string(),
+        )),
+    }
+}
+
+/// upper(s) -> uppercased string
+fn builtin_upper(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [Value::Str(s)] => Ok(Value::Str(s.to_uppercase())),
+        _ => Err(RuntimeError::TypeError(
+            "upper() expects a string (type mismatch)".to_string(),
+        )),
+    }
+}
+
+/// lower(s) -> lowercased string
+fn builtin_lower(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [Value::Str(s)] => Ok(Value::Str(s.to_lowercase())),
+        _ => Err(RuntimeError::TypeError(
+            "lower() expects a string (type mismatch)".to_string(),
+        )),
+    }
+}
+
+/// freeze(dict) -> FrozenDict (shallow copy); idempotent on FrozenDict
+fn builtin_freeze(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [Value::Dict(map)] => {
+            let frozen = map.borrow().clone();
+            Ok(Value::FrozenDict(Rc::new(frozen)))
+        }
+        [Value::FrozenDict(map)] => Ok(Value::FrozenDict(map.clone())),
+        _ => Err(RuntimeError::TypeError(
+            "freeze() expects a dict (type mismatch)".to_string(),
+        )),
+    }
+}
 
-        // length(x) for list or string
-        "length" => {
-            if args.len() != 1 {
-                Err(RuntimeError::TypeError(
-                    "length() expects one positional argument (arity mismatch)".to_string(),
+// --- Math ---------------------------------------------------------------
+//
+// These coerce through `Value::as_float()`/`as_int()` the same way the
+// arithmetic handlers in `ops_arith.rs` do, so `pow(2, 3)` stays an `Int`
+// while `pow(2.0, 3)` promotes to `Float` — they're built as ordinary
+// builtins (like `chr`/`hex`/`bits` above) rather than new VM opcodes, so
+// they don't need bytecode-format or disassembler changes to add.
+
+/// pow(base, exp) -> base raised to exp. Integer base and non-negative
+/// integer exponent stay `Int` (via repeated `checked_mul`, overflowing to 0,
+/// unlike `handle_mul`, which raises `IntegerOverflow`); any `Float` operand, or a negative integer
+/// exponent, promotes to `f64::powf`.
+fn builtin_pow(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [Value::Int(base), Value::Int(exp)] if *exp >= 0 => {
+            let mut acc: i64 = 1;
+            for _ in 0..*exp {
+                acc = acc.checked_mul(*base).unwrap_or(0);
+            }
+            Ok(Value::Int(acc))
+        }
+        [base, exp] => Ok(Value::Float(base.as_float()?.powf(exp.as_float()?))),
+        _ => Err(RuntimeError::TypeError(
+            "pow() expects (base, exp) (arity mismatch)".to_string(),
+        )),
+    }
+}
+
+/// sqrt(x) -> square root as a `Float`. Negative input is a `ValueError`
+/// (there is no complex type to fall back to).
+fn builtin_sqrt(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [v] => {
+            let f = v.as_float()?;
+            if f < 0.0 {
+                Err(RuntimeError::ValueError(
+                    "sqrt() of a negative number is not supported".to_string(),
                 ))
             } else {
-                match &args[0] {
-                    Value::List(list) => Ok(Value::Int(list.borrow().len() as i64)),
-                    Value::Str(s) => Ok(Value::Int(s.chars().count() as i64)),
-                    _ => Err(RuntimeError::TypeError(
-                        "length() expects list or string (type mismatch)".to_string(),
-                    )),
-                }
+                Ok(Value::Float(f.sqrt()))
             }
         }
+        _ => Err(RuntimeError::TypeError(
+            "sqrt() expects one argument (arity mismatch)".to_string(),
+        )),
+    }
+}
 
-        // freeze(dict) -> FrozenDict (shallow copy); idempotent on FrozenDict
-        "freeze" => match args {
-            [Value::Dict(map)] => {
-                let frozen = map.borrow().clone();
-                Ok(Value::FrozenDict(Rc::new(frozen)))
+/// floor(x) -> largest integer <= x, as an `Int`
+fn builtin_floor(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [v] => Ok(Value::Int(v.as_float()?.floor() as i64)),
+        _ => Err(RuntimeError::TypeError(
+            "floor() expects one argument (arity mismatch)".to_string(),
+        )),
+    }
+}
+
+/// ceil(x) -> smallest integer >= x, as an `Int`
+fn builtin_ceil(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [v] => Ok(Value::Int(v.as_float()?.ceil() as i64)),
+        _ => Err(RuntimeError::TypeError(
+            "ceil() expects one argument (arity mismatch)".to_string(),
+        )),
+    }
+}
+
+/// round(x) -> nearest integer (half away from zero, matching `f64::round`),
+/// as an `Int`
+fn builtin_round(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [v] => Ok(Value::Int(v.as_float()?.round() as i64)),
+        _ => Err(RuntimeError::TypeError(
+            "round() expects one argument (arity mismatch)".to_string(),
+        )),
+    }
+}
+
+/// abs(x) -> absolute value, preserving `Int`/`Float`-ness of the input
+fn builtin_abs(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [Value::Float(f)] => Ok(Value::Float(f.abs())),
+        [v] => Ok(Value::Int(v.as_int()?.abs())),
+        _ => Err(RuntimeError::TypeError(
+            "abs() expects one argument (arity mismatch)".to_string(),
+        )),
+    }
+}
+
+/// min(a, b) -> the smaller of two operands, numerically compared the same
+/// way `handle_lt` does (promoting to float if either side is one); returns
+/// whichever original `Value` won so non-numeric types round-trip untouched.
+fn builtin_min(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [a, b] => {
+            let a_le_b = match (a, b) {
+                (Value::Str(sa), Value::Str(sb)) => sa <= sb,
+                (Value::Float(_), _) | (_, Value::Float(_)) => a.as_float()? <= b.as_float()?,
+                _ => a.as_int()? <= b.as_int()?,
+            };
+            if a_le_b {
+                Ok(a.clone())
+            } else {
+                Ok(b.clone())
             }
-            [Value::FrozenDict(map)] => Ok(Value::FrozenDict(map.clone())),
-            _ => Err(RuntimeError::TypeError(
-                "freeze() expects a dict (type mismatch)".to_string(),
-            )),
-        },
+        }
+        _ => Err(RuntimeError::TypeError(
+            "min() expects two arguments (arity mismatch)".to_string(),
+        )),
+    }
+}
 
-        // --- Errors -----------------------------------------------------------
+/// max(a, b) -> the larger of two operands; see `min()` for comparison rules.
+fn builtin_max(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [a, b] => {
+            let a_ge_b = match (a, b) {
+                (Value::Str(sa), Value::Str(sb)) => sa >= sb,
+                (Value::Float(_), _) | (_, Value::Float(_)) => a.as_float()? >= b.as_float()?,
+                _ => a.as_int()? >= b.as_int()?,
+            };
+            if a_ge_b {
+                Ok(a.clone())
+            } else {
+                Ok(b.clone())
+            }
+        }
+        _ => Err(RuntimeError::TypeError(
+            "max() expects two arguments (arity mismatch)".to_string(),
+        )),
+    }
+}
 
-        // panic("message") -> directly raise RuntimeError::Raised
-        "panic" => match args {
-            [Value::Str(msg)] => Err(RuntimeError::Raised(msg.clone())),
-            _ => Err(RuntimeError::TypeError(
-                "panic() expects a string (type mismatch)".to_string(),
-            )),
-        },
-
-        // raise("message") -> synthesize a VM raise of ErrorKind::Generic
-        //
-        // We reuse the VM’s raise path to ensure handlers (SetupExcept) can catch it.
-        "raise" => match args {
-            [Value::Str(msg)] => {
-                let mut stack = vec![Value::Str(msg.clone())];
-                ops_control::handle_raise(&ErrorKind::Generic, &mut stack)?;
-                unreachable!()
+// --- Randomness -----------------------------------------------------------
+//
+// A small, seedable SplitMix64 generator, stored process-wide the same way
+// `LAST_IO_ERROR`/`FILE_HANDLES` above are: a `Lazy<Mutex<u64>>` holding just
+// the 64-bit generator state. Deterministic by default (a fixed seed, not
+// drawn from OS entropy) so a script's random sequence is reproducible
+// without calling `seed()` first, and `seed(n)` lets a test pin it to a
+// specific sequence explicitly.
+
+/// Default seed the generator starts from before any `seed()` call.
+const DEFAULT_PRNG_SEED: u64 = 0x2545_F491_4F6C_DD1D;
+
+static PRNG_STATE: Lazy<Mutex<u64>> = Lazy::new(|| Mutex::new(DEFAULT_PRNG_SEED));
+
+/// Advance `state` and return the next 64-bit output (SplitMix64).
+fn splitmix64_next(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// random() -> Float in [0.0, 1.0), drawn from the top 53 bits of the next
+/// generator output (the usual construction for a uniform `f64` from a
+/// 64-bit generator).
+fn builtin_random(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    if !args.is_empty() {
+        return Err(RuntimeError::TypeError(
+            "random() expects no arguments (arity mismatch)".to_string(),
+        ));
+    }
+    let mut state = PRNG_STATE.lock().unwrap();
+    let bits = splitmix64_next(&mut state) >> 11;
+    Ok(Value::Float(bits as f64 / (1u64 << 53) as f64))
+}
+
+/// randint(lo, hi) -> Int uniformly drawn from `[lo, hi]` (inclusive on both
+/// ends). `lo > hi` is a `ValueError`.
+fn builtin_randint(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [Value::Int(lo), Value::Int(hi)] => {
+            if lo > hi {
+                return Err(RuntimeError::ValueError(
+                    "randint() expects lo <= hi".to_string(),
+                ));
             }
-            _ => Err(RuntimeError::TypeError(
-                "raise() expects a string (type mismatch)".to_string(),
-            )),
-        },
-
-        // --- Filesystem -------------------------------------------------------
-
-        // read_file("path") -> String content; resolves relative to current_dir
-        "read_file" => match args {
-            [Value::Str(path)] => {
-                let path_buf = resolve_path(path, env, globals);
-                match fs::read_to_string(&path_buf) {
-                    Ok(content) => Ok(Value::Str(content)),
-                    // Use ModuleImportError because this is commonly used by importers.
-                    Err(err) => Err(RuntimeError::ModuleImportError(format!(
+            // `hi - lo` can overflow i64 at the extremes (e.g. lo = i64::MIN,
+            // hi = i64::MAX), so widen to i128 before computing the range.
+            // The final `lo + offset` is done with wrapping_add: `offset` is
+            // always < 2^64, so reinterpreting it as i64 and wrapping back
+            // onto `lo` reproduces the same in-range result the widened
+            // arithmetic computed, without panicking on the intermediate
+            // two's-complement overflow in debug builds.
+            let range = (*hi as i128 - *lo as i128 + 1) as u128;
+            let mut state = PRNG_STATE.lock().unwrap();
+            let offset = (splitmix64_next(&mut state) as u128) % range;
+            Ok(Value::Int(lo.wrapping_add(offset as i64)))
+        }
+        _ => Err(RuntimeError::TypeError(
+            "randint() expects two integers (arity mismatch)".to_string(),
+        )),
+    }
+}
+
+/// one_in(n) -> Bool, true with probability `1/n`. `n == 1` is always true;
+/// `n <= 0` is a `ValueError` (there's no well-defined probability).
+fn builtin_one_in(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [Value::Int(n)] if *n == 1 => Ok(Value::Bool(true)),
+        [Value::Int(n)] if *n > 1 => {
+            let mut state = PRNG_STATE.lock().unwrap();
+            Ok(Value::Bool(splitmix64_next(&mut state) % (*n as u64) == 0))
+        }
+        [Value::Int(_)] => Err(RuntimeError::ValueError(
+            "one_in() expects a positive n".to_string(),
+        )),
+        _ => Err(RuntimeError::TypeError(
+            "one_in() expects one integer (arity mismatch)".to_string(),
+        )),
+    }
+}
+
+/// seed(n) -> None; reset the generator state to `n`, so subsequent
+/// `random()`/`randint()`/`one_in()` calls produce a reproducible sequence.
+fn builtin_seed(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [Value::Int(n)] => {
+            *PRNG_STATE.lock().unwrap() = *n as u64;
+            Ok(Value::None)
+        }
+        _ => Err(RuntimeError::TypeError(
+            "seed() expects one integer (arity mismatch)".to_string(),
+        )),
+    }
+}
+
+// --- Collections --------------------------------------------------------
+
+/// keys(dict) -> list of keys, each in its original type (int/string/bool)
+fn builtin_keys(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [Value::Dict(map)] => Ok(Value::new_list(
+            map.borrow().keys().map(DictKey::to_value).collect(),
+        )),
+        [Value::FrozenDict(map)] => Ok(Value::new_list(
+            map.keys().map(DictKey::to_value).collect(),
+        )),
+        _ => Err(RuntimeError::TypeError(
+            "keys() expects a dict (type mismatch)".to_string(),
+        )),
+    }
+}
+
+/// values(dict) -> list of values
+fn builtin_values(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [Value::Dict(map)] => Ok(Value::new_list(map.borrow().values().cloned().collect())),
+        [Value::FrozenDict(map)] => Ok(Value::new_list(map.values().cloned().collect())),
+        _ => Err(RuntimeError::TypeError(
+            "values() expects a dict (type mismatch)".to_string(),
+        )),
+    }
+}
+
+/// range(end) / range(start, end) / range(start, end, step) -> list of ints
+fn builtin_range(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    let (start, end, step) = match args {
+        [Value::Int(end)] => (0, *end, 1),
+        [Value::Int(start), Value::Int(end)] => (*start, *end, 1),
+        [Value::Int(start), Value::Int(end), Value::Int(step)] => (*start, *end, *step),
+        _ => {
+            return Err(RuntimeError::TypeError(
+                "range() expects one to three integers (arity mismatch)".to_string(),
+            ))
+        }
+    };
+    if step == 0 {
+        return Err(RuntimeError::ValueError(
+            "range() step must not be zero".to_string(),
+        ));
+    }
+    let mut out = Vec::new();
+    let mut i = start;
+    if step > 0 {
+        while i < end {
+            out.push(Value::Int(i));
+            i += step;
+        }
+    } else {
+        while i > end {
+            out.push(Value::Int(i));
+            i += step;
+        }
+    }
+    Ok(Value::new_list(out))
+}
+
+/// map("builtin_name", list) -> list with `builtin_name(x)` applied to each element.
+///
+/// The callback is looked up in the default builtin registry, not the VM's
+/// user-function table (builtins have no access to `funcs`/the call stack),
+/// so this only composes with other builtins, e.g. `map("str", xs)`.
+fn builtin_map(args: &[Value], env: &HashMap<String, Value>, globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [Value::Str(f), Value::List(list)] => {
+            let items = list.borrow().clone();
+            let mapped: Result<Vec<Value>, RuntimeError> = items
+                .iter()
+                .map(|v| call_builtin(f, std::slice::from_ref(v), env, globals))
+                .collect();
+            Ok(Value::new_list(mapped?))
+        }
+        _ => Err(RuntimeError::TypeError(
+            "map() expects a builtin name and a list".to_string(),
+        )),
+    }
+}
+
+/// filter("builtin_name", list) -> list of elements where `builtin_name(x)` is truthy.
+///
+/// Same callback restriction as [`builtin_map`].
+fn builtin_filter(args: &[Value], env: &HashMap<String, Value>, globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [Value::Str(f), Value::List(list)] => {
+            let items = list.borrow().clone();
+            let mut kept = Vec::new();
+            for v in items {
+                if call_builtin(f, std::slice::from_ref(&v), env, globals)?.as_bool() {
+                    kept.push(v);
+                }
+            }
+            Ok(Value::new_list(kept))
+        }
+        _ => Err(RuntimeError::TypeError(
+            "filter() expects a builtin name and a list".to_string(),
+        )),
+    }
+}
+
+/// reduce("builtin_name", list, init) -> fold `builtin_name(acc, x)` over the list.
+///
+/// Same callback restriction as [`builtin_map`]; `builtin_name` must accept
+/// two positional arguments (accumulator, element).
+fn builtin_reduce(args: &[Value], env: &HashMap<String, Value>, globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [Value::Str(f), Value::List(list), init] => {
+            let items = list.borrow().clone();
+            let mut acc = init.clone();
+            for v in items {
+                acc = call_builtin(f, &[acc, v], env, globals)?;
+            }
+            Ok(acc)
+        }
+        _ => Err(RuntimeError::TypeError(
+            "reduce() expects a builtin name, a list, and an initial value".to_string(),
+        )),
+    }
+}
+
+// --- Errors -----------------------------------------------------------
+
+/// panic("message") -> directly raise RuntimeError::Raised
+fn builtin_panic(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [Value::Str(msg)] => Err(RuntimeError::Raised(msg.clone())),
+        _ => Err(RuntimeError::TypeError(
+            "panic() expects a string (type mismatch)".to_string(),
+        )),
+    }
+}
+
+/// raise("message") -> synthesize a VM raise of ErrorKind::Generic
+///
+/// We reuse the VM’s raise path to ensure handlers (SetupExcept) can catch it.
+fn builtin_raise(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [Value::Str(msg)] => {
+            let mut stack = vec![Value::Str(msg.clone())];
+            ops_control::handle_raise(&ErrorKind::Generic, &mut stack)?;
+            unreachable!()
+        }
+        _ => Err(RuntimeError::TypeError(
+            "raise() expects a string (type mismatch)".to_string(),
+        )),
+    }
+}
+
+// --- Filesystem -------------------------------------------------------
+
+/// read_file("path") -> String content; resolves relative to current_dir
+fn builtin_read_file(args: &[Value], env: &HashMap<String, Value>, globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [Value::Str(path)] => {
+            let path_buf = resolve_path(path, env, globals);
+            match fs::read_to_string(&path_buf) {
+                Ok(content) => Ok(Value::Str(content)),
+                // Use ModuleImportError because this is commonly used by importers.
+                Err(err) => {
+                    let err = record_io_error(err);
+                    Err(RuntimeError::ModuleImportError(format!(
                         "failed to read '{}': {}",
                         path_buf.display(),
                         err
-                    ))),
+                    )))
                 }
             }
-            _ => Err(RuntimeError::TypeError(
-                "read_file() expects a file path".to_string(),
-            )),
-        },
-
-        // file_open("path", "r|rb|w|wb|a|ab") -> handle (int)
-        "file_open" => match args {
-            [Value::Str(path), Value::Str(mode)] => {
-                let path_buf = resolve_path(path, env, globals);
-                let mut opts = OpenOptions::new();
-                let binary = mode.contains('b');
-                // Configure options based on mode; we support read/write/append.
-                match mode.as_str() {
-                    "r" | "rb" => {
-                        opts.read(true);
-                    }
-                    "w" | "wb" => {
-                        opts.write(true).create(true).truncate(true);
-                    }
-                    "a" | "ab" => {
-                        opts.write(true).create(true).append(true);
-                    }
-                    _ => {
-                        return Err(RuntimeError::ValueError(
-                            "invalid file mode".to_string(),
-                        ));
-                    }
+        }
+        _ => Err(RuntimeError::TypeError(
+            "read_file() expects a file path".to_string(),
+        )),
+    }
+}
+
+/// write_file("path", "content") -> None; overwrites, resolves relative to current_dir
+fn builtin_write_file(args: &[Value], env: &HashMap<String, Value>, globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [Value::Str(path), Value::Str(content)] => {
+            let path_buf = resolve_path(path, env, globals);
+            fs::write(&path_buf, content).map_err(|err| {
+                RuntimeError::ValueError(format!("failed to write '{}': {}", path_buf.display(), err))
+            })?;
+            Ok(Value::None)
+        }
+        _ => Err(RuntimeError::TypeError(
+            "write_file() expects a path and string content".to_string(),
+        )),
+    }
+}
+
+/// read_bytes("path") -> List[Int 0..255]; one-shot binary read, unlike
+/// `read_file` (which decodes as UTF-8 text), so non-UTF-8 files (images,
+/// packed records) round-trip without corruption.
+fn builtin_read_bytes(args: &[Value], env: &HashMap<String, Value>, globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [Value::Str(path)] => {
+            let path_buf = resolve_path(path, env, globals);
+            match fs::read(&path_buf) {
+                Ok(bytes) => {
+                    let list: Vec<Value> = bytes.into_iter().map(|b| Value::Int(b as i64)).collect();
+                    Ok(Value::new_list(list))
                 }
-                match opts.open(&path_buf) {
-                    Ok(file) => {
-                        let handle = NEXT_FD.fetch_add(1, Ordering::SeqCst);
-                        FILE_HANDLES
-                            .lock()
-                            .unwrap()
-                            .insert(handle, FileEntry { file, binary });
-                        Ok(Value::Int(handle as i64))
-                    }
-                    Err(err) => Err(RuntimeError::ValueError(format!(
+                Err(err) => Err(RuntimeError::ModuleImportError(format!(
+                    "failed to read '{}': {}",
+                    path_buf.display(),
+                    err
+                ))),
+            }
+        }
+        _ => Err(RuntimeError::TypeError(
+            "read_bytes() expects a file path".to_string(),
+        )),
+    }
+}
+
+/// write_bytes("path", list) -> None; one-shot binary write, the counterpart
+/// to `read_bytes`. `list` must contain byte integers (0-255).
+fn builtin_write_bytes(args: &[Value], env: &HashMap<String, Value>, globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [Value::Str(path), Value::List(list)] => {
+            let path_buf = resolve_path(path, env, globals);
+            let bytes = list
+                .borrow()
+                .iter()
+                .map(|v| match v {
+                    Value::Int(i) if *i >= 0 && *i <= 255 => Ok(*i as u8),
+                    _ => Err(RuntimeError::TypeError(
+                        "write_bytes() expects bytes 0-255".to_string(),
+                    )),
+                })
+                .collect::<Result<Vec<u8>, RuntimeError>>()?;
+            fs::write(&path_buf, &bytes).map_err(|err| {
+                RuntimeError::ValueError(format!("failed to write '{}': {}", path_buf.display(), err))
+            })?;
+            Ok(Value::None)
+        }
+        _ => Err(RuntimeError::TypeError(
+            "write_bytes() expects a path and a list of byte integers".to_string(),
+        )),
+    }
+}
+
+/// file_open("path", "r|rb|w|wb|a|ab") -> handle (int)
+fn builtin_file_open(args: &[Value], env: &HashMap<String, Value>, globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [Value::Str(path), Value::Str(mode)] => {
+            if FILE_HANDLES.lock().unwrap().len() >= MAX_OPEN_FILES.load(Ordering::SeqCst) as usize {
+                return Err(RuntimeError::ValueError(
+                    "too many open files".to_string(),
+                ));
+            }
+            let path_buf = resolve_path(path, env, globals);
+            let mut opts = OpenOptions::new();
+            let binary = mode.contains('b');
+            // Configure options based on mode; we support read/write/append.
+            match mode.as_str() {
+                "r" | "rb" => {
+                    opts.read(true);
+                }
+                "w" | "wb" => {
+                    opts.write(true).create(true).truncate(true);
+                }
+                "a" | "ab" => {
+                    opts.write(true).create(true).append(true);
+                }
+                _ => {
+                    return Err(RuntimeError::ValueError(
+                        "invalid file mode".to_string(),
+                    ));
+                }
+            }
+            match opts.open(&path_buf) {
+                Ok(file) => {
+                    let handle = NEXT_FD.fetch_add(1, Ordering::SeqCst);
+                    FILE_HANDLES
+                        .lock()
+                        .unwrap()
+                        .insert(handle, FileEntry::new(file, binary));
+                    Ok(Value::Int(handle as i64))
+                }
+                Err(err) => {
+                    let err = record_io_error(err);
+                    Err(RuntimeError::ValueError(format!(
                         "cannot open '{}': {}",
                         path_buf.display(),
                         err
-                    ))),
+                    )))
                 }
             }
-            _ => Err(RuntimeError::TypeError(
-                "file_open() expects path and mode".to_string(),
-            )),
-        },
-
-        // file_read(handle) -> String for text; List[Int bytes] for binary
-        "file_read" => match args {
-            [Value::Int(handle)] => {
-                let mut table = FILE_HANDLES.lock().unwrap();
-                if let Some(entry) = table.get_mut(&(*handle as i32)) {
-                    if entry.binary {
-                        // Binary: read whole file to Vec<u8>, return as list of Ints [0..255]
-                        let mut buf = Vec::new();
-                        entry
-                            .file
-                            .read_to_end(&mut buf)
-                            .map_err(|e| RuntimeError::ValueError(e.to_string()))?;
-                        let list: Vec<Value> =
-                            buf.into_iter().map(|b| Value::Int(b as i64)).collect();
-                        Ok(Value::List(Rc::new(RefCell::new(list))))
-                    } else {
-                        // Text: read whole file to String
-                        let mut s = String::new();
-                        entry
-                            .file
-                            .read_to_string(&mut s)
-                            .map_err(|e| RuntimeError::ValueError(e.to_string()))?;
-                        Ok(Value::Str(s))
-                    }
+        }
+        _ => Err(RuntimeError::TypeError(
+            "file_open() expects path and mode".to_string(),
+        )),
+    }
+}
+
+/// file_read(handle) -> String for text; List[Int bytes] for binary
+fn builtin_file_read(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [Value::Int(handle)] => {
+            let mut table = FILE_HANDLES.lock().unwrap();
+            if let Some(entry) = table.get_mut(&(*handle as i32)) {
+                if entry.binary {
+                    // Binary: read whole file to Vec<u8>, return as list of Ints [0..255]
+                    let mut buf = Vec::new();
+                    entry
+                        .raw_file()?
+                        .read_to_end(&mut buf)
+                        .map_err(|e| RuntimeError::ValueError(record_io_error(e).to_string()))?;
+                    let list: Vec<Value> =
+                        buf.into_iter().map(|b| Value::Int(b as i64)).collect();
+                    Ok(Value::new_list(list))
                 } else {
-                    Err(RuntimeError::ValueError("invalid file handle".to_string()))
+                    // Text: read whole file to String
+                    let mut s = String::new();
+                    entry
+                        .raw_file()?
+                        .read_to_string(&mut s)
+                        .map_err(|e| RuntimeError::ValueError(record_io_error(e).to_string()))?;
+                    Ok(Value::Str(s))
                 }
+            } else {
+                Err(RuntimeError::ValueError("invalid file handle".to_string()))
             }
-            _ => Err(RuntimeError::TypeError(
-                "file_read() expects a handle".to_string(),
-            )),
-        },
-
-        // file_write(handle, data) -> Int bytes written
-        // - Text handle expects String
-        // - Binary handle expects List[Int 0..255]
-        "file_write" => match args {
-            // Text write
-            [Value::Int(handle), Value::Str(data)] => {
-                let mut table = FILE_HANDLES.lock().unwrap();
-                if let Some(entry) = table.get_mut(&(*handle as i32)) {
-                    if entry.binary {
-                        return Err(RuntimeError::TypeError(
-                            "file_write() binary handle expects list".to_string(),
-                        ));
-                    }
-                    entry
-                        .file
-                        .write_all(data.as_bytes())
-                        .map_err(|e| RuntimeError::ValueError(e.to_string()))?;
-                    Ok(Value::Int(data.as_bytes().len() as i64))
-                } else {
-                    Err(RuntimeError::ValueError("invalid file handle".to_string()))
+        }
+        _ => Err(RuntimeError::TypeError(
+            "file_read() expects a handle".to_string(),
+        )),
+    }
+}
+
+/// file_write(handle, data) -> Int bytes written
+/// - Text handle expects String
+/// - Binary handle expects List[Int 0..255]
+fn builtin_file_write(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        // Text write
+        [Value::Int(handle), Value::Str(data)] => {
+            let mut table = FILE_HANDLES.lock().unwrap();
+            if let Some(entry) = table.get_mut(&(*handle as i32)) {
+                if entry.binary {
+                    return Err(RuntimeError::TypeError(
+                        "file_write() binary handle expects list".to_string(),
+                    ));
                 }
+                entry
+                    .raw_file()?
+                    .write_all(data.as_bytes())
+                    .map_err(|e| RuntimeError::ValueError(record_io_error(e).to_string()))?;
+                Ok(Value::Int(data.as_bytes().len() as i64))
+            } else {
+                Err(RuntimeError::ValueError("invalid file handle".to_string()))
             }
-            // Binary write
-            [Value::Int(handle), Value::List(list)] => {
-                let mut table = FILE_HANDLES.lock().unwrap();
-                if let Some(entry) = table.get_mut(&(*handle as i32)) {
-                    if !entry.binary {
-                        return Err(RuntimeError::TypeError(
-                            "file_write() text handle expects string".to_string(),
-                        ));
+        }
+        // Binary write
+        [Value::Int(handle), Value::List(list)] => {
+            let mut table = FILE_HANDLES.lock().unwrap();
+            if let Some(entry) = table.get_mut(&(*handle as i32)) {
+                if !entry.binary {
+                    return Err(RuntimeError::TypeError(
+                        "file_write() text handle expects string".to_string(),
+                    ));
+                }
+                // Validate and pack list of ints into bytes
+                let vec = list
+                    .borrow()
+                    .iter()
+                    .map(|v| match v {
+                        Value::Int(i) if *i >= 0 && *i <= 255 => Ok(*i as u8),
+                        _ => Err(RuntimeError::TypeError(
+                            "file_write() expects bytes 0-255".to_string(),
+                        )),
+                    })
+                    .collect::<Result<Vec<u8>, RuntimeError>>()?;
+                entry
+                    .raw_file()?
+                    .write_all(&vec)
+                    .map_err(|e| RuntimeError::ValueError(record_io_error(e).to_string()))?;
+                Ok(Value::Int(vec.len() as i64))
+            } else {
+                Err(RuntimeError::ValueError("invalid file handle".to_string()))
+            }
+        }
+        _ => Err(RuntimeError::TypeError(
+            "file_write() expects handle and data".to_string(),
+        )),
+    }
+}
+
+/// file_close(handle) -> None
+fn builtin_file_close(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [Value::Int(handle)] => {
+            let mut table = FILE_HANDLES.lock().unwrap();
+            if table.remove(&(*handle as i32)).is_some() {
+                Ok(Value::None)
+            } else {
+                Err(RuntimeError::ValueError("invalid file handle".to_string()))
+            }
+        }
+        _ => Err(RuntimeError::TypeError(
+            "file_close() expects handle".to_string(),
+        )),
+    }
+}
+
+/// file_readline(handle) -> Str | None; reads the next line from a text
+/// handle (stripping a trailing `\r\n`/`\n`, matching `read_line()`'s
+/// stdin convention), or `None` at EOF. Lazily switches the handle into
+/// buffered mode via `FileEntry::buffered` — after this call, the handle can
+/// no longer be used with `file_read`/`file_write` (see `FileEntry::raw_file`).
+fn builtin_file_readline(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [Value::Int(handle)] => {
+            let mut table = FILE_HANDLES.lock().unwrap();
+            if let Some(entry) = table.get_mut(&(*handle as i32)) {
+                if entry.binary {
+                    return Err(RuntimeError::TypeError(
+                        "file_readline() expects a text handle".to_string(),
+                    ));
+                }
+                let mut line = String::new();
+                let n = entry
+                    .buffered()
+                    .read_line(&mut line)
+                    .map_err(|e| RuntimeError::ValueError(e.to_string()))?;
+                if n == 0 {
+                    return Ok(Value::None);
+                }
+                if line.ends_with('\n') {
+                    line.pop();
+                    if line.ends_with('\r') {
+                        line.pop();
                     }
-                    // Validate and pack list of ints into bytes
-                    let vec = list
-                        .borrow()
-                        .iter()
-                        .map(|v| match v {
-                            Value::Int(i) if *i >= 0 && *i <= 255 => Ok(*i as u8),
-                            _ => Err(RuntimeError::TypeError(
-                                "file_write() expects bytes 0-255".to_string(),
-                            )),
-                        })
-                        .collect::<Result<Vec<u8>, RuntimeError>>()?;
-                    entry
-                        .file
-                        .write_all(&vec)
-                        .map_err(|e| RuntimeError::ValueError(e.to_string()))?;
-                    Ok(Value::Int(vec.len() as i64))
-                } else {
-                    Err(RuntimeError::ValueError("invalid file handle".to_string()))
                 }
+                Ok(Value::Str(line))
+            } else {
+                Err(RuntimeError::ValueError("invalid file handle".to_string()))
             }
-            _ => Err(RuntimeError::TypeError(
-                "file_write() expects handle and data".to_string(),
-            )),
-        },
-
-        // file_close(handle) -> None
-        "file_close" => match args {
-            [Value::Int(handle)] => {
-                let mut table = FILE_HANDLES.lock().unwrap();
-                if table.remove(&(*handle as i32)).is_some() {
-                    Ok(Value::None)
-                } else {
-                    Err(RuntimeError::ValueError("invalid file handle".to_string()))
+        }
+        _ => Err(RuntimeError::TypeError(
+            "file_readline() expects a handle".to_string(),
+        )),
+    }
+}
+
+/// file_readlines(handle) -> List[Str]; reads every remaining line from a
+/// text handle via repeated `file_readline` until EOF.
+fn builtin_file_readlines(args: &[Value], env: &HashMap<String, Value>, globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [Value::Int(_)] => {
+            let mut lines = Vec::new();
+            loop {
+                match builtin_file_readline(args, env, globals)? {
+                    Value::Str(line) => lines.push(Value::Str(line)),
+                    Value::None => break,
+                    _ => unreachable!("file_readline() only returns Str or None"),
                 }
             }
-            _ => Err(RuntimeError::TypeError(
-                "file_close() expects handle".to_string(),
-            )),
-        },
+            Ok(Value::new_list(lines))
+        }
+        _ => Err(RuntimeError::TypeError(
+            "file_readlines() expects a handle".to_string(),
+        )),
+    }
+}
 
-        // file_exists("path") -> Bool
-        "file_exists" => match args {
-            [Value::Str(path)] => {
-                let path_buf = resolve_path(path, env, globals);
-                Ok(Value::Bool(path_buf.exists()))
+/// file_read_until(handle, delim) -> List[Int]; reads a binary handle up to
+/// and including the next byte equal to `delim` (0-255), or to EOF,
+/// returning the bytes read (empty list at EOF). Backed by `BufRead::read_until`,
+/// via the same lazily-buffered handle as `file_readline`.
+fn builtin_file_read_until(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [Value::Int(handle), Value::Int(delim)] if (0..=255).contains(delim) => {
+            let mut table = FILE_HANDLES.lock().unwrap();
+            if let Some(entry) = table.get_mut(&(*handle as i32)) {
+                if !entry.binary {
+                    return Err(RuntimeError::TypeError(
+                        "file_read_until() expects a binary handle".to_string(),
+                    ));
+                }
+                let mut buf = Vec::new();
+                entry
+                    .buffered()
+                    .read_until(*delim as u8, &mut buf)
+                    .map_err(|e| RuntimeError::ValueError(e.to_string()))?;
+                let list: Vec<Value> = buf.into_iter().map(|b| Value::Int(b as i64)).collect();
+                Ok(Value::new_list(list))
+            } else {
+                Err(RuntimeError::ValueError("invalid file handle".to_string()))
             }
-            _ => Err(RuntimeError::TypeError(
-                "file_exists() expects a path".to_string(),
-            )),
-        },
+        }
+        [Value::Int(_), Value::Int(_)] => Err(RuntimeError::ValueError(
+            "file_read_until() delimiter must be a byte (0-255)".to_string(),
+        )),
+        _ => Err(RuntimeError::TypeError(
+            "file_read_until() expects a handle and a delimiter byte".to_string(),
+        )),
+    }
+}
 
-        // call_builtin("name", [args...]) -> Value (delegates to another builtin)
-        "call_builtin" => match args {
-            [Value::Str(inner), Value::List(list)] => {
-                let inner_args = list.borrow().clone();
-                call_builtin(inner, &inner_args, env, globals)
+/// file_copy(src_handle, dst_handle) -> Int bytes copied; streams from one
+/// open handle to another via `std::io::copy`, without ever materializing
+/// the contents as an OMG `Value` (unlike `file_read` + `file_write`, which
+/// loads the whole source into the interpreter heap as a `List[Int]`).
+/// Works at the byte level regardless of text/binary mode. Both handles are
+/// briefly removed from the FD table and reinserted afterward, since the
+/// table can't hand out two simultaneous mutable borrows of its own entries
+/// otherwise. The destination's file position advances by the copy; the
+/// source's does too, since `std::io::copy` reads it to completion.
+fn builtin_file_copy(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [Value::Int(src), Value::Int(dst)] => {
+            if src == dst {
+                return Err(RuntimeError::ValueError(
+                    "file_copy() source and destination handles must differ".to_string(),
+                ));
             }
-            _ => Err(RuntimeError::TypeError(
-                "call_builtin() expects a name and argument list".to_string(),
-            )),
-        },
+            let mut table = FILE_HANDLES.lock().unwrap();
+            let mut src_entry = table
+                .remove(&(*src as i32))
+                .ok_or_else(|| RuntimeError::ValueError("invalid file handle".to_string()))?;
+            let mut dst_entry = match table.remove(&(*dst as i32)) {
+                Some(entry) => entry,
+                None => {
+                    table.insert(*src as i32, src_entry);
+                    return Err(RuntimeError::ValueError("invalid file handle".to_string()));
+                }
+            };
+
+            let result = (|| -> Result<u64, RuntimeError> {
+                let src_file = src_entry.raw_file()?;
+                let dst_file = dst_entry.raw_file()?;
+                std::io::copy(src_file, dst_file).map_err(|e| RuntimeError::ValueError(e.to_string()))
+            })();
+
+            table.insert(*src as i32, src_entry);
+            table.insert(*dst as i32, dst_entry);
+
+            result.map(|n| Value::Int(n as i64))
+        }
+        _ => Err(RuntimeError::TypeError(
+            "file_copy() expects a source and destination handle".to_string(),
+        )),
+    }
+}
+
+/// file_seek(handle, offset, whence) -> Int new absolute position.
+///
+/// `whence` follows the C `fseek`/POSIX convention: `0` = from the start,
+/// `1` = from the current position, `2` = from the end. Goes through
+/// [`FileEntry::seek`] rather than reaching into the underlying `fs::File`
+/// directly, so a handle already switched into buffered line-read mode has
+/// its `BufReader`'s internal buffer correctly discarded instead of serving
+/// stale bytes after the jump.
+fn builtin_file_seek(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [Value::Int(handle), Value::Int(offset), Value::Int(whence)] => {
+            let pos = match whence {
+                0 => {
+                    if *offset < 0 {
+                        return Err(RuntimeError::ValueError(
+                            "file_seek() offset must be non-negative when whence is 0 (start)".to_string(),
+                        ));
+                    }
+                    SeekFrom::Start(*offset as u64)
+                }
+                1 => SeekFrom::Current(*offset),
+                2 => SeekFrom::End(*offset),
+                _ => {
+                    return Err(RuntimeError::ValueError(
+                        "file_seek() whence must be 0 (start), 1 (current), or 2 (end)".to_string(),
+                    ))
+                }
+            };
+
+            let mut table = FILE_HANDLES.lock().unwrap();
+            let entry = table
+                .get_mut(&(*handle as i32))
+                .ok_or_else(|| RuntimeError::ValueError("invalid file handle".to_string()))?;
+            entry
+                .seek(pos)
+                .map(|n| Value::Int(n as i64))
+                .map_err(|e| RuntimeError::ValueError(e.to_string()))
+        }
+        _ => Err(RuntimeError::TypeError(
+            "file_seek() expects a handle, an offset, and a whence (0, 1, or 2)".to_string(),
+        )),
+    }
+}
+
+/// file_tell(handle) -> Int current position; implemented as a zero-offset
+/// `SeekFrom::Current` seek, the standard trick for "where am I" when the
+/// only positioning primitive is `Seek::seek`.
+fn builtin_file_tell(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [Value::Int(handle)] => {
+            let mut table = FILE_HANDLES.lock().unwrap();
+            let entry = table
+                .get_mut(&(*handle as i32))
+                .ok_or_else(|| RuntimeError::ValueError("invalid file handle".to_string()))?;
+            entry
+                .seek(SeekFrom::Current(0))
+                .map(|n| Value::Int(n as i64))
+                .map_err(|e| RuntimeError::ValueError(e.to_string()))
+        }
+        _ => Err(RuntimeError::TypeError("file_tell() expects a handle".to_string())),
+    }
+}
+
+/// file_exists("path") -> Bool
+fn builtin_file_exists(args: &[Value], env: &HashMap<String, Value>, globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [Value::Str(path)] => {
+            let path_buf = resolve_path(path, env, globals);
+            Ok(Value::Bool(path_buf.exists()))
+        }
+        _ => Err(RuntimeError::TypeError(
+            "file_exists() expects a path".to_string(),
+        )),
+    }
+}
+
+/// read_line() -> Str | None; reads one line from stdin, stripping the
+/// trailing newline. Returns `Value::None` at EOF (no bytes read) so a
+/// program can loop `while (line := read_line()) != none { ... }`-style
+/// without a separate "more input?" builtin.
+fn builtin_read_line(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    if !args.is_empty() {
+        return Err(RuntimeError::TypeError(
+            "read_line() expects no arguments".to_string(),
+        ));
+    }
+    let mut line = String::new();
+    let n = std::io::stdin()
+        .read_line(&mut line)
+        .map_err(|e| RuntimeError::ValueError(format!("read_line() failed: {}", e)))?;
+    if n == 0 {
+        return Ok(Value::None);
+    }
+    if line.ends_with('\n') {
+        line.pop();
+        if line.ends_with('\r') {
+            line.pop();
+        }
+    }
+    Ok(Value::Str(line))
+}
+
+/// read_all_stdin() -> Str; reads stdin through to EOF in one shot.
+fn builtin_read_all_stdin(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    if !args.is_empty() {
+        return Err(RuntimeError::TypeError(
+            "read_all_stdin() expects no arguments".to_string(),
+        ));
+    }
+    let mut content = String::new();
+    std::io::stdin()
+        .read_to_string(&mut content)
+        .map_err(|e| RuntimeError::ValueError(format!("read_all_stdin() failed: {}", e)))?;
+    Ok(Value::Str(content))
+}
+
+/// call_builtin("name", [args...]) -> Value (delegates to another builtin)
+fn builtin_call_builtin(args: &[Value], env: &HashMap<String, Value>, globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [Value::Str(inner), Value::List(list)] => {
+            let inner_args = list.borrow().clone();
+            call_builtin(inner, &inner_args, env, globals)
+        }
+        _ => Err(RuntimeError::TypeError(
+            "call_builtin() expects a name and argument list".to_string(),
+        )),
+    }
+}
+
+// --- System ---------------------------------------------------------------
+
+/// argv() -> the program argument list (mirrors `globals["args"]`)
+fn builtin_argv(args: &[Value], _env: &HashMap<String, Value>, globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    if !args.is_empty() {
+        return Err(RuntimeError::TypeError(
+            "argv() expects no arguments (arity mismatch)".to_string(),
+        ));
+    }
+    Ok(globals
+        .get("args")
+        .cloned()
+        .unwrap_or_else(|| Value::new_list(Vec::new())))
+}
+
+/// env("NAME") -> environment variable value, or `None` if unset
+fn builtin_env(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [Value::Str(name)] => Ok(match std_env::var(name) {
+            Ok(val) => Value::Str(val),
+            Err(_) => Value::None,
+        }),
+        _ => Err(RuntimeError::TypeError(
+            "env() expects one string argument (arity mismatch)".to_string(),
+        )),
+    }
+}
+
+/// exit(code) -> terminates the host process immediately; never returns.
+fn builtin_exit(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    match args {
+        [Value::Int(code)] => std::process::exit(*code as i32),
+        _ => Err(RuntimeError::TypeError(
+            "exit() expects one integer (arity mismatch)".to_string(),
+        )),
+    }
+}
+
+// --- Garbage collector ------------------------------------------------------
+//
+// `gc.collect()` itself is special-cased in `vm.rs`'s `CallBuiltin` dispatch,
+// not registered here: collecting needs the full root set (operand stack +
+// every live env frame), which this module's `(args, env, globals)` builtin
+// signature can't see. These two are plain queries over the gc module's own
+// bookkeeping, so they work fine as ordinary builtins.
+
+/// gc.alloc_count() -> total number of lists/dicts ever allocated.
+fn builtin_gc_alloc_count(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    if !args.is_empty() {
+        return Err(RuntimeError::TypeError(
+            "gc.alloc_count() expects no arguments (arity mismatch)".to_string(),
+        ));
+    }
+    Ok(Value::Int(crate::gc::alloc_count() as i64))
+}
 
-        // Unknown builtin
-        _ => Err(RuntimeError::TypeError(format!(
-            "unknown builtin: {}",
-            name
-        ))),
+/// gc.live_count() -> number of list/dict handles still alive.
+fn builtin_gc_live_count(args: &[Value], _env: &HashMap<String, Value>, _globals: &HashMap<String, Value>) -> Result<Value, RuntimeError> {
+    if !args.is_empty() {
+        return Err(RuntimeError::TypeError(
+            "gc.live_count() expects no arguments (arity mismatch)".to_string(),
+        ));
     }
+    Ok(Value::Int(crate::gc::live_count() as i64))
 }
@@ -17,18 +17,26 @@
 //!   - `Str + any` or `any + Str` → stringify the non-string side and concat
 //!   - `List + List` → in‑place extend left list (preserving its Rc identity)
 //!   - otherwise → integer addition via `as_int()`
-//! - `-`, `*`, `/`, `%`, bitwise ops, shifts, and unary ops operate on **integers**
-//!   via `as_int()`.
+//! - `-`, `*`, `/`, `%`, and `+`'s integer fallback above, promote to floating-point when **either** operand is a
+//!   `Value::Float` (int op int stays an integer; int op float widens via
+//!   `as_float()`). Bitwise ops, shifts, and unary ops still operate on
+//!   **integers** only, via `as_int()` (which truncates a `Float` operand).
 //! - Boolean `and`/`or` use `as_bool()` (see [`Value`] for truthiness rules).
-//! - Comparisons allow **string vs string** lexicographic comparison; otherwise
-//!   they fall back to integer comparison via `as_int()`.
+//! - Comparisons allow **string vs string** lexicographic comparison, promote
+//!   to float the same way the arithmetic ops do when either side is a
+//!   `Float`, and otherwise fall back to integer comparison via `as_int()`.
 //! - Equality/inequality (`==`, `!=`) compare **stringified** values so that
 //!   heterogenous types can be compared consistently at the VM layer.
 //!
 //! ## Error behavior
-//! - Division/modulo by zero → `RuntimeError::ZeroDivisionError`.
-//! - Type mismatches bubble up from `Value::as_int()` / `as_bool()`.
-//! - `handle_mul` uses `checked_mul`; on overflow it **returns 0** (by design).
+//! - Division/modulo by zero → `RuntimeError::ZeroDivisionError`, checked
+//!   separately in both the integer and the float branch.
+//! - Type mismatches bubble up from `Value::as_int()` / `as_float()` / `as_bool()`.
+//! - Integer `+`, `-`, `*`, unary `-`, and `<<` use their `checked_*` Rust
+//!   equivalent and fault with `RuntimeError::IntegerOverflow` on `None`
+//!   rather than silently wrapping (or, for `handle_mul` previously, silently
+//!   returning 0) — wrapping in release builds would make `fail fast` a lie.
+//!   Float arithmetic is unaffected (IEEE overflow already saturates to `inf`).
 //!
 //! ## Notes
 //! - `handle_not` implements **bitwise NOT** (`~`), *not* logical negation.
@@ -38,6 +46,12 @@ use super::pop;
 use crate::error::RuntimeError;
 use crate::value::Value;
 
+/// True if either operand is a `Value::Float`, in which case an arithmetic
+/// or comparison handler should promote to floating-point rather than `as_int()`.
+fn either_float(a: &Value, b: &Value) -> bool {
+    matches!(a, Value::Float(_)) || matches!(b, Value::Float(_))
+}
+
 /// Handle addition operation.
 ///
 /// Supports:
@@ -63,52 +77,93 @@ pub(super) fn handle_add(stack: &mut Vec<Value>) -> Result<(), RuntimeError> {
             }
             stack.push(Value::List(la));
         }
-        // Otherwise: integer addition
+        // Otherwise: integer addition, promoted to float if either side is one
         (a, b) => {
-            let ai = a.as_int()?;
-            let bi = b.as_int()?;
-            stack.push(Value::Int(ai + bi));
+            if either_float(&a, &b) {
+                stack.push(Value::Float(a.as_float()? + b.as_float()?));
+            } else {
+                let (ai, bi) = (a.as_int()?, b.as_int()?);
+                let sum = ai.checked_add(bi).ok_or_else(|| {
+                    RuntimeError::IntegerOverflow(format!("{} + {} overflows i64", ai, bi))
+                })?;
+                stack.push(Value::Int(sum));
+            }
         }
     }
     Ok(())
 }
 
-/// Handle subtraction of two integers.
+/// Handle subtraction. Promotes to float if either operand is a `Float`.
 pub(super) fn handle_sub(stack: &mut Vec<Value>) -> Result<(), RuntimeError> {
-    let b = pop(stack)?.as_int()?;
-    let a = pop(stack)?.as_int()?;
-    stack.push(Value::Int(a - b));
+    let b = pop(stack)?;
+    let a = pop(stack)?;
+    if either_float(&a, &b) {
+        stack.push(Value::Float(a.as_float()? - b.as_float()?));
+    } else {
+        let (ai, bi) = (a.as_int()?, b.as_int()?);
+        let diff = ai.checked_sub(bi).ok_or_else(|| {
+            RuntimeError::IntegerOverflow(format!("{} - {} overflows i64", ai, bi))
+        })?;
+        stack.push(Value::Int(diff));
+    }
     Ok(())
 }
 
-/// Handle multiplication of two integers.
+/// Handle multiplication. Promotes to float if either operand is a `Float`.
 pub(super) fn handle_mul(stack: &mut Vec<Value>) -> Result<(), RuntimeError> {
-    let b = pop(stack)?.as_int()?;
-    let a = pop(stack)?.as_int()?;
-    // Use checked_mul to prevent overflow panics; fallback to 0 on overflow.
-    stack.push(Value::Int(a.checked_mul(b).unwrap_or(0)));
+    let b = pop(stack)?;
+    let a = pop(stack)?;
+    if either_float(&a, &b) {
+        stack.push(Value::Float(a.as_float()? * b.as_float()?));
+    } else {
+        let (ai, bi) = (a.as_int()?, b.as_int()?);
+        let product = ai.checked_mul(bi).ok_or_else(|| {
+            RuntimeError::IntegerOverflow(format!("{} * {} overflows i64", ai, bi))
+        })?;
+        stack.push(Value::Int(product));
+    }
     Ok(())
 }
 
-/// Handle integer division. Errors on division by zero.
+/// Handle division. Promotes to float if either operand is a `Float`.
+/// Errors on division by zero in either mode.
 pub(super) fn handle_div(stack: &mut Vec<Value>) -> Result<(), RuntimeError> {
-    let b = pop(stack)?.as_int()?;
-    if b == 0 {
-        return Err(RuntimeError::ZeroDivisionError);
+    let b = pop(stack)?;
+    let a = pop(stack)?;
+    if either_float(&a, &b) {
+        let bf = b.as_float()?;
+        if bf == 0.0 {
+            return Err(RuntimeError::ZeroDivisionError);
+        }
+        stack.push(Value::Float(a.as_float()? / bf));
+    } else {
+        let bi = b.as_int()?;
+        if bi == 0 {
+            return Err(RuntimeError::ZeroDivisionError);
+        }
+        stack.push(Value::Int(a.as_int()? / bi));
     }
-    let a = pop(stack)?.as_int()?;
-    stack.push(Value::Int(a / b));
     Ok(())
 }
 
-/// Handle integer modulus. Errors on division by zero.
+/// Handle modulus. Promotes to float if either operand is a `Float`.
+/// Errors on division by zero in either mode.
 pub(super) fn handle_mod(stack: &mut Vec<Value>) -> Result<(), RuntimeError> {
-    let b = pop(stack)?.as_int()?;
-    if b == 0 {
-        return Err(RuntimeError::ZeroDivisionError);
+    let b = pop(stack)?;
+    let a = pop(stack)?;
+    if either_float(&a, &b) {
+        let bf = b.as_float()?;
+        if bf == 0.0 {
+            return Err(RuntimeError::ZeroDivisionError);
+        }
+        stack.push(Value::Float(a.as_float()? % bf));
+    } else {
+        let bi = b.as_int()?;
+        if bi == 0 {
+            return Err(RuntimeError::ZeroDivisionError);
+        }
+        stack.push(Value::Int(a.as_int()? % bi));
     }
-    let a = pop(stack)?.as_int()?;
-    stack.push(Value::Int(a % b));
     Ok(())
 }
 
@@ -128,48 +183,56 @@ pub(super) fn handle_ne(stack: &mut Vec<Value>) -> Result<(), RuntimeError> {
     Ok(())
 }
 
-/// Handle less-than comparison. Supports integers and strings.
+/// Handle less-than comparison. Supports strings, and numerics (promoted to
+/// float if either side is a `Float`).
 pub(super) fn handle_lt(stack: &mut Vec<Value>) -> Result<(), RuntimeError> {
     let b = pop(stack)?;
     let a = pop(stack)?;
     let res = match (&a, &b) {
         (Value::Str(sa), Value::Str(sb)) => sa < sb,
+        _ if either_float(&a, &b) => a.as_float()? < b.as_float()?,
         _ => a.as_int()? < b.as_int()?,
     };
     stack.push(Value::Bool(res));
     Ok(())
 }
 
-/// Handle <= comparison. Supports integers and strings.
+/// Handle <= comparison. Supports strings, and numerics (promoted to float
+/// if either side is a `Float`).
 pub(super) fn handle_le(stack: &mut Vec<Value>) -> Result<(), RuntimeError> {
     let b = pop(stack)?;
     let a = pop(stack)?;
     let res = match (&a, &b) {
         (Value::Str(sa), Value::Str(sb)) => sa <= sb,
+        _ if either_float(&a, &b) => a.as_float()? <= b.as_float()?,
         _ => a.as_int()? <= b.as_int()?,
     };
     stack.push(Value::Bool(res));
     Ok(())
 }
 
-/// Handle greater-than comparison. Supports integers and strings.
+/// Handle greater-than comparison. Supports strings, and numerics (promoted
+/// to float if either side is a `Float`).
 pub(super) fn handle_gt(stack: &mut Vec<Value>) -> Result<(), RuntimeError> {
     let b = pop(stack)?;
     let a = pop(stack)?;
     let res = match (&a, &b) {
         (Value::Str(sa), Value::Str(sb)) => sa > sb,
+        _ if either_float(&a, &b) => a.as_float()? > b.as_float()?,
         _ => a.as_int()? > b.as_int()?,
     };
     stack.push(Value::Bool(res));
     Ok(())
 }
 
-/// Handle >= comparison. Supports integers and strings.
+/// Handle >= comparison. Supports strings, and numerics (promoted to float
+/// if either side is a `Float`).
 pub(super) fn handle_ge(stack: &mut Vec<Value>) -> Result<(), RuntimeError> {
     let b = pop(stack)?;
     let a = pop(stack)?;
     let res = match (&a, &b) {
         (Value::Str(sa), Value::Str(sb)) => sa >= sb,
+        _ if either_float(&a, &b) => a.as_float()? >= b.as_float()?,
         _ => a.as_int()? >= b.as_int()?,
     };
     stack.push(Value::Bool(res));
@@ -204,7 +267,10 @@ pub(super) fn handle_bxor(stack: &mut Vec<Value>) -> Result<(), RuntimeError> {
 pub(super) fn handle_shl(stack: &mut Vec<Value>) -> Result<(), RuntimeError> {
     let b = pop(stack)?.as_int()? as u32;
     let a = pop(stack)?.as_int()?;
-    stack.push(Value::Int(a << b));
+    let shifted = a.checked_shl(b).ok_or_else(|| {
+        RuntimeError::IntegerOverflow(format!("{} << {} overflows i64", a, b))
+    })?;
+    stack.push(Value::Int(shifted));
     Ok(())
 }
 
@@ -242,6 +308,9 @@ pub(super) fn handle_not(stack: &mut Vec<Value>) -> Result<(), RuntimeError> {
 /// Handle unary negation (-) of an integer.
 pub(super) fn handle_neg(stack: &mut Vec<Value>) -> Result<(), RuntimeError> {
     let v = pop(stack)?.as_int()?;
-    stack.push(Value::Int(-v));
+    let negated = v
+        .checked_neg()
+        .ok_or_else(|| RuntimeError::IntegerOverflow(format!("-({}) overflows i64", v)))?;
+    stack.push(Value::Int(negated));
     Ok(())
 }
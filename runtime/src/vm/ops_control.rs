@@ -6,7 +6,7 @@
 //! - **Function calls** (direct, tail, first-class, builtins)
 //! - **Stack control** (`pop`, return value handling)
 //! - **Program termination** (`halt`)
-//! - **I/O** (`emit`)
+//! - **I/O** (`emit`, `flush`)
 //! - **Exception handling** (`setup_except`, `pop_block`, `raise`)
 //!
 //! ## Execution model
@@ -28,13 +28,23 @@
 //!   then pushes the return value back to the operand stack.
 
 use std::collections::HashMap;
+use std::io::Write;
 use std::mem;
 
 use crate::bytecode::Function;
-use crate::error::{ErrorKind, RuntimeError};
+use crate::error::{ErrorKind, Frame, RuntimeError};
 use crate::value::Value;
-use super::{pop, Block};
-use super::builtins::call_builtin;
+use super::{pop, Block, MAX_CALL_DEPTH};
+use super::builtins::Builtins;
+
+/// Error raised when a call would push the call stack past the configured
+/// depth limit (see `max_call_depth` on [`handle_call`]/[`handle_call_value`]).
+fn recursion_error(max_call_depth: usize) -> RuntimeError {
+    RuntimeError::RecursionError(format!(
+        "maximum recursion depth exceeded ({} frames)",
+        max_call_depth
+    ))
+}
 
 /// Handle `assert`: pops a boolean condition; errors if false.
 pub(super) fn handle_assert(stack: &mut Vec<Value>) -> Result<(), RuntimeError> {
@@ -68,6 +78,10 @@ pub(super) fn handle_jump_if_false(
 
 /// Handle first-class call: pop callee (must be a string function name) + args,
 /// push new environment, save return address, and jump to function address.
+///
+/// `max_call_depth` is the caller's configured limit on `env_stack` depth
+/// (see [`crate::vm::Budget::max_call_depth`]) — defaults to
+/// [`MAX_CALL_DEPTH`] when the caller has not overridden it.
 pub(super) fn handle_call_value(
     argc: usize,
     stack: &mut Vec<Value>,
@@ -75,8 +89,10 @@ pub(super) fn handle_call_value(
     env: &mut HashMap<String, Value>,
     env_stack: &mut Vec<HashMap<String, Value>>,
     ret_stack: &mut Vec<usize>,
+    call_frames: &mut Vec<Frame>,
     pc: &mut usize,
     advance_pc: &mut bool,
+    max_call_depth: usize,
 ) -> Result<(), RuntimeError> {
     let mut args_vec: Vec<Value> = Vec::new();
     for _ in 0..argc {
@@ -86,13 +102,23 @@ pub(super) fn handle_call_value(
     let func_val = pop(stack)?;
     if let Value::Str(name) = func_val {
         if let Some(func) = funcs.get(&name) {
-            let mut new_env = HashMap::new();
+            if env_stack.len() >= max_call_depth {
+                return Err(recursion_error(max_call_depth));
+            }
+            // Reserve every local's slot in one allocation (see
+            // `Function::local_count`, from `bytecode`'s static stack/local
+            // analysis) rather than growing the map one `insert` at a time.
+            let mut new_env = HashMap::with_capacity(func.local_count.max(func.params.len()));
             for param in func.params.iter().rev() {
                 let arg = args_vec.pop().unwrap();
                 new_env.insert(param.clone(), arg);
             }
             env_stack.push(mem::take(env));
             ret_stack.push(*pc + 1);
+            call_frames.push(Frame {
+                function: name.clone(),
+                call_site: *pc,
+            });
             *env = new_env;
             *pc = func.address;
             *advance_pc = false;
@@ -108,6 +134,10 @@ pub(super) fn handle_call_value(
 }
 
 /// Handle direct named function call (like `call foo`).
+///
+/// `max_call_depth` is the caller's configured limit on `env_stack` depth
+/// (see [`crate::vm::Budget::max_call_depth`]) — defaults to
+/// [`MAX_CALL_DEPTH`] when the caller has not overridden it.
 pub(super) fn handle_call(
     name: &String,
     funcs: &HashMap<String, Function>,
@@ -115,17 +145,29 @@ pub(super) fn handle_call(
     env: &mut HashMap<String, Value>,
     env_stack: &mut Vec<HashMap<String, Value>>,
     ret_stack: &mut Vec<usize>,
+    call_frames: &mut Vec<Frame>,
     pc: &mut usize,
     advance_pc: &mut bool,
+    max_call_depth: usize,
 ) -> Result<(), RuntimeError> {
     if let Some(func) = funcs.get(name) {
-        let mut new_env = HashMap::new();
+        if env_stack.len() >= max_call_depth {
+            return Err(recursion_error(max_call_depth));
+        }
+        // Reserve every local's slot in one allocation (see
+        // `Function::local_count`, from `bytecode`'s static stack/local
+        // analysis) rather than growing the map one `insert` at a time.
+        let mut new_env = HashMap::with_capacity(func.local_count.max(func.params.len()));
         for param in func.params.iter().rev() {
             let arg = pop(stack)?;
             new_env.insert(param.clone(), arg);
         }
         env_stack.push(mem::take(env));
         ret_stack.push(*pc + 1);
+        call_frames.push(Frame {
+            function: name.clone(),
+            call_site: *pc,
+        });
         *env = new_env;
         *pc = func.address;
         *advance_pc = false;
@@ -145,7 +187,7 @@ pub(super) fn handle_tail_call(
     advance_pc: &mut bool,
 ) -> Result<(), RuntimeError> {
     if let Some(func) = funcs.get(name) {
-        let mut new_env = HashMap::new();
+        let mut new_env = HashMap::with_capacity(func.local_count.max(func.params.len()));
         for param in func.params.iter().rev() {
             let arg = pop(stack)?;
             new_env.insert(param.clone(), arg);
@@ -166,13 +208,14 @@ pub(super) fn handle_call_builtin(
     stack: &mut Vec<Value>,
     env: &HashMap<String, Value>,
     globals: &HashMap<String, Value>,
+    builtins: &Builtins,
 ) -> Result<(), RuntimeError> {
     let mut args: Vec<Value> = Vec::new();
     for _ in 0..argc {
         args.push(pop(stack)?);
     }
     args.reverse();
-    match call_builtin(name, &args, env, globals) {
+    match builtins.call(name, &args, env, globals) {
         Ok(val) => {
             stack.push(val);
             Ok(())
@@ -187,26 +230,56 @@ pub(super) fn handle_pop(stack: &mut Vec<Value>) {
 }
 
 /// Handle `ret`: restore caller’s PC + environment and push return value.
+///
+/// A corrupt `ret_stack`/`env_stack` (e.g. a `Ret` with no matching `Call`)
+/// surfaces as a `RuntimeError::VmInvariant` instead of panicking, so callers
+/// embedding the VM never see an abort.
 pub(super) fn handle_ret(
     stack: &mut Vec<Value>,
     pc: &mut usize,
     env: &mut HashMap<String, Value>,
     env_stack: &mut Vec<HashMap<String, Value>>,
     ret_stack: &mut Vec<usize>,
+    call_frames: &mut Vec<Frame>,
     advance_pc: &mut bool,
-) {
+) -> Result<(), RuntimeError> {
     let ret_val = stack.pop().unwrap_or(Value::Int(0));
-    *pc = ret_stack.pop().unwrap();
-    *env = env_stack.pop().unwrap();
+    *pc = ret_stack
+        .pop()
+        .ok_or_else(|| RuntimeError::VmInvariant("ret with empty return stack".to_string()))?;
+    *env = env_stack
+        .pop()
+        .ok_or_else(|| RuntimeError::VmInvariant("ret with empty env stack".to_string()))?;
+    call_frames.pop();
     stack.push(ret_val);
     *advance_pc = false;
+    Ok(())
 }
 
-/// Handle `emit`: pop and print top-of-stack.
-pub(super) fn handle_emit(stack: &mut Vec<Value>) {
+/// Handle `emit`: pop top-of-stack and write it (plus a newline) to `sink`.
+///
+/// `sink` is whatever the caller of `run`/`run_inner` threaded in (real
+/// stdout for the CLI, an in-memory buffer for the golden-test runner, an
+/// embedder-supplied writer, ...), so this never touches the process's real
+/// stdout directly. A write failure (e.g. a broken pipe) surfaces as a
+/// `ValueError` like other I/O failures in this codebase (`write_file`, etc.)
+/// rather than panicking.
+pub(super) fn handle_emit(stack: &mut Vec<Value>, sink: &mut dyn Write) -> Result<(), RuntimeError> {
     if let Some(v) = stack.pop() {
-        println!("{}", v.to_string());
+        writeln!(sink, "{}", v.to_string())
+            .map_err(|e| RuntimeError::ValueError(format!("emit failed: {}", e)))?;
     }
+    Ok(())
+}
+
+/// Handle `flush`: flush `sink` (the same destination `emit` writes to).
+///
+/// Exposed as the `flush()` builtin, but handled here rather than through the
+/// ordinary `Builtins` table because it needs the sink, which a plain
+/// builtin (env/globals only) can't see — the same reason `gc.collect` is
+/// special-cased in the VM loop instead of the builtin table.
+pub(super) fn handle_flush(sink: &mut dyn Write) -> Result<(), RuntimeError> {
+    sink.flush().map_err(|e| RuntimeError::ValueError(format!("flush failed: {}", e)))
 }
 
 /// Handle `halt`: set PC beyond code length to stop execution.
@@ -216,8 +289,12 @@ pub(super) fn handle_halt(code_len: usize, pc: &mut usize, advance_pc: &mut bool
 }
 
 /// Handle `setup_except`: push an exception handler block.
+///
+/// `kinds` restricts which `ErrorKind`s this handler catches; an empty list
+/// preserves the original catch-all behavior.
 pub(super) fn handle_setup_except(
     target: usize,
+    kinds: Vec<ErrorKind>,
     stack: &Vec<Value>,
     env_stack: &Vec<HashMap<String, Value>>,
     ret_stack: &Vec<usize>,
@@ -228,6 +305,7 @@ pub(super) fn handle_setup_except(
         stack_size: stack.len(),
         env_depth: env_stack.len(),
         ret_depth: ret_stack.len(),
+        filter: kinds,
     });
 }
 
@@ -236,12 +314,19 @@ pub(super) fn handle_pop_block(block_stack: &mut Vec<Block>) {
     block_stack.pop();
 }
 
-/// Handle `raise`: pop message value and raise a runtime error of given kind.
+/// Handle `raise`: pop the raised value and raise a runtime error.
+///
+/// If the raised value is a plain string, it's folded into the categorized
+/// `RuntimeError` for `kind` as before (`kind.into_runtime(msg)`). Otherwise
+/// the original [`Value`] (a `Dict` describing the error, a `List`, etc.) is
+/// preserved in `RuntimeError::RaisedValue` alongside `kind` itself, so a
+/// typed `except` filter still sees the kind the `raise` instruction named
+/// rather than every structured raise collapsing to `Generic`.
 pub(super) fn handle_raise(
     kind: &ErrorKind,
     stack: &mut Vec<Value>,
 ) -> Result<(), RuntimeError> {
-    let msg_val = match stack.pop() {
+    let val = match stack.pop() {
         Some(v) => v,
         None => {
             return Err(RuntimeError::VmInvariant(
@@ -249,6 +334,8 @@ pub(super) fn handle_raise(
             ));
         }
     };
-    let msg = msg_val.to_string();
-    Err(kind.into_runtime(msg))
+    match val {
+        Value::Str(msg) => Err(kind.into_runtime(msg)),
+        other => Err(RuntimeError::RaisedValue(*kind, other)),
+    }
 }
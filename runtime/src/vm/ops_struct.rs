@@ -2,11 +2,15 @@
 //!
 //! This module implements all VM instructions that manipulate **compound
 //! values**:
-//! - **List construction** (`build_list`) and concatenation
-//! - **Dictionary construction** (`build_dict`) and key/value access
+//! - **List construction** (`build_list`) and concatenation/repetition
+//!   (`concat`/`repeat`, shared by lists and strings)
+//! - **Dictionary construction** (`build_dict`) and key/value access, keyed
+//!   by [`DictKey`] so int/string/bool keys stay distinct (see
+//!   [`DictKey::from_value`])
 //! - **Indexing** (`list[i]`, `dict[k]`, `str[i]`)
-//! - **Slicing** (`list[start:end]`, `str[start:end]`)
-//! - **Attribute access** (`dict.key` shorthand)
+//! - **Slicing** (`list[start:end:step]`, `str[start:end:step]`, all three
+//!   parts optional — see [`slice_indices`])
+//! - **Attribute access** (`dict.key` shorthand, always a `DictKey::Str`)
 //! - **Mutable updates** (`list[i] = v`, `dict[k] = v`, `obj.key = v`)
 //!
 //! ## Execution model
@@ -22,43 +26,60 @@
 //! - Missing keys → `RuntimeError::KeyError`.
 //! - Writes to frozen dicts → `RuntimeError::FrozenWriteError`.
 //! - Wrong operand types → `RuntimeError::TypeError`.
+//!
+//! ## Negative indexing
+//! List/string indexing and indexed assignment accept Python-style negative
+//! indices: `i < 0` is rewritten to `len + i` before bounds-checking, so
+//! `xs[-1]` means "last element". Dict integer keys are unaffected by this —
+//! `dict[-1]` is a real, distinct key (see [`DictKey`]) rather than an
+//! offset-from-end, since dicts have no length to normalize against.
 
-use std::cell::RefCell;
 use std::collections::HashMap;
-use std::rc::Rc;
 
 use super::pop;
 use crate::error::RuntimeError;
-use crate::value::Value;
+use crate::value::{DictKey, Value};
+
+/// Rewrite a possibly-negative index against a container of length `len`,
+/// Python-style (`i < 0` becomes `len + i`). Returns the error `build_err`
+/// produces if the result still falls outside `0..len`.
+fn normalize_index(i: i64, len: usize, build_err: impl Fn() -> RuntimeError) -> Result<usize, RuntimeError> {
+    let resolved = if i < 0 { i + len as i64 } else { i };
+    if resolved < 0 || resolved as usize >= len {
+        return Err(build_err());
+    }
+    Ok(resolved as usize)
+}
 
 /// Build a list from the top `n` stack values.
-/// Pops `n` elements, reverses them to preserve order, and wraps in `Rc<RefCell<Vec<Value>>>`.
+/// Pops `n` elements, reverses them to preserve order, and allocates via [`Value::new_list`].
 pub(super) fn handle_build_list(n: usize, stack: &mut Vec<Value>) -> Result<(), RuntimeError> {
     let mut elements = Vec::new();
     for _ in 0..n {
         elements.push(pop(stack)?);
     }
     elements.reverse();
-    stack.push(Value::List(Rc::new(RefCell::new(elements))));
+    stack.push(Value::new_list(elements));
     Ok(())
 }
 
 /// Build a dictionary from the top `n` key/value pairs on the stack.
-/// Each pair is popped as (key, value); keys are converted to string.
+/// Each pair is popped as (key, value); keys are converted to [`DictKey`]
+/// (see [`DictKey::from_value`]), erroring if a key isn't hashable.
 pub(super) fn handle_build_dict(n: usize, stack: &mut Vec<Value>) -> Result<(), RuntimeError> {
-    let mut map: HashMap<String, Value> = HashMap::new();
+    let mut map: HashMap<DictKey, Value> = HashMap::new();
     for _ in 0..n {
         let val = pop(stack)?;
-        let key = pop(stack)?.to_string();
+        let key = DictKey::from_value(&pop(stack)?)?;
         map.insert(key, val);
     }
-    stack.push(Value::Dict(Rc::new(RefCell::new(map))));
+    stack.push(Value::new_dict(map));
     Ok(())
 }
 
 /// Handle indexing into a list, dict, or string.
 /// - `list[i]` → element at index
-/// - `dict[k]` → value for key
+/// - `dict[k]` → value for key (`k` converted via [`DictKey::from_value`])
 /// - `string[i]` → single-character string
 pub(super) fn handle_index(stack: &mut Vec<Value>) -> Result<(), RuntimeError> {
     let idx = pop(stack)?;
@@ -66,62 +87,37 @@ pub(super) fn handle_index(stack: &mut Vec<Value>) -> Result<(), RuntimeError> {
     match (base, idx) {
         // List indexing
         (Value::List(list), Value::Int(i)) => {
-            if i < 0 {
-                return Err(RuntimeError::IndexError("List index out of bounds!".to_string()));
-            }
             let l = list.borrow();
-            let idx_usize = i as usize;
-            if idx_usize < l.len() {
-                stack.push(l[idx_usize].clone());
-            } else {
-                return Err(RuntimeError::IndexError("List index out of bounds!".to_string()));
-            }
+            let idx_usize = normalize_index(i, l.len(), || {
+                RuntimeError::IndexError("List index out of bounds!".to_string())
+            })?;
+            stack.push(l[idx_usize].clone());
         }
-        // Dict key lookup (string key)
-        (Value::Dict(map), Value::Str(k)) => {
-            if let Some(v) = map.borrow().get(&k).cloned() {
-                stack.push(v);
-            } else {
-                return Err(RuntimeError::KeyError(k));
-            }
-        }
-        // Dict key lookup (integer → stringified key)
-        (Value::Dict(map), Value::Int(i)) => {
-            let key = i.to_string();
+        // Dict key lookup (any hashable key: int, string, or bool)
+        (Value::Dict(map), key_val) => {
+            let key = DictKey::from_value(&key_val)?;
             if let Some(v) = map.borrow().get(&key).cloned() {
                 stack.push(v);
             } else {
-                return Err(RuntimeError::KeyError(key));
+                return Err(RuntimeError::KeyError(key.to_string()));
             }
         }
         // Frozen dict behaves like immutable dict
-        (Value::FrozenDict(map), Value::Str(k)) => {
-            if let Some(v) = map.get(&k).cloned() {
-                stack.push(v);
-            } else {
-                return Err(RuntimeError::KeyError(k));
-            }
-        }
-        (Value::FrozenDict(map), Value::Int(i)) => {
-            let key = i.to_string();
+        (Value::FrozenDict(map), key_val) => {
+            let key = DictKey::from_value(&key_val)?;
             if let Some(v) = map.get(&key).cloned() {
                 stack.push(v);
             } else {
-                return Err(RuntimeError::KeyError(key));
+                return Err(RuntimeError::KeyError(key.to_string()));
             }
         }
         // String indexing → return one-character string
         (Value::Str(s), Value::Int(i)) => {
-            if i < 0 {
-                return Err(RuntimeError::IndexError("String index out of bounds!".to_string()));
-            }
             let chars: Vec<char> = s.chars().collect();
-            let idx_usize = i as usize;
-            if idx_usize < chars.len() {
-                stack.push(Value::Str(chars[idx_usize].to_string()));
-            } else {
-                return Err(RuntimeError::IndexError("String index out of bounds!".to_string()));
-            }
+            let idx_usize = normalize_index(i, chars.len(), || {
+                RuntimeError::IndexError("String index out of bounds!".to_string())
+            })?;
+            stack.push(Value::Str(chars[idx_usize].to_string()));
         }
         // Invalid base type
         (other, _) => {
@@ -131,67 +127,103 @@ pub(super) fn handle_index(stack: &mut Vec<Value>) -> Result<(), RuntimeError> {
     Ok(())
 }
 
-/// Handle slicing of lists and strings: `base[start:end]`.
-/// Both `start` and `end` are required to be non-negative; `end` may be `None`.
+/// Resolve the `(start, end, step)` triple of a slice operation into the
+/// concrete sequence of indices to collect from a container of length `len`,
+/// Python-style: `start`/`end` default based on `step`'s sign (`0`/`len` for
+/// a positive step, `len-1`/`-1` for a negative one, so `xs[::-1]` walks the
+/// whole container backwards), negative `start`/`end` normalize against
+/// `len`, and indices are produced by repeatedly adding `step` while still
+/// short of `end` (`<` for positive step, `>` for negative).
+fn slice_indices(start_val: &Value, end_val: &Value, step: i64, len: usize) -> Result<Vec<usize>, RuntimeError> {
+    if step == 0 {
+        return Err(RuntimeError::IndexError("Slice step cannot be zero!".to_string()));
+    }
+    let len_i64 = len as i64;
+    let normalize = |i: i64| -> i64 { if i < 0 { i + len_i64 } else { i } };
+
+    let start = match start_val {
+        Value::None => if step < 0 { len_i64 - 1 } else { 0 },
+        v => normalize(v.as_int()?),
+    };
+    let end = match end_val {
+        Value::None => if step < 0 { -1 } else { len_i64 },
+        v => normalize(v.as_int()?),
+    };
+
+    // Clamp into the range the loop below can ever legitimately step
+    // through, so a huge explicit bound (`"x"[0:9223372036854775807]`)
+    // can't blow up the iteration count the in-loop `i >= 0 && i < len`
+    // filter only bounded *which* indices got pushed, not how many times
+    // the loop ran. `-1` is kept as the floor (not `0`) since it's the
+    // legitimate default `end` sentinel for a negative step — the full
+    // reversal down to and including index `0`.
+    let start = start.clamp(-1, len_i64);
+    let end = end.clamp(-1, len_i64);
+
+    let mut indices = Vec::new();
+    let mut i = start;
+    if step > 0 {
+        while i < end {
+            if i >= 0 && (i as usize) < len {
+                indices.push(i as usize);
+            }
+            i += step;
+        }
+    } else {
+        while i > end {
+            if i >= 0 && (i as usize) < len {
+                indices.push(i as usize);
+            }
+            i += step;
+        }
+    }
+    Ok(indices)
+}
+
+/// Handle slicing of lists and strings: `base[start:end:step]`.
+///
+/// Pops `step`, `end`, `start` (in that order — they were pushed `start`,
+/// `end`, `step`), any of which may be `Value::None` to take its default.
+/// See [`slice_indices`] for the full start/end/step resolution rules.
+/// Any other base (dicts included — there's no positional slice of a dict)
+/// is a `RuntimeError::TypeError`, mirroring `handle_index`'s "is not
+/// indexable".
 pub(super) fn handle_slice(stack: &mut Vec<Value>) -> Result<(), RuntimeError> {
+    let step_val = pop(stack)?;
     let end_val = pop(stack)?;
     let start_val = pop(stack)?;
     let base = pop(stack)?;
-    let start_i64 = start_val.as_int()?;
+    let step = match &step_val {
+        Value::None => 1,
+        v => v.as_int()?,
+    };
     match base {
         // List slicing
         Value::List(list) => {
             let list_ref = list.borrow();
-            let len = list_ref.len();
-            if start_i64 < 0 {
-                return Err(RuntimeError::IndexError("Slice indices out of bounds!".to_string()));
-            }
-            let start = start_i64 as usize;
-            let end_i64 = match end_val {
-                Value::None => len as i64,
-                v => v.as_int()?,
-            };
-            if end_i64 < 0 {
-                return Err(RuntimeError::IndexError("Slice indices out of bounds!".to_string()));
-            }
-            let end = end_i64 as usize;
-            if start > end || end > len {
-                return Err(RuntimeError::IndexError("Slice indices out of bounds!".to_string()));
-            }
-            let slice = list_ref[start..end].to_vec();
-            stack.push(Value::List(Rc::new(RefCell::new(slice))));
+            let indices = slice_indices(&start_val, &end_val, step, list_ref.len())?;
+            let slice: Vec<Value> = indices.into_iter().map(|i| list_ref[i].clone()).collect();
+            stack.push(Value::new_list(slice));
         }
         // String slicing
         Value::Str(s) => {
             let chars: Vec<char> = s.chars().collect();
-            let len = chars.len();
-            if start_i64 < 0 {
-                return Err(RuntimeError::IndexError("Slice indices out of bounds!".to_string()));
-            }
-            let start = start_i64 as usize;
-            let end_i64 = match end_val {
-                Value::None => len as i64,
-                v => v.as_int()?,
-            };
-            if end_i64 < 0 {
-                return Err(RuntimeError::IndexError("Slice indices out of bounds!".to_string()));
-            }
-            let end = end_i64 as usize;
-            if start > end || end > len {
-                return Err(RuntimeError::IndexError("Slice indices out of bounds!".to_string()));
-            }
-            let slice: String = chars[start..end].iter().collect();
+            let indices = slice_indices(&start_val, &end_val, step, chars.len())?;
+            let slice: String = indices.into_iter().map(|i| chars[i]).collect();
             stack.push(Value::Str(slice));
         }
-        // Invalid base → push dummy 0 (VM design choice)
-        _ => stack.push(Value::Int(0)),
+        // Invalid base type (dicts included — there's no positional slice of
+        // a dict) — fail fast instead of masking the bug with a dummy value.
+        other => {
+            return Err(RuntimeError::TypeError(format!("{} is not sliceable", other.to_string())));
+        }
     }
     Ok(())
 }
 
 /// Handle indexed assignment: `base[idx] = val`.
 /// - Lists grow automatically if index >= len.
-/// - Dict keys accept string or integer (stringified).
+/// - Dict keys are converted via [`DictKey::from_value`] (int/string/bool).
 /// - Frozen dicts error on write.
 pub(super) fn handle_store_index(stack: &mut Vec<Value>) -> Result<(), RuntimeError> {
     let val = pop(stack)?;
@@ -200,17 +232,22 @@ pub(super) fn handle_store_index(stack: &mut Vec<Value>) -> Result<(), RuntimeEr
     match (base, idx) {
         (Value::List(list), Value::Int(i)) => {
             let mut l = list.borrow_mut();
-            let idx_usize = i as usize;
+            // Negative indices resolve into the existing range (Python-style
+            // `xs[-1] = v`); they never grow the list, since `len + i < len`
+            // whenever `i < 0`. A resolved index still `< 0` is out of bounds.
+            let resolved = if i < 0 { i + l.len() as i64 } else { i };
+            if resolved < 0 {
+                return Err(RuntimeError::IndexError("List index out of bounds!".to_string()));
+            }
+            let idx_usize = resolved as usize;
             if idx_usize >= l.len() {
                 l.resize(idx_usize + 1, Value::Int(0));
             }
             l[idx_usize] = val;
         }
-        (Value::Dict(map), Value::Str(k)) => {
-            map.borrow_mut().insert(k, val);
-        }
-        (Value::Dict(map), Value::Int(i)) => {
-            map.borrow_mut().insert(i.to_string(), val);
+        (Value::Dict(map), key_val) => {
+            let key = DictKey::from_value(&key_val)?;
+            map.borrow_mut().insert(key, val);
         }
         (Value::FrozenDict(_), _) => {
             return Err(RuntimeError::FrozenWriteError);
@@ -224,16 +261,17 @@ pub(super) fn handle_store_index(stack: &mut Vec<Value>) -> Result<(), RuntimeEr
 /// Only dictionaries (mutable or frozen) support attributes.
 pub(super) fn handle_attr(attr: &String, stack: &mut Vec<Value>) -> Result<(), RuntimeError> {
     let base = pop(stack)?;
+    let key = DictKey::Str(attr.clone());
     match base {
         Value::Dict(map) => {
-            if let Some(v) = map.borrow().get(attr).cloned() {
+            if let Some(v) = map.borrow().get(&key).cloned() {
                 stack.push(v);
             } else {
                 return Err(RuntimeError::KeyError(attr.clone()));
             }
         }
         Value::FrozenDict(map) => {
-            if let Some(v) = map.get(attr).cloned() {
+            if let Some(v) = map.get(&key).cloned() {
                 stack.push(v);
             } else {
                 return Err(RuntimeError::KeyError(attr.clone()));
@@ -258,7 +296,7 @@ pub(super) fn handle_store_attr(attr: &String, stack: &mut Vec<Value>) -> Result
     let base = pop(stack)?;
     match base {
         Value::Dict(map) => {
-            map.borrow_mut().insert(attr.clone(), val);
+            map.borrow_mut().insert(DictKey::Str(attr.clone()), val);
         }
         Value::FrozenDict(_) => {
             return Err(RuntimeError::FrozenWriteError);
@@ -267,3 +305,63 @@ pub(super) fn handle_store_attr(attr: &String, stack: &mut Vec<Value>) -> Result
     }
     Ok(())
 }
+
+/// Handle `left ++ right` (list/string concatenation): pops `right`, then
+/// `left`, and pushes a fresh value holding `left`'s elements/characters
+/// followed by `right`'s. Both sides must be the same kind (list+list or
+/// string+string); mixing is a `RuntimeError::TypeError`.
+pub(super) fn handle_concat(stack: &mut Vec<Value>) -> Result<(), RuntimeError> {
+    let right = pop(stack)?;
+    let left = pop(stack)?;
+    match (left, right) {
+        (Value::List(a), Value::List(b)) => {
+            let mut elements = a.borrow().clone();
+            elements.extend(b.borrow().iter().cloned());
+            stack.push(Value::new_list(elements));
+        }
+        (Value::Str(a), Value::Str(b)) => {
+            stack.push(Value::Str(a + &b));
+        }
+        (a, b) => {
+            return Err(RuntimeError::TypeError(format!(
+                "cannot concatenate {} and {}",
+                a.to_string(),
+                b.to_string()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Handle `base * n` (list/string repetition): pops a repeat count `n`, then
+/// the list/string to repeat, and pushes a fresh value holding the contents
+/// repeated `n` times (`n <= 0` yields an empty value). List repetition
+/// clones each element for every repetition; the clone is shallow, so a
+/// nested list or dict element is the same `Rc` in the original and in
+/// every repeated slot.
+pub(super) fn handle_repeat(stack: &mut Vec<Value>) -> Result<(), RuntimeError> {
+    let count_val = pop(stack)?;
+    let base = pop(stack)?;
+    let count = count_val.as_int()?;
+    let n = if count > 0 { count as usize } else { 0 };
+    match base {
+        Value::List(list) => {
+            let source = list.borrow();
+            let mut elements = Vec::with_capacity(source.len() * n);
+            for _ in 0..n {
+                elements.extend(source.iter().cloned());
+            }
+            stack.push(Value::new_list(elements));
+        }
+        Value::Str(s) => {
+            stack.push(Value::Str(s.repeat(n)));
+        }
+        other => {
+            return Err(RuntimeError::TypeError(format!(
+                "{} cannot be repeated",
+                other.to_string()
+            )));
+        }
+    }
+    Ok(())
+}
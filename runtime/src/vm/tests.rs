@@ -1,5 +1,5 @@
 use super::*;
-use crate::bytecode::{Function, Instr};
+use crate::bytecode::{disassemble, main_max_stack, Function, Instr};
 use crate::error::{ErrorKind, RuntimeError};
 use std::collections::HashMap;
 
@@ -32,6 +32,117 @@ fn store_index_on_frozen_dict_errors() {
     assert_eq!(result, Err(RuntimeError::FrozenWriteError));
 }
 
+#[test]
+fn negative_list_index_reads_from_the_end() {
+    let code = vec![
+        Instr::PushInt(1),
+        Instr::PushInt(2),
+        Instr::PushInt(3),
+        Instr::BuildList(3),
+        Instr::PushInt(-1),
+        Instr::Index,
+        Instr::PushInt(3),
+        Instr::Eq,
+        Instr::Assert,
+        Instr::Halt,
+    ];
+    let funcs = HashMap::new();
+    let result = run(&code, &funcs, &[]);
+    assert!(result.is_ok());
+}
+
+#[test]
+fn negative_list_index_out_of_range_errors() {
+    let code = vec![
+        Instr::PushInt(1),
+        Instr::BuildList(1),
+        Instr::PushInt(-2),
+        Instr::Index,
+        Instr::Halt,
+    ];
+    let funcs = HashMap::new();
+    let result = run(&code, &funcs, &[]);
+    assert_eq!(
+        result,
+        Err(RuntimeError::IndexError("List index out of bounds!".to_string()))
+    );
+}
+
+#[test]
+fn int_and_string_dict_keys_land_in_distinct_slots() {
+    // {1: "int-key", "1": "str-key"}, then confirm dict[1] != dict["1"].
+    let code = vec![
+        Instr::PushInt(1),
+        Instr::PushStr("int-key".to_string()),
+        Instr::PushStr("1".to_string()),
+        Instr::PushStr("str-key".to_string()),
+        Instr::BuildDict(2),
+        Instr::PushInt(1),
+        Instr::Index,
+        Instr::PushStr("int-key".to_string()),
+        Instr::Eq,
+        Instr::Assert,
+        Instr::Halt,
+    ];
+    let funcs = HashMap::new();
+    let result = run(&code, &funcs, &[]);
+    assert!(result.is_ok());
+}
+
+#[test]
+fn bool_dict_key_round_trips() {
+    let code = vec![
+        Instr::PushBool(true),
+        Instr::PushStr("yes".to_string()),
+        Instr::BuildDict(1),
+        Instr::PushBool(true),
+        Instr::Index,
+        Instr::PushStr("yes".to_string()),
+        Instr::Eq,
+        Instr::Assert,
+        Instr::Halt,
+    ];
+    let funcs = HashMap::new();
+    let result = run(&code, &funcs, &[]);
+    assert!(result.is_ok());
+}
+
+#[test]
+fn read_line_pulls_from_input_provider_when_supplied() {
+    // Two read_line() calls: the provider yields one line, then is
+    // exhausted, mirroring real read_line()'s `Value::None` at EOF.
+    let code = vec![
+        Instr::CallBuiltin("read_line".to_string(), 0),
+        Instr::PushStr("hello".to_string()),
+        Instr::Eq,
+        Instr::Assert,
+        Instr::CallBuiltin("read_line".to_string(), 0),
+        Instr::PushNone,
+        Instr::Eq,
+        Instr::Assert,
+        Instr::Halt,
+    ];
+    let funcs = HashMap::new();
+    let mut lines = vec!["hello".to_string()].into_iter();
+    let mut provider = move || lines.next();
+    let mut sink: Vec<u8> = Vec::new();
+    let result = run_with_input(&code, &funcs, &[], &mut sink, Some(&mut provider));
+    assert!(result.is_ok());
+}
+
+#[test]
+fn list_as_dict_key_errors() {
+    let code = vec![
+        Instr::BuildList(0),
+        Instr::PushInt(1),
+        Instr::BuildDict(1),
+        Instr::Halt,
+    ];
+    let funcs = HashMap::new();
+    let result = run(&code, &funcs, &[]);
+    assert!(matches!(result, Err(RuntimeError::TypeError(_))));
+}
+
 #[test]
 fn raise_caught_in_caller() {
     let mut funcs = HashMap::new();
@@ -40,10 +151,11 @@ fn raise_caught_in_caller() {
         Function {
             params: vec![],
             address: 7,
+            ..Default::default()
         },
     );
     let code = vec![
-        Instr::SetupExcept(4),
+        Instr::SetupExcept(4, vec![]),
         Instr::Call("boom".to_string()),
         Instr::PopBlock,
         Instr::Jump(6),
@@ -134,7 +246,7 @@ fn uncaught_assert_surfaces() {
 #[test]
 fn assert_caught_in_block() {
     let code = vec![
-        Instr::SetupExcept(5),
+        Instr::SetupExcept(5, vec![]),
         Instr::PushBool(false),
         Instr::Assert,
         Instr::PopBlock,
@@ -296,30 +408,99 @@ fn call_value_unknown_function_errors() {
 }
 
 #[test]
-fn list_slice_with_invalid_bounds_errors() {
+fn list_slice_with_out_of_range_bounds_clamps_to_empty() {
+    // Python-style slicing clamps rather than erroring: `start > end` (here
+    // clamped to the empty range 0..0) just yields an empty result, not an
+    // IndexError.
     let code = vec![
         Instr::BuildList(0),
         Instr::PushInt(1),
         Instr::PushInt(0),
+        Instr::PushNone,
         Instr::Slice,
+        Instr::BuildList(0),
+        Instr::Eq,
+        Instr::Assert,
         Instr::Halt,
     ];
     let funcs = HashMap::new();
     let result = run(&code, &funcs, &[]);
-    assert_eq!(
-        result,
-        Err(RuntimeError::IndexError(
-            "Slice indices out of bounds!".to_string()
-        ))
-    );
+    assert!(result.is_ok());
 }
 
 #[test]
-fn string_slice_with_invalid_bounds_errors() {
+fn string_slice_with_end_past_len_clamps() {
     let code = vec![
         Instr::PushStr("ab".to_string()),
         Instr::PushInt(0),
         Instr::PushInt(3),
+        Instr::PushNone,
+        Instr::Slice,
+        Instr::PushStr("ab".to_string()),
+        Instr::Eq,
+        Instr::Assert,
+        Instr::Halt,
+    ];
+    let funcs = HashMap::new();
+    let result = run(&code, &funcs, &[]);
+    assert!(result.is_ok());
+}
+
+#[test]
+fn negative_step_slice_reverses() {
+    let code = vec![
+        Instr::PushInt(1),
+        Instr::PushInt(2),
+        Instr::PushInt(3),
+        Instr::BuildList(3),
+        Instr::PushNone,
+        Instr::PushNone,
+        Instr::PushInt(-1),
+        Instr::Slice,
+        Instr::PushInt(3),
+        Instr::PushInt(2),
+        Instr::PushInt(1),
+        Instr::BuildList(3),
+        Instr::Eq,
+        Instr::Assert,
+        Instr::Halt,
+    ];
+    let funcs = HashMap::new();
+    let result = run(&code, &funcs, &[]);
+    assert!(result.is_ok());
+}
+
+#[test]
+fn slice_with_huge_explicit_bound_does_not_hang() {
+    // A bound far larger than the sequence length used to make
+    // `slice_indices` iterate the full (huge) bound before its in-loop
+    // `i < len` filter discarded each out-of-range step — a real hang for
+    // something like `i64::MAX`. Clamping the bound itself before the loop
+    // runs means this still completes and produces the same clamped-to-
+    // the-whole-string result.
+    let code = vec![
+        Instr::PushStr("ab".to_string()),
+        Instr::PushInt(0),
+        Instr::PushInt(i64::MAX),
+        Instr::PushNone,
+        Instr::Slice,
+        Instr::PushStr("ab".to_string()),
+        Instr::Eq,
+        Instr::Assert,
+        Instr::Halt,
+    ];
+    let funcs = HashMap::new();
+    let result = run(&code, &funcs, &[]);
+    assert!(result.is_ok());
+}
+
+#[test]
+fn zero_step_slice_errors() {
+    let code = vec![
+        Instr::BuildList(0),
+        Instr::PushNone,
+        Instr::PushNone,
+        Instr::PushInt(0),
         Instr::Slice,
         Instr::Halt,
     ];
@@ -327,12 +508,216 @@ fn string_slice_with_invalid_bounds_errors() {
     let result = run(&code, &funcs, &[]);
     assert_eq!(
         result,
-        Err(RuntimeError::IndexError(
-            "Slice indices out of bounds!".to_string()
-        ))
+        Err(RuntimeError::IndexError("Slice step cannot be zero!".to_string()))
+    );
+}
+
+#[test]
+fn slicing_an_int_errors() {
+    let code = vec![
+        Instr::PushInt(5),
+        Instr::PushNone,
+        Instr::PushNone,
+        Instr::PushNone,
+        Instr::Slice,
+        Instr::Halt,
+    ];
+    let funcs = HashMap::new();
+    let result = run(&code, &funcs, &[]);
+    assert!(matches!(result, Err(RuntimeError::TypeError(_))));
+}
+
+#[test]
+fn slicing_a_dict_errors() {
+    let code = vec![
+        Instr::BuildDict(0),
+        Instr::PushNone,
+        Instr::PushNone,
+        Instr::PushNone,
+        Instr::Slice,
+        Instr::Halt,
+    ];
+    let funcs = HashMap::new();
+    let result = run(&code, &funcs, &[]);
+    assert!(matches!(result, Err(RuntimeError::TypeError(_))));
+}
+
+#[test]
+fn slicing_none_errors() {
+    let code = vec![
+        Instr::PushNone,
+        Instr::PushNone,
+        Instr::PushNone,
+        Instr::PushNone,
+        Instr::Slice,
+        Instr::Halt,
+    ];
+    let funcs = HashMap::new();
+    let result = run(&code, &funcs, &[]);
+    assert!(matches!(result, Err(RuntimeError::TypeError(_))));
+}
+
+#[test]
+fn typed_handler_catches_matching_kind() {
+    let code = vec![
+        Instr::SetupExcept(4, vec![ErrorKind::Value]),
+        Instr::PushStr("boom".to_string()),
+        Instr::Raise(ErrorKind::Value),
+        Instr::Halt,
+        Instr::PopBlock,
+        Instr::Halt,
+    ];
+    let funcs = HashMap::new();
+    let result = run(&code, &funcs, &[]);
+    assert!(result.is_ok());
+}
+
+#[test]
+fn typed_handler_catches_structured_raise_by_its_declared_kind() {
+    // A raised Dict (or any non-Str value) used to always classify as
+    // ErrorKind::Generic regardless of the kind the `raise` instruction
+    // itself named, so a typed handler filtering on that declared kind could
+    // never catch it. BuildDict(0) pushes a structured (non-Str) payload;
+    // the handler below only declares ErrorKind::Value, the same kind the
+    // Raise instruction carries, and must still catch it.
+    let code = vec![
+        Instr::SetupExcept(4, vec![ErrorKind::Value]),
+        Instr::BuildDict(0),
+        Instr::Raise(ErrorKind::Value),
+        Instr::Halt,
+        Instr::PopBlock,
+        Instr::Halt,
+    ];
+    let funcs = HashMap::new();
+    let result = run(&code, &funcs, &[]);
+    assert!(result.is_ok());
+}
+
+#[test]
+fn typed_handler_ignores_non_matching_kind() {
+    let code = vec![
+        Instr::SetupExcept(4, vec![ErrorKind::Value]),
+        Instr::PushStr("boom".to_string()),
+        Instr::Raise(ErrorKind::Type),
+        Instr::Halt,
+        Instr::PopBlock,
+        Instr::Halt,
+    ];
+    let funcs = HashMap::new();
+    let result = run(&code, &funcs, &[]);
+    assert_eq!(result, Err(RuntimeError::TypeError("boom".to_string())));
+}
+
+#[test]
+fn non_matching_inner_handler_is_skipped_for_outer_match() {
+    // Outer block catches Value errors; inner block only catches Type
+    // errors. A Value raise must unwind past the non-matching inner block
+    // (popping it while searching) and land in the outer handler instead of
+    // surfacing uncaught.
+    let code = vec![
+        Instr::SetupExcept(8, vec![ErrorKind::Value]), // outer
+        Instr::SetupExcept(6, vec![ErrorKind::Type]),  // inner
+        Instr::PushStr("boom".to_string()),
+        Instr::Raise(ErrorKind::Value),
+        Instr::PopBlock,
+        Instr::Jump(9),
+        Instr::PopBlock, // inner handler (unreached)
+        Instr::Jump(9),
+        Instr::Pop, // outer handler: discard the caught message
+        Instr::Halt,
+    ];
+    let funcs = HashMap::new();
+    let result = run(&code, &funcs, &[]);
+    assert!(result.is_ok());
+}
+
+#[test]
+fn one_in_one_is_always_true() {
+    let code = vec![
+        Instr::PushInt(1),
+        Instr::CallBuiltin("one_in".to_string(), 1),
+        Instr::Assert,
+        Instr::Halt,
+    ];
+    let funcs = HashMap::new();
+    let result = run(&code, &funcs, &[]);
+    assert!(result.is_ok());
+}
+
+#[test]
+fn one_in_non_positive_is_value_error() {
+    let code = vec![
+        Instr::PushInt(0),
+        Instr::CallBuiltin("one_in".to_string(), 1),
+        Instr::Halt,
+    ];
+    let funcs = HashMap::new();
+    let result = run(&code, &funcs, &[]);
+    assert_eq!(
+        result,
+        Err(RuntimeError::ValueError("one_in() expects a positive n".to_string()))
+    );
+}
+
+#[test]
+fn seeding_reproduces_the_same_randint_sequence() {
+    // Reseeding to the same value must reproduce the same draw.
+    let code = vec![
+        Instr::PushInt(42),
+        Instr::CallBuiltin("seed".to_string(), 1),
+        Instr::Pop,
+        Instr::PushInt(1),
+        Instr::PushInt(1_000_000),
+        Instr::CallBuiltin("randint".to_string(), 2),
+        Instr::Store("first".to_string()),
+        Instr::PushInt(42),
+        Instr::CallBuiltin("seed".to_string(), 1),
+        Instr::Pop,
+        Instr::PushInt(1),
+        Instr::PushInt(1_000_000),
+        Instr::CallBuiltin("randint".to_string(), 2),
+        Instr::Load("first".to_string()),
+        Instr::Eq,
+        Instr::Assert,
+        Instr::Halt,
+    ];
+    let funcs = HashMap::new();
+    let result = run(&code, &funcs, &[]);
+    assert!(result.is_ok());
+}
+
+#[test]
+fn randint_out_of_order_bounds_is_value_error() {
+    let code = vec![
+        Instr::PushInt(5),
+        Instr::PushInt(1),
+        Instr::CallBuiltin("randint".to_string(), 2),
+        Instr::Halt,
+    ];
+    let funcs = HashMap::new();
+    let result = run(&code, &funcs, &[]);
+    assert_eq!(
+        result,
+        Err(RuntimeError::ValueError("randint() expects lo <= hi".to_string()))
     );
 }
 
+#[test]
+fn randint_full_i64_range_does_not_overflow() {
+    // lo/hi spanning the full i64 range used to overflow `hi - lo` in i64
+    // before the range computation was widened to i128; this just needs to
+    // run and stay within bounds rather than panic.
+    let code = vec![
+        Instr::PushInt(i64::MIN),
+        Instr::PushInt(i64::MAX),
+        Instr::CallBuiltin("randint".to_string(), 2),
+        Instr::Halt,
+    ];
+    let funcs = HashMap::new();
+    let result = run(&code, &funcs, &[]);
+    assert!(result.is_ok());
+}
+
 #[test]
 fn neg_on_non_int_string_errors() {
     let code = vec![Instr::PushStr("abc".to_string()), Instr::Neg, Instr::Halt];
@@ -344,4 +729,411 @@ fn neg_on_non_int_string_errors() {
             "Invalid literal for int(): 'abc'".to_string(),
         )),
     );
+}
+
+#[test]
+fn int_plus_float_promotes_and_succeeds() {
+    let code = vec![
+        Instr::PushInt(1),
+        Instr::PushFloat(2.5),
+        Instr::Add,
+        Instr::Halt,
+    ];
+    let funcs = HashMap::new();
+    let result = run(&code, &funcs, &[]);
+    assert!(result.is_ok());
+}
+
+#[test]
+fn float_division_by_zero_errors() {
+    let code = vec![
+        Instr::PushFloat(1.0),
+        Instr::PushFloat(0.0),
+        Instr::Div,
+        Instr::Halt,
+    ];
+    let funcs = HashMap::new();
+    let result = run(&code, &funcs, &[]);
+    assert_eq!(result, Err(RuntimeError::ZeroDivisionError));
+}
+
+#[test]
+fn list_concat_joins_without_aliasing() {
+    let code = vec![
+        Instr::PushInt(1),
+        Instr::BuildList(1),
+        Instr::PushInt(2),
+        Instr::BuildList(1),
+        Instr::Concat,
+        Instr::PushInt(1),
+        Instr::PushInt(2),
+        Instr::BuildList(2),
+        Instr::Eq,
+        Instr::Assert,
+        Instr::Halt,
+    ];
+    let funcs = HashMap::new();
+    let result = run(&code, &funcs, &[]);
+    assert!(result.is_ok());
+}
+
+#[test]
+fn concat_mismatched_types_errors() {
+    let code = vec![
+        Instr::BuildList(0),
+        Instr::PushStr("x".to_string()),
+        Instr::Concat,
+        Instr::Halt,
+    ];
+    let funcs = HashMap::new();
+    let result = run(&code, &funcs, &[]);
+    assert!(matches!(result, Err(RuntimeError::TypeError(_))));
+}
+
+#[test]
+fn list_repeat_builds_independent_elements() {
+    let code = vec![
+        Instr::PushInt(0),
+        Instr::BuildList(1),
+        Instr::PushInt(3),
+        Instr::Repeat,
+        Instr::PushInt(0),
+        Instr::PushInt(0),
+        Instr::PushInt(0),
+        Instr::BuildList(3),
+        Instr::Eq,
+        Instr::Assert,
+        Instr::Halt,
+    ];
+    let funcs = HashMap::new();
+    let result = run(&code, &funcs, &[]);
+    assert!(result.is_ok());
+}
+
+#[test]
+fn string_repeat_with_non_positive_count_is_empty() {
+    let code = vec![
+        Instr::PushStr("ab".to_string()),
+        Instr::PushInt(0),
+        Instr::Repeat,
+        Instr::PushStr("".to_string()),
+        Instr::Eq,
+        Instr::Assert,
+        Instr::Halt,
+    ];
+    let funcs = HashMap::new();
+    let result = run(&code, &funcs, &[]);
+    assert!(result.is_ok());
+}
+
+#[test]
+fn vm_step_yields_then_resumes_to_halt() {
+    // push 1, yield, push 2, halt — step() should surface the yielded 1
+    // (Yield pops its operand rather than leaving it on the stack), then
+    // resuming with further step() calls should run push-2/halt to
+    // completion, with the stack left holding only the 2.
+    let code = vec![
+        Instr::PushInt(1),
+        Instr::Yield,
+        Instr::PushInt(2),
+        Instr::Halt,
+    ];
+    let funcs = HashMap::new();
+    let builtins = Builtins::standard();
+    let mut vm = Vm::new(&code, &funcs, &builtins, &[]);
+    let mut sink: Vec<u8> = Vec::new();
+
+    assert!(matches!(vm.step(&mut sink), StepResult::Continue)); // PushInt(1)
+    match vm.step(&mut sink) {
+        StepResult::Yielded(Value::Int(1)) => {}
+        other => panic!("expected Yielded(1), got {:?}", other),
+    }
+    assert!(matches!(vm.step(&mut sink), StepResult::Continue)); // PushInt(2)
+    assert!(matches!(vm.step(&mut sink), StepResult::Halted));
+    assert_eq!(vm.stack().as_slice(), &[Value::Int(2)]);
+}
+
+#[test]
+fn vm_step_faults_on_undefined_identifier() {
+    let code = vec![Instr::Load("nope".to_string()), Instr::Halt];
+    let funcs = HashMap::new();
+    let builtins = Builtins::standard();
+    let mut vm = Vm::new(&code, &funcs, &builtins, &[]);
+    let mut sink: Vec<u8> = Vec::new();
+    match vm.step(&mut sink) {
+        StepResult::Faulted(RuntimeError::UndefinedIdentError(name)) => assert_eq!(name, "nope"),
+        other => panic!("expected Faulted(UndefinedIdentError), got {:?}", other),
+    }
+}
+
+#[test]
+fn max_call_depth_override_triggers_recursion_error_sooner() {
+    // `f`'s own address is its call site, so calling it recurses forever;
+    // overriding the default 10_000-frame limit down to 2 confirms
+    // `Budget::max_call_depth` actually takes effect instead of the default.
+    let code = vec![
+        Instr::Call("f".to_string()), // pc 0, also f's entry point
+        Instr::Halt,
+    ];
+    let mut funcs = HashMap::new();
+    funcs.insert(
+        "f".to_string(),
+        Function {
+            params: vec![],
+            address: 0,
+            ..Default::default()
+        },
+    );
+    let mut budget = Budget::with_max_call_depth(2);
+    let mut sink: Vec<u8> = Vec::new();
+    let result = run_with_budget(&code, &funcs, &[], None, Some(&mut budget), &mut sink);
+    assert!(matches!(result, Err(RuntimeError::RecursionError(_))));
+}
+
+#[test]
+fn add_overflow_errors_instead_of_wrapping() {
+    let code = vec![
+        Instr::PushInt(i64::MAX),
+        Instr::PushInt(1),
+        Instr::Add,
+        Instr::Halt,
+    ];
+    let funcs = HashMap::new();
+    let result = run(&code, &funcs, &[]);
+    assert!(matches!(result, Err(RuntimeError::IntegerOverflow(_))));
+}
+
+#[test]
+fn sub_overflow_errors_instead_of_wrapping() {
+    let code = vec![
+        Instr::PushInt(i64::MIN),
+        Instr::PushInt(1),
+        Instr::Sub,
+        Instr::Halt,
+    ];
+    let funcs = HashMap::new();
+    let result = run(&code, &funcs, &[]);
+    assert!(matches!(result, Err(RuntimeError::IntegerOverflow(_))));
+}
+
+#[test]
+fn mul_overflow_errors_instead_of_returning_zero() {
+    let code = vec![
+        Instr::PushInt(i64::MAX),
+        Instr::PushInt(2),
+        Instr::Mul,
+        Instr::Halt,
+    ];
+    let funcs = HashMap::new();
+    let result = run(&code, &funcs, &[]);
+    assert!(matches!(result, Err(RuntimeError::IntegerOverflow(_))));
+}
+
+#[test]
+fn neg_overflow_on_int_min_errors() {
+    let code = vec![Instr::PushInt(i64::MIN), Instr::Neg, Instr::Halt];
+    let funcs = HashMap::new();
+    let result = run(&code, &funcs, &[]);
+    assert!(matches!(result, Err(RuntimeError::IntegerOverflow(_))));
+}
+
+#[test]
+fn shl_overflow_on_shift_past_bit_width_errors() {
+    let code = vec![
+        Instr::PushInt(1),
+        Instr::PushInt(64),
+        Instr::Shl,
+        Instr::Halt,
+    ];
+    let funcs = HashMap::new();
+    let result = run(&code, &funcs, &[]);
+    assert!(matches!(result, Err(RuntimeError::IntegerOverflow(_))));
+}
+
+#[test]
+fn host_registered_closure_is_dispatched_as_a_builtin() {
+    let code = vec![
+        Instr::PushInt(40),
+        Instr::CallBuiltin("host_add_two".to_string(), 1),
+        Instr::Halt,
+    ];
+    let funcs = HashMap::new();
+    let mut builtins = Builtins::standard();
+    builtins.register_fn("host_add_two", |args, _env, _globals| match args {
+        [Value::Int(n)] => Ok(Value::Int(n + 2)),
+        _ => Err(RuntimeError::TypeError("host_add_two() expects an int".to_string())),
+    });
+    let mut sink: Vec<u8> = Vec::new();
+    let result = run_with_builtins(&code, &funcs, &[], None, None, &builtins, &mut sink);
+    assert!(result.is_ok());
+}
+
+#[test]
+fn host_registered_closure_never_shadows_a_core_builtin() {
+    let mut builtins = Builtins::standard();
+    builtins.register_fn("abs", |_args, _env, _globals| Ok(Value::Int(0)));
+    let result = builtins.call("abs", &[Value::Int(-5)], &HashMap::new(), &HashMap::new());
+    assert_eq!(result, Ok(Value::Int(5)));
+}
+
+#[test]
+fn main_max_stack_tracks_peak_depth_across_a_branch() {
+    // Pushes 1, then branches: the true arm pushes two more (peak depth 3)
+    // before popping back down; the false arm pushes just one. The analysis
+    // must report the max over both arms, not just whichever it walks first.
+    let code = vec![
+        Instr::PushInt(1),          // 0: depth 0 -> 1
+        Instr::PushBool(true),      // 1: depth 1 -> 2
+        Instr::JumpIfFalse(6),      // 2: depth 2 -> 1
+        Instr::PushInt(2),          // 3: depth 1 -> 2
+        Instr::PushInt(3),          // 4: depth 2 -> 3 (peak)
+        Instr::Jump(8),             // 5
+        Instr::PushInt(9),          // 6: depth 1 -> 2 (false arm)
+        Instr::Pop,                 // 7: depth 2 -> 1
+        Instr::Halt,                // 8
+    ];
+    let funcs = HashMap::new();
+    assert_eq!(main_max_stack(&code, &funcs), 3);
+}
+
+#[test]
+fn call_sites_size_callee_pops_from_declared_params() {
+    // `add_one(x)` pops exactly 1 arg before pushing its return value, so the
+    // peak depth at the call site is driven by what's pushed before the call
+    // plus the callee's own body (which runs unreached from entry 0 here).
+    let mut funcs = HashMap::new();
+    funcs.insert(
+        "add_one".to_string(),
+        Function {
+            params: vec!["x".to_string()],
+            address: 100,
+            ..Default::default()
+        },
+    );
+    let code = vec![
+        Instr::PushInt(41),
+        Instr::Call("add_one".to_string()),
+        Instr::Halt,
+    ];
+    assert_eq!(main_max_stack(&code, &funcs), 1);
+}
+
+#[test]
+fn parse_and_assemble_round_trip_populates_function_shape() {
+    // A function with one param and two distinct locals; its max_stack and
+    // local_count should come back populated (non-zero) after a round trip
+    // through `assemble`/`parse_bytecode`, not left at the zero default a
+    // hand-built `Function` literal gets.
+    let code = vec![
+        Instr::Load("x".to_string()),   // 0: func entry
+        Instr::Store("a".to_string()),  // 1
+        Instr::Load("a".to_string()),   // 2
+        Instr::Store("b".to_string()),  // 3
+        Instr::Load("b".to_string()),   // 4
+        Instr::Ret,                     // 5
+        Instr::Call("f".to_string()),   // 6: top-level body
+        Instr::Halt,                    // 7
+    ];
+    let mut funcs = HashMap::new();
+    funcs.insert(
+        "f".to_string(),
+        Function {
+            params: vec!["x".to_string()],
+            address: 0,
+            ..Default::default()
+        },
+    );
+    let bytes = crate::bytecode::assemble(&code, &funcs);
+    let (_parsed_code, parsed_funcs) = crate::bytecode::parse_bytecode(&bytes);
+    let f = parsed_funcs.get("f").unwrap();
+    assert!(f.max_stack >= 1);
+    assert!(f.local_count >= 3); // x, a, b
+}
+
+#[test]
+fn disassemble_labels_jump_targets_and_function_entries() {
+    // if-else-shaped jump past a PUSH_INT, plus a SetupExcept whose target
+    // and whose typed filter both need to show up in the listing.
+    let mut funcs = HashMap::new();
+    funcs.insert(
+        "f".to_string(),
+        Function {
+            params: vec!["x".to_string()],
+            address: 7,
+            ..Default::default()
+        },
+    );
+    let code = vec![
+        Instr::SetupExcept(3, vec![ErrorKind::Value]),
+        Instr::Jump(3),
+        Instr::PushInt(1),
+        Instr::PopBlock,
+        Instr::Halt,
+        Instr::Halt,
+        Instr::Halt,
+        Instr::Load("x".to_string()), // f's entry point
+        Instr::Ret,
+    ];
+    let text = disassemble(&code, &funcs);
+    assert!(text.contains("; f(x) @ 7"));
+    assert!(text.contains("SETUP_EXCEPT L0003 [ValueError]"));
+    assert!(text.contains("JUMP L0003"));
+    assert!(text.contains("L0003:"));
+    assert!(text.contains("f:\n0007  LOAD x"));
+}
+
+#[test]
+fn tracer_sees_one_event_per_dispatched_instruction() {
+    struct RecordingTracer {
+        pcs: Vec<usize>,
+    }
+    impl Tracer for RecordingTracer {
+        fn on_instr(&mut self, event: &TraceEvent) {
+            self.pcs.push(event.pc);
+        }
+    }
+
+    let code = vec![
+        Instr::PushInt(1),
+        Instr::PushInt(2),
+        Instr::Add,
+        Instr::PushInt(3),
+        Instr::Eq,
+        Instr::Assert,
+        Instr::Halt,
+    ];
+    let funcs = HashMap::new();
+    let mut sink: Vec<u8> = Vec::new();
+    let mut tracer = RecordingTracer { pcs: Vec::new() };
+    let result = run_with_tracer(&code, &funcs, &[], &mut sink, &mut tracer);
+    assert!(result.is_ok());
+    assert_eq!(tracer.pcs, vec![0, 1, 2, 3, 4, 5, 6]);
+}
+
+#[test]
+fn tracer_stack_top_reflects_pending_values() {
+    struct SnapshotTracer {
+        // stack depth observed right before the `Add` at pc 2 executes.
+        depth_before_add: Option<usize>,
+    }
+    impl Tracer for SnapshotTracer {
+        fn on_instr(&mut self, event: &TraceEvent) {
+            if event.pc == 2 {
+                self.depth_before_add = Some(event.stack_depth);
+            }
+        }
+    }
+
+    let code = vec![
+        Instr::PushInt(1),
+        Instr::PushInt(2),
+        Instr::Add,
+        Instr::Halt,
+    ];
+    let funcs = HashMap::new();
+    let mut sink: Vec<u8> = Vec::new();
+    let mut tracer = SnapshotTracer { depth_before_add: None };
+    let result = run_with_tracer(&code, &funcs, &[], &mut sink, &mut tracer);
+    assert!(result.is_ok());
+    assert_eq!(tracer.depth_before_add, Some(2));
 }
\ No newline at end of file
@@ -0,0 +1,66 @@
+//! # Instruction-level execution tracing
+//!
+//! An opt-in hook into `run_inner`'s fetch-decode-execute loop: a [`Tracer`]
+//! is invoked once per dispatched instruction, just before it executes, with
+//! a [`TraceEvent`] snapshot of the machine state at that point. Every
+//! ordinary `run_*` entry point passes `None` for the tracer, so the hot
+//! loop's only added cost there is a single `Option::as_deref_mut()` check;
+//! [`super::run_with_tracer`] is the one entry point that actually drives
+//! one.
+//!
+//! This exists to let a caller reconstruct the exact instruction sequence
+//! leading to a `VmInvariant`/`Raised` without recompiling with ad hoc
+//! `eprintln!`s — a step-by-step narrative a test can capture into a buffer
+//! via a `Tracer` impl instead of only ever seeing the final `Err`.
+
+use crate::bytecode::Instr;
+use crate::value::Value;
+
+/// Number of top-of-stack values a [`TraceEvent`] snapshots. Bounded rather
+/// than the whole stack, so tracing a deeply recursive program doesn't make
+/// each event itself cost O(stack depth).
+pub(super) const TRACE_STACK_SNAPSHOT: usize = 8;
+
+/// One instruction's worth of machine state, handed to [`Tracer::on_instr`]
+/// just before the instruction at `pc` executes.
+pub struct TraceEvent<'a> {
+    /// Program counter of the instruction about to execute.
+    pub pc: usize,
+    /// The instruction itself.
+    pub instr: &'a Instr,
+    /// Operand stack height before this instruction runs.
+    pub stack_depth: usize,
+    /// Up to the top [`TRACE_STACK_SNAPSHOT`] operand stack values, deepest
+    /// first (same order as the stack itself) — bounded so tracing a deep
+    /// stack doesn't copy the whole thing on every instruction.
+    pub stack_top: &'a [Value],
+    /// Number of active `SetupExcept` blocks (the exception-handler stack
+    /// depth) at this point.
+    pub block_depth: usize,
+}
+
+impl TraceEvent<'_> {
+    /// Render this event as one human-readable line: `pc`, the instruction's
+    /// mnemonic, stack depth, the bounded top-of-stack snapshot, and the
+    /// active handler-block depth. A convenience for `Tracer` impls that just
+    /// want a readable line instead of hand-formatting each field themselves.
+    pub fn to_line(&self) -> String {
+        let top: Vec<String> = self.stack_top.iter().map(Value::to_string).collect();
+        format!(
+            "{:04} {:<14} stack={} top=[{}] blocks={}",
+            self.pc,
+            self.instr.name(),
+            self.stack_depth,
+            top.join(", "),
+            self.block_depth,
+        )
+    }
+}
+
+/// Sink for per-instruction trace events (see the module docs). Implement
+/// this to capture a run's instruction sequence — into a test buffer, a log
+/// file, stderr, wherever — and drive the VM with [`super::run_with_tracer`].
+pub trait Tracer {
+    /// Called once per dispatched instruction, before it executes.
+    fn on_instr(&mut self, event: &TraceEvent);
+}